@@ -0,0 +1,158 @@
+//! Decompression for the IMA ADPCM variant used on WAVE files, the `0x40`
+//! (mono) and `0x80` (stereo) compression bits.
+//!
+//! A stream is a zero byte, the bit shift the encoder used, a 16-bit
+//! starting sample per channel and then one byte per sample, alternating
+//! between channels.  Output is 16-bit little endian samples.
+
+use std::cmp;
+
+use byteorder::{ReadBytesExt, WriteBytesExt, LittleEndian};
+
+const INITIAL_STEP_INDEX: usize = 0x2C;
+const MAX_STEP_INDEX: usize = 88;
+
+/// Bytes with this bit set are commands rather than samples, picked by
+/// the low seven bits.
+const COMMAND: u8 = 0x80;
+/// Repeats the last sample and lowers the step size.
+const REPEAT_SAMPLE: u8 = 0x00;
+/// Raises the step size without producing a sample.
+const RAISE_STEP: u8 = 0x01;
+/// Does nothing, but still moves on to the next channel.
+const SKIP_SAMPLE: u8 = 0x02;
+
+static NEXT_STEP: [i8; 32] = [
+    -1, 0, -1, 4, -1, 2, -1, 6, -1, 1, -1, 5, -1, 3, -1, 7,
+    -1, 1, -1, 5, -1, 3, -1, 7, -1, 2, -1, 4, -1, 6, -1, 8,
+];
+
+static STEP_SIZE: [i32; MAX_STEP_INDEX + 1] = [
+    7, 8, 9, 10, 11, 12, 13, 14, 16, 17, 19, 21, 23, 25, 28, 31,
+    34, 37, 41, 45, 50, 55, 60, 66, 73, 80, 88, 97, 107, 118, 130, 143,
+    157, 173, 190, 209, 230, 253, 279, 307, 337, 371, 408, 449, 494, 544, 598, 658,
+    724, 796, 876, 963, 1060, 1166, 1282, 1411, 1552, 1707, 1878, 2066, 2272, 2499, 2749, 3024,
+    3327, 3660, 4026, 4428, 4871, 5358, 5894, 6484, 7132, 7845, 8630, 9493, 10442, 11487, 12635, 13899,
+    15289, 16818, 18500, 20350, 22385, 24623, 27086, 29794, 32767,
+];
+
+/// Decompresses `channels` interleaved channels into `output`, returning
+/// the number of bytes written, or `None` if the stream is too short to
+/// hold its header.
+pub fn decompress(input: &[u8], channels: usize, output: &mut Vec<u8>) -> Option<usize> {
+    if input.len() < 2 + 2 * channels {
+        return None;
+    }
+    let shift = input[1] as u32;
+    let start = output.len();
+
+    let mut rdr = &input[2..];
+    let mut samples = [0i32; 2];
+    let mut steps = [INITIAL_STEP_INDEX; 2];
+    for channel in 0..channels {
+        let sample = rdr.read_i16::<LittleEndian>().unwrap();
+        samples[channel] = sample as i32;
+        output.write_i16::<LittleEndian>(sample).unwrap();
+    }
+
+    let mut channel = channels - 1;
+    for &encoded in rdr.iter() {
+        channel = (channel + 1) % channels;
+        if encoded & COMMAND == 0 {
+            let step = STEP_SIZE[steps[channel]];
+            let mut difference = step.checked_shr(shift).unwrap_or(0);
+            for bit in 0..6 {
+                if encoded & (1 << bit) != 0 {
+                    difference += step >> bit;
+                }
+            }
+            let sample = if encoded & 0x40 != 0 {
+                cmp::max(samples[channel] - difference, -32768)
+            } else {
+                cmp::min(samples[channel] + difference, 32767)
+            };
+            samples[channel] = sample;
+            output.write_i16::<LittleEndian>(sample as i16).unwrap();
+
+            let next = steps[channel] as i32 + NEXT_STEP[(encoded & 0x1F) as usize] as i32;
+            steps[channel] = cmp::max(0, cmp::min(next, MAX_STEP_INDEX as i32)) as usize;
+            continue;
+        }
+        match encoded & !COMMAND {
+            REPEAT_SAMPLE => {
+                steps[channel] = steps[channel].saturating_sub(1);
+                output.write_i16::<LittleEndian>(samples[channel] as i16).unwrap();
+            },
+            RAISE_STEP => {
+                steps[channel] = cmp::min(steps[channel] + 8, MAX_STEP_INDEX);
+                // the next byte is for the same channel
+                channel = (channel + channels - 1) % channels;
+            },
+            SKIP_SAMPLE => {},
+            _ => {
+                // no sample, and the next byte is for the same channel
+                channel = (channel + channels - 1) % channels;
+            },
+        }
+    }
+    Some(output.len() - start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::decompress;
+    use std::io::Cursor;
+    use byteorder::{ReadBytesExt, LittleEndian};
+
+    fn samples(buf: &[u8]) -> Vec<i16> {
+        let mut rdr = Cursor::new(buf);
+        (0..buf.len() / 2).map(|_| rdr.read_i16::<LittleEndian>().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_mono() {
+        // the step size starts at 494, and the bit shift of 1 adds 247 to
+        // every difference
+        let input = [0x00, 0x01, 0xE8, 0x03, 0x01, 0x41, 0x80, 0x81, 0x00];
+        let mut output = Vec::new();
+        assert_eq!(decompress(&input, 1, &mut output), Some(10));
+        // 0x80 lowers the step index to 43 and 0x81 raises it to 51: 963
+        assert_eq!(samples(&output), vec![1000, 1741, 1000, 1000, 1481]);
+    }
+
+    #[test]
+    fn test_stereo() {
+        let input = [0x00, 0x01, 0x64, 0x00, 0x9C, 0xFF, 0x00, 0x40, 0x81, 0x00, 0x80];
+        let mut output = Vec::new();
+        assert_eq!(decompress(&input, 2, &mut output), Some(12));
+        // the left channel goes up, the right down; 0x81 raises the left
+        // step size to 963 and the byte after it is for the left channel
+        assert_eq!(samples(&output), vec![100, -100, 347, -347, 828, -347]);
+    }
+
+    #[test]
+    fn test_other_commands() {
+        // 0x82 skips the left channel without a sample; 0x85 and 0xFF
+        // produce nothing and leave the next byte for the right channel,
+        // which goes up, and then the left goes down
+        let input = [0x00, 0x01, 0x64, 0x00, 0x9C, 0xFF, 0x82, 0x85, 0xFF, 0x00, 0x40];
+        let mut output = Vec::new();
+        assert_eq!(decompress(&input, 2, &mut output), Some(8));
+        assert_eq!(samples(&output), vec![100, -100, 147, -147]);
+    }
+
+    #[test]
+    fn test_clamping() {
+        let input = [0x00, 0x00, 0x00, 0x80, 0x7F, 0x3F];
+        let mut output = Vec::new();
+        decompress(&input, 1, &mut output);
+        assert_eq!(samples(&output), vec![-32768, -32768, -29622]);
+    }
+
+    #[test]
+    fn test_short() {
+        let mut output = Vec::new();
+        assert_eq!(decompress(&[0x00, 0x01, 0xE8], 1, &mut output), None);
+        assert_eq!(decompress(&[0x00, 0x01, 0xE8, 0x03], 2, &mut output), None);
+    }
+}
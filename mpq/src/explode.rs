@@ -0,0 +1,290 @@
+//! Decompression for PKWARE Data Compression Library "implode" streams,
+//! used by `MPQ_FILE_IMPLODE` files and the `0x08` compression bit.
+//!
+//! The Huffman codes are canonical and stored as runs of code lengths: the
+//! high nibble of each byte is the run length less one and the low nibble
+//! the code length.
+
+// literal codes, when the stream codes its literals
+static LITERAL_LENGTHS: &'static [u8] = &[
+    11, 124, 8, 7, 28, 7, 188, 13, 76, 4, 10, 8, 12, 10, 12, 10, 8, 23, 8,
+    9, 7, 6, 7, 8, 7, 6, 55, 8, 23, 24, 12, 11, 7, 9, 11, 12, 6, 7, 22, 5,
+    7, 24, 6, 11, 9, 6, 7, 22, 7, 11, 38, 7, 9, 8, 25, 11, 8, 11, 9, 12,
+    8, 12, 5, 38, 5, 38, 5, 11, 7, 5, 6, 21, 6, 10, 53, 8, 7, 24, 10, 27,
+    44, 253, 253, 253, 252, 252, 252, 13, 12, 45, 12, 45, 12, 61, 12, 45,
+    44, 173,
+];
+static LENGTH_LENGTHS: &'static [u8] = &[2, 35, 36, 53, 38, 23];
+static DISTANCE_LENGTHS: &'static [u8] = &[2, 20, 53, 230, 247, 151, 248];
+
+// copy lengths by length symbol
+static LENGTH_BASE: [u16; 16] = [3, 2, 4, 5, 6, 7, 8, 9, 10, 12, 16, 24, 40, 72, 136, 264];
+static LENGTH_EXTRA: [u8; 16] = [0, 0, 0, 0, 0, 0, 0, 0, 1, 2, 3, 4, 5, 6, 7, 8];
+
+/// The copy length that ends the stream.
+const END_LENGTH: usize = 519;
+const MAX_CODE_LENGTH: usize = 13;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The stream ended before its end code.
+    Truncated,
+    BadLiteralMode,
+    BadDictionarySize,
+    /// A copy reaches back before the start of the output.
+    BadDistance,
+    BadCode,
+}
+
+struct Huffman {
+    // codes of each length
+    count: [u16; MAX_CODE_LENGTH + 1],
+    // symbols ordered by code
+    symbol: Vec<u16>,
+}
+
+impl Huffman {
+    fn from_runs(runs: &[u8]) -> Huffman {
+        let mut lengths = Vec::new();
+        for &run in runs.iter() {
+            for _ in 0..(run >> 4) + 1 {
+                lengths.push(run & 15);
+            }
+        }
+
+        let mut count = [0; MAX_CODE_LENGTH + 1];
+        for &length in lengths.iter() {
+            count[length as usize] += 1;
+        }
+        let mut offsets = [0; MAX_CODE_LENGTH + 1];
+        for length in 1..MAX_CODE_LENGTH {
+            offsets[length + 1] = offsets[length] + count[length];
+        }
+        let mut symbol = vec![0; lengths.len()];
+        for (sym, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbol[offsets[length as usize] as usize] = sym as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Huffman {
+            count: count,
+            symbol: symbol,
+        }
+    }
+}
+
+struct BitReader<'a> {
+    input: &'a [u8],
+    position: usize,
+    buffer: u32,
+    available: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(input: &'a [u8]) -> BitReader<'a> {
+        BitReader {
+            input: input,
+            position: 0,
+            buffer: 0,
+            available: 0,
+        }
+    }
+
+    /// The next `need` bits, least significant first.
+    fn bits(&mut self, need: u32) -> Result<u32, Error> {
+        while self.available < need {
+            let byte = match self.input.get(self.position) {
+                Some(&byte) => byte,
+                None => return Err(Error::Truncated),
+            };
+            self.position += 1;
+            self.buffer |= (byte as u32) << self.available;
+            self.available += 8;
+        }
+        let value = self.buffer & ((1 << need) - 1);
+        self.buffer >>= need;
+        self.available -= need;
+        Ok(value)
+    }
+
+    /// The next symbol of `code`.  Codes are stored with their bits
+    /// inverted.
+    fn decode(&mut self, code: &Huffman) -> Result<u16, Error> {
+        let (mut value, mut first, mut index) = (0, 0, 0);
+        for length in 1..MAX_CODE_LENGTH + 1 {
+            value |= try!(self.bits(1)) ^ 1;
+            let count = code.count[length] as u32;
+            if value < first + count {
+                return Ok(code.symbol[(index + value - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            value <<= 1;
+        }
+        Err(Error::BadCode)
+    }
+}
+
+/// Decompresses an imploded stream into `output`, returning the number of
+/// bytes written.
+pub fn explode(input: &[u8], output: &mut Vec<u8>) -> Result<usize, Error> {
+    let literals = Huffman::from_runs(LITERAL_LENGTHS);
+    let lengths = Huffman::from_runs(LENGTH_LENGTHS);
+    let distances = Huffman::from_runs(DISTANCE_LENGTHS);
+
+    let mut rdr = BitReader::new(input);
+    let coded_literals = match try!(rdr.bits(8)) {
+        0 => false,
+        1 => true,
+        _ => return Err(Error::BadLiteralMode),
+    };
+    let dictionary_bits = try!(rdr.bits(8));
+    if dictionary_bits < 4 || 6 < dictionary_bits {
+        return Err(Error::BadDictionarySize);
+    }
+
+    let start = output.len();
+    loop {
+        if try!(rdr.bits(1)) == 0 {
+            let literal = if coded_literals {
+                try!(rdr.decode(&literals)) as u8
+            } else {
+                try!(rdr.bits(8)) as u8
+            };
+            output.push(literal);
+            continue;
+        }
+
+        let symbol = try!(rdr.decode(&lengths)) as usize;
+        let length = LENGTH_BASE[symbol] as usize + try!(rdr.bits(LENGTH_EXTRA[symbol] as u32)) as usize;
+        if length == END_LENGTH {
+            return Ok(output.len() - start);
+        }
+        let low_bits = if length == 2 { 2 } else { dictionary_bits };
+        let distance = ((try!(rdr.decode(&distances)) as usize) << low_bits)
+            + try!(rdr.bits(low_bits)) as usize + 1;
+        if output.len() - start < distance {
+            return Err(Error::BadDistance);
+        }
+        // byte by byte, as the copy may overlap what it writes
+        for _ in 0..length {
+            let byte = output[output.len() - distance];
+            output.push(byte);
+        }
+    }
+}
+
+#[cfg(test)]
+struct BitWriter {
+    output: Vec<u8>,
+    buffer: u32,
+    used: u32,
+}
+
+#[cfg(test)]
+impl BitWriter {
+    fn bits(&mut self, value: u32, count: u32) {
+        for bit in 0..count {
+            self.buffer |= ((value >> bit) & 1) << self.used;
+            self.used += 1;
+            if self.used == 8 {
+                self.output.push(self.buffer as u8);
+                self.buffer = 0;
+                self.used = 0;
+            }
+        }
+    }
+
+    /// Writes `symbol`'s code, most significant bit first and inverted.
+    fn code(&mut self, code: &Huffman, symbol: u16) {
+        let (mut value, mut index) = (0, 0);
+        for length in 1..code.count.len() {
+            for _ in 0..code.count[length] {
+                if code.symbol[index] == symbol {
+                    for bit in (0..length).rev() {
+                        self.bits(((value >> bit) & 1) ^ 1, 1);
+                    }
+                    return;
+                }
+                value += 1;
+                index += 1;
+            }
+            value <<= 1;
+        }
+        panic!("no code for {}", symbol);
+    }
+}
+
+/// `data` imploded as coded literals only, for tests.
+#[cfg(test)]
+pub fn implode_literals(data: &[u8]) -> Vec<u8> {
+    let literals = Huffman::from_runs(LITERAL_LENGTHS);
+    let lengths = Huffman::from_runs(LENGTH_LENGTHS);
+    let mut writer = BitWriter { output: Vec::new(), buffer: 0, used: 0 };
+    writer.bits(1, 8);
+    writer.bits(4, 8);
+    for &byte in data.iter() {
+        writer.bits(0, 1);
+        writer.code(&literals, byte as u16);
+    }
+    // the end code: the longest length symbol with every extra bit set
+    writer.bits(1, 1);
+    writer.code(&lengths, 15);
+    writer.bits(0xFF, 8);
+    writer.bits(0, 7);
+    writer.output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{explode, implode_literals, BitWriter, Error, Huffman, LITERAL_LENGTHS, LENGTH_LENGTHS, DISTANCE_LENGTHS};
+
+    #[test]
+    fn test_complete_codes() {
+        for &(runs, symbols) in [(LITERAL_LENGTHS, 256), (LENGTH_LENGTHS, 16), (DISTANCE_LENGTHS, 64)].iter() {
+            let code = Huffman::from_runs(runs);
+            assert_eq!(code.symbol.len(), symbols);
+            // every bit pattern decodes to something
+            let mut left = 1i32;
+            for length in 1..code.count.len() {
+                left = (left << 1) - code.count[length] as i32;
+                assert!(left >= 0);
+            }
+            assert_eq!(left, 0);
+        }
+    }
+
+    #[test]
+    fn test_explode_copies() {
+        let mut output = Vec::new();
+        assert_eq!(explode(b"\x00\x04\x82\x24\x25\x8f\x80\x7f", &mut output), Ok(13));
+        assert_eq!(&output[..], b"AIAIAIAIAIAIA");
+    }
+
+    #[test]
+    fn test_explode_coded_literals() {
+        let data: Vec<u8> = (0..600).map(|x| (x * 7) as u8).collect();
+        let mut output = vec![b'x'];
+        assert_eq!(explode(&implode_literals(&data), &mut output), Ok(600));
+        assert_eq!(&output[1..], &data[..]);
+    }
+
+    #[test]
+    fn test_explode_errors() {
+        let mut output = Vec::new();
+        assert_eq!(explode(b"\x02\x04", &mut output), Err(Error::BadLiteralMode));
+        assert_eq!(explode(b"\x00\x07", &mut output), Err(Error::BadDictionarySize));
+        assert_eq!(explode(b"\x00\x04\x82\x24\x25", &mut output), Err(Error::Truncated));
+
+        // a copy before anything has been written
+        let mut writer = BitWriter { output: Vec::new(), buffer: 0, used: 0 };
+        writer.bits(0, 8);
+        writer.bits(4, 8);
+        writer.bits(1, 1);
+        writer.code(&Huffman::from_runs(LENGTH_LENGTHS), 0);
+        writer.code(&Huffman::from_runs(DISTANCE_LENGTHS), 0);
+        writer.bits(0, 12);
+        assert_eq!(explode(&writer.output, &mut output), Err(Error::BadDistance));
+    }
+}
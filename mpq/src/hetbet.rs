@@ -0,0 +1,294 @@
+//! The HET and BET tables of version 3 and 4 archives, which replace the
+//! hash and block tables.
+//!
+//! Files are looked up by the Jenkins hash of their name: the HET table
+//! holds the top byte of each hash, in slots probed from the hash modulo
+//! the table size, and the index of the file in the BET table, which holds
+//! the rest of the hash and the file's position, sizes and flags.  Both
+//! tables start with a 12-byte header of signature, version and data size,
+//! and are passed here decrypted and decompressed.
+
+use std::cmp;
+use std::io::{self, Cursor};
+
+use byteorder::{ReadBytesExt, LittleEndian};
+
+use super::BlockTableEntry;
+
+const HET_SIGNATURE: u32 = 0x1A544548;
+const BET_SIGNATURE: u32 = 0x1A544542;
+const HET_ENTRY_FREE: u8 = 0;
+
+pub struct HetTable {
+    hash_bits: u32,
+    // the top byte of each slot's name hash
+    name_hashes: Vec<u8>,
+    // the BET index of each slot
+    indexes: Vec<u64>,
+    // the rest of each file's name hash, by BET index
+    bet_hashes: Vec<u64>,
+}
+
+impl HetTable {
+    /// The index in the BET table of `filename`.
+    pub fn find(&self, filename: &[u8]) -> Option<usize> {
+        let mask = if self.hash_bits == 64 { !0 } else { (1 << self.hash_bits) - 1 };
+        let hash = (jenkins_hash(filename) & mask) | (1 << (self.hash_bits - 1));
+        let top = (hash >> (self.hash_bits - 8)) as u8;
+        let rest = hash & ((1 << (self.hash_bits - 8)) - 1);
+
+        let count = self.name_hashes.len();
+        let start = (hash % count as u64) as usize;
+        for slot in (start..count).chain(0..start) {
+            match self.name_hashes[slot] {
+                HET_ENTRY_FREE => return None,
+                byte if byte == top => {
+                    let index = self.indexes[slot] as usize;
+                    if self.bet_hashes.get(index) == Some(&rest) {
+                        return Some(index);
+                    }
+                },
+                _ => {},
+            }
+        }
+        None
+    }
+}
+
+/// The HET table and the BET table's files.  Errors name the table at
+/// fault.
+pub fn read_tables(het: &[u8], bet: &[u8]) -> Result<(HetTable, Vec<BlockTableEntry>), &'static str> {
+    let mut het_table = try!(read_het(het).map_err(|_| "HET table"));
+    let (files, bet_hashes) = try!(read_bet(bet).map_err(|_| "BET table"));
+    het_table.bet_hashes = bet_hashes;
+    Ok((het_table, files))
+}
+
+fn invalid(reason: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, reason)
+}
+
+fn read_het(data: &[u8]) -> io::Result<HetTable> {
+    let mut rdr = Cursor::new(data);
+    if try!(rdr.read_u32::<LittleEndian>()) != HET_SIGNATURE {
+        return Err(invalid("bad signature"));
+    }
+    // version, data size, table size and used slots
+    for _ in 0..4 {
+        try!(rdr.read_u32::<LittleEndian>());
+    }
+    let total_count = try!(rdr.read_u32::<LittleEndian>());
+    let hash_bits = try!(rdr.read_u32::<LittleEndian>());
+    let index_size_total = try!(rdr.read_u32::<LittleEndian>());
+    let _index_size_extra = try!(rdr.read_u32::<LittleEndian>());
+    let index_size = try!(rdr.read_u32::<LittleEndian>());
+    let index_table_size = try!(rdr.read_u32::<LittleEndian>());
+    if total_count == 0 || hash_bits < 9 || 64 < hash_bits || 32 < index_size {
+        return Err(invalid("bad header"));
+    }
+
+    let start = rdr.position() as usize;
+    let hashes_end = start + total_count as usize;
+    let table_end = hashes_end + index_table_size as usize;
+    if data.len() < table_end {
+        return Err(invalid("table truncated"));
+    }
+    let index_table = &data[hashes_end..table_end];
+    let mut indexes = Vec::with_capacity(total_count as usize);
+    for slot in 0..total_count as u64 {
+        indexes.push(try!(bits(index_table, slot * index_size_total as u64, index_size)));
+    }
+    Ok(HetTable {
+        hash_bits: hash_bits,
+        name_hashes: data[start..hashes_end].to_vec(),
+        indexes: indexes,
+        bet_hashes: Vec::new(),
+    })
+}
+
+fn read_bet(data: &[u8]) -> io::Result<(Vec<BlockTableEntry>, Vec<u64>)> {
+    let mut rdr = Cursor::new(data);
+    if try!(rdr.read_u32::<LittleEndian>()) != BET_SIGNATURE {
+        return Err(invalid("bad signature"));
+    }
+    // version, data size and table size
+    for _ in 0..3 {
+        try!(rdr.read_u32::<LittleEndian>());
+    }
+    let entry_count = try!(rdr.read_u32::<LittleEndian>());
+    let _unknown = try!(rdr.read_u32::<LittleEndian>());
+    let entry_size = try!(rdr.read_u32::<LittleEndian>());
+    // where each field of an entry starts, then its width in bits:
+    // position, size, archived size, flag index and one unused
+    let mut fields = [(0u64, 0u32); 5];
+    for field in fields.iter_mut() {
+        field.0 = try!(rdr.read_u32::<LittleEndian>()) as u64;
+    }
+    for field in fields.iter_mut() {
+        field.1 = try!(rdr.read_u32::<LittleEndian>());
+    }
+    let hash_size_total = try!(rdr.read_u32::<LittleEndian>());
+    let _hash_size_extra = try!(rdr.read_u32::<LittleEndian>());
+    let hash_size = try!(rdr.read_u32::<LittleEndian>());
+    let hash_array_size = try!(rdr.read_u32::<LittleEndian>());
+    let flag_count = try!(rdr.read_u32::<LittleEndian>());
+    if fields.iter().any(|&(_, width)| 64 < width) || 64 < hash_size || (entry_size == 0 && entry_count > 0) {
+        return Err(invalid("bad header"));
+    }
+
+    let mut flags = Vec::new();
+    for _ in 0..flag_count {
+        flags.push(try!(rdr.read_u32::<LittleEndian>()));
+    }
+    let start = rdr.position() as usize;
+    // non-empty entries bound the entry count by the table's length
+    let table_end = start + ((entry_count as u64 * entry_size as u64 + 7) / 8) as usize;
+    let hashes_end = table_end + hash_array_size as usize;
+    if data.len() < hashes_end {
+        return Err(invalid("table truncated"));
+    }
+    let table = &data[start..table_end];
+    let hash_array = &data[table_end..hashes_end];
+
+    let mut files = Vec::with_capacity(entry_count as usize);
+    let mut hashes = Vec::with_capacity(entry_count as usize);
+    for idx in 0..entry_count as u64 {
+        let entry = idx * entry_size as u64;
+        let mut values = [0; 4];
+        for (value, &(start, width)) in values.iter_mut().zip(fields.iter()) {
+            *value = try!(bits(table, entry + start, width));
+        }
+        let flags = if flag_count == 0 {
+            0
+        } else {
+            *try!(flags.get(values[3] as usize).ok_or(invalid("bad flag index")))
+        };
+        files.push(BlockTableEntry {
+            offset: values[0],
+            archived_size: values[2] as u32,
+            size: values[1] as u32,
+            flags: flags,
+        });
+        hashes.push(try!(bits(hash_array, idx * hash_size_total as u64, hash_size)));
+    }
+    Ok((files, hashes))
+}
+
+/// The `count` bits of `data` from bit `position`, least significant
+/// first.
+fn bits(data: &[u8], position: u64, count: u32) -> io::Result<u64> {
+    if (data.len() as u64) * 8 < position + count as u64 {
+        return Err(invalid("table truncated"));
+    }
+    let mut value = 0;
+    for bit in 0..count as u64 {
+        let at = position + bit;
+        value |= (((data[(at / 8) as usize] >> (at % 8)) & 1) as u64) << bit;
+    }
+    Ok(value)
+}
+
+/// Bob Jenkins' `hashlittle2` of the name, lowercased and with `/` as `\`,
+/// with the primary hash in the high half.
+pub fn jenkins_hash(filename: &[u8]) -> u64 {
+    let name: Vec<u8> = filename.iter().map(|&ch| match ch {
+        b'/' => b'\\',
+        b'A'...b'Z' => ch - b'A' + b'a',
+        _ => ch,
+    }).collect();
+    let (primary, secondary) = hashlittle2(&name, 1, 2);
+    ((primary as u64) << 32) | secondary as u64
+}
+
+/// `hashlittle2` from lookup3.c, returning `(b, c)`.
+fn hashlittle2(key: &[u8], pb: u32, pc: u32) -> (u32, u32) {
+    let init = 0xdeadbeefu32.wrapping_add(key.len() as u32).wrapping_add(pc);
+    let (mut a, mut b, mut c) = (init, init, init.wrapping_add(pb));
+
+    let word = |bytes: &[u8]| -> u32 {
+        bytes.iter().enumerate().fold(0, |word, (idx, &byte)| word | (byte as u32) << (8 * idx))
+    };
+    let mut rest = key;
+    while rest.len() > 12 {
+        a = a.wrapping_add(word(&rest[0..4]));
+        b = b.wrapping_add(word(&rest[4..8]));
+        c = c.wrapping_add(word(&rest[8..12]));
+        mix(&mut a, &mut b, &mut c);
+        rest = &rest[12..];
+    }
+    if rest.is_empty() {
+        return (b, c);
+    }
+    a = a.wrapping_add(word(&rest[..cmp::min(rest.len(), 4)]));
+    if rest.len() > 4 {
+        b = b.wrapping_add(word(&rest[4..cmp::min(rest.len(), 8)]));
+    }
+    if rest.len() > 8 {
+        c = c.wrapping_add(word(&rest[8..]));
+    }
+    final_mix(&mut a, &mut b, &mut c);
+    (b, c)
+}
+
+fn mix(a: &mut u32, b: &mut u32, c: &mut u32) {
+    *a = a.wrapping_sub(*c); *a ^= c.rotate_left(4); *c = c.wrapping_add(*b);
+    *b = b.wrapping_sub(*a); *b ^= a.rotate_left(6); *a = a.wrapping_add(*c);
+    *c = c.wrapping_sub(*b); *c ^= b.rotate_left(8); *b = b.wrapping_add(*a);
+    *a = a.wrapping_sub(*c); *a ^= c.rotate_left(16); *c = c.wrapping_add(*b);
+    *b = b.wrapping_sub(*a); *b ^= a.rotate_left(19); *a = a.wrapping_add(*c);
+    *c = c.wrapping_sub(*b); *c ^= b.rotate_left(4); *b = b.wrapping_add(*a);
+}
+
+fn final_mix(a: &mut u32, b: &mut u32, c: &mut u32) {
+    *c ^= *b; *c = c.wrapping_sub(b.rotate_left(14));
+    *a ^= *c; *a = a.wrapping_sub(c.rotate_left(11));
+    *b ^= *a; *b = b.wrapping_sub(a.rotate_left(25));
+    *c ^= *b; *c = c.wrapping_sub(b.rotate_left(16));
+    *a ^= *c; *a = a.wrapping_sub(c.rotate_left(4));
+    *b ^= *a; *b = b.wrapping_sub(a.rotate_left(14));
+    *c ^= *b; *c = c.wrapping_sub(b.rotate_left(24));
+}
+
+#[cfg(test)]
+mod tests {
+    use byteorder::{WriteBytesExt, LittleEndian};
+    use super::{bits, hashlittle2, jenkins_hash, read_bet, BET_SIGNATURE};
+
+    #[test]
+    fn test_hashlittle2() {
+        // the vectors in lookup3.c, as (b, c)
+        let key = b"Four score and seven years ago";
+        assert_eq!(hashlittle2(b"", 0, 0), (0xdeadbeef, 0xdeadbeef));
+        assert_eq!(hashlittle2(key, 0, 0), (0xce7226e6, 0x17770551));
+        assert_eq!(hashlittle2(key, 1, 0), (0xbd371de4, 0xe3607cae));
+        assert_eq!(hashlittle2(key, 0, 1), (0x6cbea4b3, 0xcd628161));
+    }
+
+    #[test]
+    fn test_jenkins_hash_normalizes() {
+        assert_eq!(jenkins_hash(b"Replay.Details"), jenkins_hash(b"replay.details"));
+        assert_eq!(jenkins_hash(b"Base/Map.xml"), jenkins_hash(b"base\\map.xml"));
+        assert!(jenkins_hash(b"replay.details") != jenkins_hash(b"replay.initData"));
+    }
+
+    #[test]
+    fn test_bits() {
+        let data = [0xB4, 0x5A, 0x01];
+        assert_eq!(bits(&data, 0, 8).unwrap(), 0xB4);
+        assert_eq!(bits(&data, 2, 4).unwrap(), 0xD);
+        assert_eq!(bits(&data, 4, 13).unwrap(), 0x15AB);
+        assert_eq!(bits(&data, 0, 0).unwrap(), 0);
+        assert!(bits(&data, 20, 5).is_err());
+    }
+
+    #[test]
+    fn test_bet_empty_entries() {
+        // a huge entry count with entries of no bits
+        let mut bet = Vec::new();
+        for &value in [BET_SIGNATURE, 1, 0, 0, 0xFFFFFFFF, 0x10, 0].iter() {
+            bet.write_u32::<LittleEndian>(value).unwrap();
+        }
+        bet.extend([0; 60].iter().cloned());
+        assert!(read_bet(&bet).is_err());
+    }
+}
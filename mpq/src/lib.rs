@@ -2,7 +2,11 @@
 
 extern crate byteorder;
 extern crate bzip2;
+extern crate flate2;
+extern crate xz2;
 
+use std::cmp;
+use std::fmt;
 use std::num::Wrapping;
 use std::io::{self, Read, Cursor, Seek, SeekFrom};
 use std::sync::{Once, ONCE_INIT};
@@ -10,8 +14,12 @@ use std::collections::HashMap;
 
 use byteorder::{ReadBytesExt, WriteBytesExt, BigEndian, LittleEndian};
 
+mod adpcm;
 mod enctable;
+mod explode;
+mod hetbet;
 use self::enctable::ENCRYPTION_TABLE;
+use self::hetbet::HetTable;
 
 static MPQ_FILE_IMPLODE: u32 = 0x00000100;
 static MPQ_FILE_COMPRESS: u32 = 0x00000200;
@@ -22,10 +30,74 @@ static MPQ_FILE_DELETE_MARKER: u32 = 0x02000000;
 static MPQ_FILE_SECTOR_CRC: u32 = 0x04000000;
 static MPQ_FILE_EXISTS: u32 = 0x80000000;
 
+// bits of the byte starting each compressed sector
+const MPQ_COMPRESSION_HUFFMAN: u8 = 0x01;
+const MPQ_COMPRESSION_ZLIB: u8 = 0x02;
+const MPQ_COMPRESSION_PKWARE: u8 = 0x08;
+const MPQ_COMPRESSION_BZIP2: u8 = 0x10;
+const MPQ_COMPRESSION_SPARSE: u8 = 0x20;
+const MPQ_COMPRESSION_ADPCM_MONO: u8 = 0x40;
+const MPQ_COMPRESSION_ADPCM_STEREO: u8 = 0x80;
+// not a bit: LZMA is never combined with anything
+const MPQ_COMPRESSION_LZMA: u8 = 0x12;
+
+// the size of each header version, by format version
+static MPQ_HEADER_SIZES: [u32; 4] = [32, 44, 68, 208];
+
+// block indexes of hash table slots that hold no file: empty slots end a
+// lookup, deleted ones don't
+const MPQ_HASH_ENTRY_EMPTY: u32 = 0xFFFFFFFF;
+const MPQ_HASH_ENTRY_DELETED: u32 = 0xFFFFFFFE;
+
+/// The locale of files that aren't localized.
+pub const LOCALE_NEUTRAL: u16 = 0;
+
+// files archives keep about themselves, which listfiles leave out
+static SPECIAL_FILES: &'static [&'static [u8]] = &[b"(listfile)", b"(attributes)", b"(signature)"];
+
 const MPQ_HEADER_FILE_MAGIC: u32 = 0x4d50511a;
 const MPQ_HEADER_USER_DATA_MAGIC: u32 = 0x4d50511b;
 
-fn read_header<R: Read>(rdr: &mut R) -> io::Result<Header> {
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// The archive starts with neither the archive nor the user data magic.
+    BadMagic(u32),
+    InvalidHeader(&'static str),
+    /// A hash or block table that runs past the end of the archive.
+    InvalidTable(&'static str),
+    FileNotFound,
+    /// A file whose data can't be read back.
+    InvalidFile(&'static str),
+    /// A file compressed with the mask given, which includes Huffman
+    /// coding.  It isn't supported, and neither are the ADPCM masks, which
+    /// the game only uses together with it.
+    UnsupportedCompression(u8),
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref e) => write!(f, "{}", e),
+            Error::BadMagic(magic) => write!(f, "bad magic {:08x}", magic),
+            Error::InvalidHeader(field) => write!(f, "invalid header: {}", field),
+            Error::InvalidTable(table) => write!(f, "invalid {}", table),
+            Error::FileNotFound => write!(f, "file not found"),
+            Error::InvalidFile(reason) => write!(f, "invalid file: {}", reason),
+            Error::UnsupportedCompression(mask) => write!(f, "unsupported compression {:02x}", mask),
+        }
+    }
+}
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+fn read_header<R: Read>(rdr: &mut R) -> Result<Header> {
     let magic = try!(rdr.read_u32::<BigEndian>());
     Ok(match magic {
         MPQ_HEADER_FILE_MAGIC => {
@@ -36,104 +108,314 @@ fn read_header<R: Read>(rdr: &mut R) -> io::Result<Header> {
             let header = try!(UserDataHeader::from_reader_nomagic(rdr));
             Header::UserData(header)
         },
-        _ => panic!("bad magic")  // FIXME
+        _ => return Err(Error::BadMagic(magic)),
     })
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Stored,
+    /// `MPQ_FILE_IMPLODE`, with the whole file or each sector imploded.
+    Imploded,
+    /// `MPQ_FILE_COMPRESS`, with each sector starting with a mask of the
+    /// compressions applied to it.
+    Compressed,
+}
+
+/// A file in an archive, as `Archive::entries` lists it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    /// `None` for files no listfile names.
+    pub name: Option<Vec<u8>>,
+    pub block_index: usize,
+    /// Where the file's data starts, from the start of the archive header.
+    pub offset: u64,
+    pub archived_size: u32,
+    pub size: u32,
+    pub flags: u32,
+    pub compression: Compression,
+}
+
+impl Entry {
+    pub fn is_encrypted(&self) -> bool {
+        (self.flags & MPQ_FILE_ENCRYPTED) > 0
+    }
+}
+
 pub struct Archive<R> where R: Read+Seek {
     header_offset: u32,
     header: FileHeader,
-    hash_table: HashMap<HashTableKey, HashTableValue>,
+    user_data: Option<Vec<u8>>,
+    // by slot
+    hash_table: Vec<(HashTableKey, HashTableValue)>,
+    // looks files up in archives without a hash table
+    het_table: Option<HetTable>,
     block_table: Vec<BlockTableEntry>,
     file: R,
 }
 
 impl<R> Archive<R> where R: Read+Seek {
-    pub fn load(mut file: R) -> io::Result<Archive<R>> where R: Read+Seek {
-        let (header_off, header) = match read_header(&mut file).unwrap() {
-            Header::File(header) => (0, header),
+    pub fn load(mut file: R) -> Result<Archive<R>> where R: Read+Seek {
+        let file_len = try!(file.seek(SeekFrom::End(0)));
+        try!(file.seek(SeekFrom::Start(0)));
+        let (header_off, header, user_data) = match try!(read_header(&mut file)) {
+            Header::File(header) => (0, header, None),
             Header::UserData(user_header) => {
+                if file_len <= user_header.mpq_header_offset as u64 {
+                    return Err(Error::InvalidHeader("mpq_header_offset"));
+                }
+                // the contents follow the user data header
+                let mut user_data = Vec::new();
+                let size = user_header.user_data_header_size;
+                try!((&mut file).take(size as u64).read_to_end(&mut user_data));
+                if user_data.len() != size as usize || user_header.user_data_size < size {
+                    return Err(Error::InvalidHeader("user_data_header_size"));
+                }
                 try!(file.seek(SeekFrom::Start(user_header.mpq_header_offset as u64)));
                 let header = try!(FileHeader::from_reader(&mut file));
-                (user_header.mpq_header_offset, header)
+                (user_header.mpq_header_offset, header, Some(user_data))
             }
         };
-        let hash_table = try!(read_hash_table(&mut file, &header, header_off));
-        let block_table = try!(read_block_table(&mut file, &header, header_off));
+        if 16 < header.sector_size_shift {
+            return Err(Error::InvalidHeader("sector_size_shift"));
+        }
+        let hash_table = try!(read_hash_table(&mut file, &header, header_off, file_len));
+        let mut block_table = try!(read_block_table(&mut file, &header, header_off, file_len));
+        let het_table = match try!(read_het_bet_tables(&mut file, &header, header_off, file_len)) {
+            Some((het_table, files)) => {
+                // the BET table describes the same files as the block
+                // table, which only archives without one lack
+                if block_table.is_empty() {
+                    block_table = files;
+                }
+                Some(het_table)
+            },
+            None => None,
+        };
         Ok(Archive {
             header_offset: header_off,
             header: header,
+            user_data: user_data,
             hash_table: hash_table,
+            het_table: het_table,
             block_table: block_table,
             file: file,
         })
     }
 
-    pub fn read_file(&mut self, filename: &[u8], into: &mut Vec<u8>) -> io::Result<usize> {
-        let hash_a = string_hash(ENCRYPTION_TABLE, filename, StringHashType::HashA);
-        let hash_b = string_hash(ENCRYPTION_TABLE, filename, StringHashType::HashB);
-        let hash_entry = try!(self.hash_table
-            .get(&(hash_a, hash_b))
-            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "entry not found")));
-        let block_entry = try!(self.block_table
-            .get(hash_entry.block_table_index as usize)
-            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "entry not found")));
+    /// The contents of the user data block ahead of the archive, which in
+    /// StarCraft II replays is the replay header.  `None` for archives
+    /// without one.
+    pub fn user_data(&self) -> Option<&[u8]> {
+        self.user_data.as_ref().map(|data| &data[..])
+    }
+
+    pub fn read_file(&mut self, filename: &[u8], into: &mut Vec<u8>) -> Result<usize> {
+        self.read_file_with_locale(filename, LOCALE_NEUTRAL, into)
+    }
+
+    /// Every file in the archive, named from the archive's `(listfile)` if
+    /// it has one that can be read.  Files it doesn't name are still listed,
+    /// without a name.
+    pub fn entries(&mut self) -> Result<Vec<Entry>> {
+        self.entries_with_listfile(b"")
+    }
+
+    /// `entries`, also naming the files `listfile` lists, for archives
+    /// whose own listfile is missing or incomplete.  Names are separated by
+    /// line breaks or semicolons.
+    pub fn entries_with_listfile(&mut self, listfile: &[u8]) -> Result<Vec<Entry>> {
+        // a listfile that can't be read only leaves files unnamed
+        let mut internal = Vec::new();
+        if self.read_file(b"(listfile)", &mut internal).is_err() {
+            internal.clear();
+        }
+
+        let mut names = HashMap::new();
+        let listed = SPECIAL_FILES.iter().cloned()
+            .chain(listfile_names(&internal).into_iter())
+            .chain(listfile_names(listfile).into_iter());
+        for name in listed {
+            for idx in self.block_indexes(name) {
+                names.entry(idx).or_insert_with(|| name.to_vec());
+            }
+        }
+
+        let mut entries = Vec::new();
+        for (idx, block_entry) in self.block_table.iter().enumerate() {
+            if (block_entry.flags & MPQ_FILE_EXISTS) == 0 {
+                continue;
+            }
+            let compression = if (block_entry.flags & MPQ_FILE_IMPLODE) > 0 {
+                Compression::Imploded
+            } else if (block_entry.flags & MPQ_FILE_COMPRESS) > 0 {
+                Compression::Compressed
+            } else {
+                Compression::Stored
+            };
+            entries.push(Entry {
+                name: names.remove(&idx),
+                block_index: idx,
+                offset: block_entry.offset,
+                archived_size: block_entry.archived_size,
+                size: block_entry.size,
+                flags: block_entry.flags,
+                compression: compression,
+            });
+        }
+        Ok(entries)
+    }
+
+    /// The blocks of every version of `filename`, from the HET table if
+    /// the archive has one that knows the file, else from the hash table.
+    fn block_indexes(&self, filename: &[u8]) -> Vec<usize> {
+        if let Some(idx) = self.het_table.as_ref().and_then(|het_table| het_table.find(filename)) {
+            return vec![idx];
+        }
+        hash_entries(&self.hash_table, filename).into_iter()
+            .map(|hash_entry| hash_entry.block_table_index as usize)
+            .collect()
+    }
+
+    /// Reads the version of `filename` in `locale`, or the neutral version
+    /// if there is none, or else the first version there is.  The HET table
+    /// is used whenever it has the file; only the hash table records
+    /// locales, so files found there have one version.
+    pub fn read_file_with_locale(&mut self, filename: &[u8], locale: u16, into: &mut Vec<u8>) -> Result<usize> {
+        let block_index = self.het_table.as_ref()
+            .and_then(|het_table| het_table.find(filename))
+            .or_else(|| {
+                find_hash_entry(&self.hash_table, filename, locale)
+                    .map(|hash_entry| hash_entry.block_table_index as usize)
+            });
+        let block_table = &self.block_table;
+        let block_entry = try!(block_index
+            .and_then(|idx| block_table.get(idx))
+            .ok_or(Error::FileNotFound));
 
         let mut file_data = Vec::new();
-        let data_offset = (self.header_offset + block_entry.offset) as u64;
+        let data_offset = self.header_offset as u64 + block_entry.offset;
         try!(self.file.seek(SeekFrom::Start(data_offset)));
         {
             let mut data_reader = self.file.by_ref().take(block_entry.archived_size as u64);
             let bytes_read = try!(data_reader.read_to_end(&mut file_data));
             if bytes_read < block_entry.archived_size as usize {
-                return Err(io::Error::new(io::ErrorKind::Other, "file truncated"));
+                return Err(Error::InvalidFile("file truncated"));
             }
         }
 
         if (block_entry.flags & MPQ_FILE_EXISTS) == 0 {
-            return Err(io::Error::new(io::ErrorKind::NotFound, "file not found"));
-        }
-        if (block_entry.flags & MPQ_FILE_ENCRYPTED) > 0 {
-            return Err(io::Error::new(io::ErrorKind::Other, "encrypted files not supported"));
+            return Err(Error::FileNotFound);
         }
         if block_entry.archived_size == 0 {
             return Ok(0);
         }
+        let key = if (block_entry.flags & MPQ_FILE_ENCRYPTED) > 0 {
+            Some(file_key(filename, block_entry))
+        } else {
+            None
+        };
 
-        let has_crc = (block_entry.flags & MPQ_FILE_SECTOR_CRC) > 0;
         if (block_entry.flags & MPQ_FILE_SINGLE_UNIT) == 0 {
-            // multi-unit file
-            let sector_size = 512 << self.header.sector_size_shift;
-
-            let mut sectors = block_entry.size / sector_size + 1;
-            if has_crc {
-                sectors += 1;
-            }
-            let mut positions = Vec::new();
-            for _ in 0..sectors {
-                positions.push(try!(self.file.read_u32::<LittleEndian>()));
-            }
-
-            // unimplemented;
-            return Err(io::Error::new(io::ErrorKind::Other, "multi-unit files not supported yet"));
+            read_sectors(&file_data, block_entry, self.header.sector_size_shift, key, into)
         } else {
+            if let Some(key) = key {
+                decrypt(ENCRYPTION_TABLE, key, &mut file_data);
+            }
             let is_compressed = {
-                ((block_entry.flags & MPQ_FILE_COMPRESS) > 0) &&
+                ((block_entry.flags & (MPQ_FILE_COMPRESS | MPQ_FILE_IMPLODE)) > 0) &&
                 block_entry.size > block_entry.archived_size
             };
-            return if is_compressed {
-                decompress(&file_data, into).map_err(|_| {
-                    io::Error::new(io::ErrorKind::Other, "error decompressing file")
+            if is_compressed {
+                decompress_block(block_entry.flags, &file_data, into).map_err(|_| {
+                    decompress_error(block_entry.flags, &file_data, "error decompressing file")
                 })
             } else {
                 let length = file_data.len();
                 into.extend(file_data.into_iter());
                 Ok(length)
-            }   
+            }
+        }
+    }
+}
+
+/// Reads a file stored as sectors of `512 << sector_size_shift` bytes from
+/// its archived data.  Compressed files start with a table of sector
+/// offsets, relative to the start of the file, with one entry more than
+/// there are sectors so the last sector has an end; files with
+/// `MPQ_FILE_SECTOR_CRC` carry one more entry, for the checksums after the
+/// last sector, which are not checked.  A sector no shorter than its
+/// unpacked size is stored as is.  Imploded files are laid out the same way.
+///
+/// Encrypted files have sector `n` encrypted with `key + n` and the offset
+/// table with `key - 1`.
+fn read_sectors(
+    file_data: &[u8],
+    block_entry: &BlockTableEntry,
+    sector_size_shift: u16,
+    key: Option<u32>,
+    into: &mut Vec<u8>,
+) -> Result<usize> {
+    let size = block_entry.size as usize;
+    let sector_size = 512usize << sector_size_shift;
+    let is_compressed = (block_entry.flags & (MPQ_FILE_COMPRESS | MPQ_FILE_IMPLODE)) > 0;
+    if !is_compressed {
+        if file_data.len() < size {
+            return Err(Error::InvalidFile("file truncated"));
+        }
+        for (idx, sector) in file_data[..size].chunks(sector_size).enumerate() {
+            let mut sector = sector.to_vec();
+            if let Some(key) = key {
+                decrypt(ENCRYPTION_TABLE, key.wrapping_add(idx as u32), &mut sector);
+            }
+            into.extend(sector.into_iter());
+        }
+        return Ok(size);
+    }
+
+    let sectors = (size + sector_size - 1) / sector_size;
+    let has_crc = (block_entry.flags & MPQ_FILE_SECTOR_CRC) > 0;
+    let entries = if has_crc { sectors + 2 } else { sectors + 1 };
+    if file_data.len() < 4 * entries {
+        return Err(Error::InvalidFile("sector table truncated"));
+    }
+    let mut table = file_data[..4 * entries].to_vec();
+    if let Some(key) = key {
+        decrypt(ENCRYPTION_TABLE, key.wrapping_sub(1), &mut table);
+    }
+    let mut positions = Vec::with_capacity(entries);
+    {
+        let mut table_rdr = Cursor::new(&table[..]);
+        for _ in 0..entries {
+            positions.push(try!(table_rdr.read_u32::<LittleEndian>()) as usize);
         }
+    }
 
-        unimplemented!()
+    let mut length = 0;
+    for idx in 0..sectors {
+        let (start, end) = (positions[idx], positions[idx + 1]);
+        if end < start || file_data.len() < end {
+            return Err(Error::InvalidFile("bad sector offset"));
+        }
+        let mut sector = file_data[start..end].to_vec();
+        if let Some(key) = key {
+            decrypt(ENCRYPTION_TABLE, key.wrapping_add(idx as u32), &mut sector);
+        }
+        let expected = cmp::min(sector_size, size - idx * sector_size);
+        let unpacked = if sector.len() < expected {
+            try!(decompress_block(block_entry.flags, &sector, into).map_err(|_| {
+                decompress_error(block_entry.flags, &sector, "error decompressing sector")
+            }))
+        } else {
+            into.extend(sector[..expected].iter().cloned());
+            expected
+        };
+        if unpacked != expected {
+            return Err(Error::InvalidFile("sector size mismatch"));
+        }
+        length += unpacked;
     }
+    Ok(length)
 }
 
 enum Header {
@@ -142,17 +424,31 @@ enum Header {
 }
 
 
+/// An archive header of any version, with the fields of the versions
+/// after the first defaulted from the ones before them.
 #[repr(C)]
 struct FileHeader {
     magic: [u8; 4],
     header_size: u32,
-    archive_size: u32,
+    archive_size: u64,
     format_version: u16,
     sector_size_shift: u16,
-    hash_table_offset: u32,
-    block_table_offset: u32,
+    hash_table_offset: u64,
+    block_table_offset: u64,
     hash_table_entries: u32,
     block_table_entries: u32,
+    // version 2: the high 16 bits of each file's offset
+    hi_block_table_offset: u64,
+    // version 3
+    het_table_offset: u64,
+    bet_table_offset: u64,
+    // version 4: the tables' sizes in the archive, which are smaller than
+    // their entries when they are compressed
+    hash_table_size: u64,
+    block_table_size: u64,
+    hi_block_table_size: u64,
+    het_table_size: u64,
+    bet_table_size: u64,
 }
 
 impl FileHeader {
@@ -160,26 +456,66 @@ impl FileHeader {
         [b'M', b'P', b'Q', b'\x1a']
     }
 
-    pub fn from_reader<R: Read>(rdr: &mut R) -> io::Result<FileHeader> {
+    pub fn from_reader<R: Read>(rdr: &mut R) -> Result<FileHeader> {
         let magic = try!(rdr.read_u32::<BigEndian>());
         if MPQ_HEADER_FILE_MAGIC != magic {
-            panic!("Bad magic");  // FIXME
+            return Err(Error::BadMagic(magic));
         }
         FileHeader::from_reader_nomagic(rdr)
     }
 
-    pub fn from_reader_nomagic<R: Read>(rdr: &mut R) -> io::Result<FileHeader> {
-        Ok(FileHeader {
+    pub fn from_reader_nomagic<R: Read>(rdr: &mut R) -> Result<FileHeader> {
+        let mut header = FileHeader {
             magic: FileHeader::magic(),
             header_size: try!(rdr.read_u32::<LittleEndian>()),
-            archive_size: try!(rdr.read_u32::<LittleEndian>()),
+            archive_size: try!(rdr.read_u32::<LittleEndian>()) as u64,
             format_version: try!(rdr.read_u16::<LittleEndian>()),
             sector_size_shift: try!(rdr.read_u16::<LittleEndian>()),
-            hash_table_offset: try!(rdr.read_u32::<LittleEndian>()),
-            block_table_offset: try!(rdr.read_u32::<LittleEndian>()),
+            hash_table_offset: try!(rdr.read_u32::<LittleEndian>()) as u64,
+            block_table_offset: try!(rdr.read_u32::<LittleEndian>()) as u64,
             hash_table_entries: try!(rdr.read_u32::<LittleEndian>()),
             block_table_entries: try!(rdr.read_u32::<LittleEndian>()),
-        })
+            hi_block_table_offset: 0,
+            het_table_offset: 0,
+            bet_table_offset: 0,
+            hash_table_size: 0,
+            block_table_size: 0,
+            hi_block_table_size: 0,
+            het_table_size: 0,
+            bet_table_size: 0,
+        };
+        let version = header.format_version as usize;
+        if MPQ_HEADER_SIZES.len() <= version {
+            return Err(Error::InvalidHeader("format_version"));
+        }
+        if header.header_size < MPQ_HEADER_SIZES[version] {
+            return Err(Error::InvalidHeader("header_size"));
+        }
+        header.hash_table_size = 16 * header.hash_table_entries as u64;
+        header.block_table_size = 16 * header.block_table_entries as u64;
+
+        if 1 <= version {
+            header.hi_block_table_offset = try!(rdr.read_u64::<LittleEndian>());
+            header.hash_table_offset |= (try!(rdr.read_u16::<LittleEndian>()) as u64) << 32;
+            header.block_table_offset |= (try!(rdr.read_u16::<LittleEndian>()) as u64) << 32;
+            if header.hi_block_table_offset != 0 {
+                header.hi_block_table_size = 2 * header.block_table_entries as u64;
+            }
+        }
+        if 2 <= version {
+            header.archive_size = try!(rdr.read_u64::<LittleEndian>());
+            header.bet_table_offset = try!(rdr.read_u64::<LittleEndian>());
+            header.het_table_offset = try!(rdr.read_u64::<LittleEndian>());
+        }
+        if 3 <= version {
+            header.hash_table_size = try!(rdr.read_u64::<LittleEndian>());
+            header.block_table_size = try!(rdr.read_u64::<LittleEndian>());
+            header.hi_block_table_size = try!(rdr.read_u64::<LittleEndian>());
+            header.het_table_size = try!(rdr.read_u64::<LittleEndian>());
+            header.bet_table_size = try!(rdr.read_u64::<LittleEndian>());
+            // the raw chunk size and MD5s of the tables and header follow
+        }
+        Ok(header)
     }
 }
 
@@ -192,12 +528,12 @@ struct UserDataHeader {
 }
 
 impl UserDataHeader {
-    fn from_reader<R: Read>(rdr: &mut R) -> io::Result<UserDataHeader> {
+    fn from_reader<R: Read>(rdr: &mut R) -> Result<UserDataHeader> {
         let magic = try!(rdr.read_u32::<BigEndian>());
         if MPQ_HEADER_USER_DATA_MAGIC != magic {
-            panic!("Bad magic");  // FIXME
+            return Err(Error::BadMagic(magic));
         }
-        UserDataHeader::from_reader_nomagic(rdr)
+        Ok(try!(UserDataHeader::from_reader_nomagic(rdr)))
     }
 
     pub fn from_reader_nomagic<R: Read>(rdr: &mut R) -> io::Result<UserDataHeader> {
@@ -236,22 +572,79 @@ impl HashTableValue {
     }
 }
 
+/// The names in a listfile.
+fn listfile_names(listfile: &[u8]) -> Vec<&[u8]> {
+    listfile
+        .split(|&ch| ch == b'\r' || ch == b'\n' || ch == b';')
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// The entry for `filename` in `hash_table` that best matches `locale`.
+/// Entries for the platform-neutral version are preferred.
+fn find_hash_entry<'a>(
+    hash_table: &'a [(HashTableKey, HashTableValue)],
+    filename: &[u8],
+    locale: u16,
+) -> Option<&'a HashTableValue> {
+    hash_entries(hash_table, filename).into_iter().min_by_key(|entry| {
+        let rank = if entry.locale == locale {
+            0
+        } else if entry.locale == LOCALE_NEUTRAL {
+            1
+        } else {
+            2
+        };
+        (rank, entry.platform != 0)
+    })
+}
+
+/// Every entry for `filename` in `hash_table`, probing from the slot the
+/// name hashes to, skipping deleted slots, until an empty one.
+fn hash_entries<'a>(
+    hash_table: &'a [(HashTableKey, HashTableValue)],
+    filename: &[u8],
+) -> Vec<&'a HashTableValue> {
+    let mut found = Vec::new();
+    if hash_table.is_empty() {
+        return found;
+    }
+    let key = (
+        string_hash(ENCRYPTION_TABLE, filename, StringHashType::HashA),
+        string_hash(ENCRYPTION_TABLE, filename, StringHashType::HashB),
+    );
+    let start = string_hash(ENCRYPTION_TABLE, filename, StringHashType::TableOffset) as usize % hash_table.len();
+
+    for idx in (start..hash_table.len()).chain(0..start) {
+        let (entry_key, ref entry) = hash_table[idx];
+        match entry.block_table_index {
+            MPQ_HASH_ENTRY_EMPTY => break,
+            MPQ_HASH_ENTRY_DELETED => continue,
+            _ => {},
+        }
+        if entry_key == key {
+            found.push(entry);
+        }
+    }
+    found
+}
+
 #[derive(Debug)]
 struct BlockTableEntry {
-    offset: u32,
+    offset: u64,
     archived_size: u32,
     size: u32,
     flags: u32,
 }
 
 impl BlockTableEntry {
-    pub fn to_tuple(&self) -> (u32, u32, u32, u32) {
+    pub fn to_tuple(&self) -> (u64, u32, u32, u32) {
         (self.offset, self.archived_size, self.size, self.flags)
     }
 
     fn from_reader(rdr: &mut Read) -> io::Result<BlockTableEntry> {
         Ok(BlockTableEntry {
-            offset: try!(rdr.read_u32::<LittleEndian>()),
+            offset: try!(rdr.read_u32::<LittleEndian>()) as u64,
             archived_size: try!(rdr.read_u32::<LittleEndian>()),
             size: try!(rdr.read_u32::<LittleEndian>()),
             flags: try!(rdr.read_u32::<LittleEndian>()),
@@ -259,13 +652,26 @@ impl BlockTableEntry {
     }
 }
 
+/// The key a file is encrypted with, from its name without the directory,
+/// adjusted by its position and size for `MPQ_FILE_FIX_KEY`.
+fn file_key(filename: &[u8], block_entry: &BlockTableEntry) -> u32 {
+    let basename = match filename.iter().rposition(|&ch| ch == b'\\' || ch == b'/') {
+        Some(idx) => &filename[idx + 1..],
+        None => filename,
+    };
+    let key = string_hash(ENCRYPTION_TABLE, basename, StringHashType::Table);
+    if (block_entry.flags & MPQ_FILE_FIX_KEY) > 0 {
+        key.wrapping_add(block_entry.offset as u32) ^ block_entry.size
+    } else {
+        key
+    }
+}
+
 fn decrypt(table: [Wrapping<u32>; 1280], key: u32, buf: &mut [u8]) {
     let mut seed1: Wrapping<u32> = Wrapping(key);
     let mut seed2: Wrapping<u32> = Wrapping(0xEEEEEEEE);
 
-    if buf.len() % 4 != 0 {
-        panic!("buffer length must be multiple of four");
-    }
+    // a partial word at the end is not encrypted
     let word_count = buf.len() / 4;
     let mut tmp = Cursor::new(Vec::with_capacity(buf.len()));
 
@@ -285,28 +691,144 @@ fn decrypt(table: [Wrapping<u32>; 1280], key: u32, buf: &mut [u8]) {
     ::std::slice::bytes::copy_memory(&tmp, buf);
 }
 
-fn decompress(input: &[u8], output: &mut Vec<u8>) -> Result<usize, ()> {
+type Decompressor = fn(&[u8], &mut Vec<u8>) -> ::std::result::Result<usize, ()>;
+
+/// The steps of the compression mask in the order they are undone, the
+/// reverse of the order they were applied in.
+static DECOMPRESSORS: [(u8, Decompressor); 7] = [
+    (MPQ_COMPRESSION_BZIP2, bz2_decompress as Decompressor),
+    (MPQ_COMPRESSION_PKWARE, explode_decompress as Decompressor),
+    (MPQ_COMPRESSION_ZLIB, zlib_decompress as Decompressor),
+    (MPQ_COMPRESSION_HUFFMAN, huffman_decompress as Decompressor),
+    (MPQ_COMPRESSION_ADPCM_STEREO, adpcm_stereo_decompress as Decompressor),
+    (MPQ_COMPRESSION_ADPCM_MONO, adpcm_mono_decompress as Decompressor),
+    (MPQ_COMPRESSION_SPARSE, sparse_decompress as Decompressor),
+];
+
+/// Decompresses a whole single unit file or one sector, as `flags` says
+/// it was compressed.  `MPQ_FILE_IMPLODE` data has no compression mask.
+fn decompress_block(flags: u32, input: &[u8], output: &mut Vec<u8>) -> ::std::result::Result<usize, ()> {
+    if (flags & MPQ_FILE_IMPLODE) > 0 {
+        explode_decompress(input, output)
+    } else {
+        decompress(input, output)
+    }
+}
+
+/// Why `input` didn't decompress: it uses Huffman coding, which isn't
+/// supported, or else it is invalid.
+fn decompress_error(flags: u32, input: &[u8], reason: &'static str) -> Error {
+    match input.first() {
+        Some(&mask) if (flags & MPQ_FILE_IMPLODE) == 0 && (mask & MPQ_COMPRESSION_HUFFMAN) > 0 => {
+            Error::UnsupportedCompression(mask)
+        },
+        _ => Error::InvalidFile(reason),
+    }
+}
+
+fn decompress(input: &[u8], output: &mut Vec<u8>) -> ::std::result::Result<usize, ()> {
     if input.len() == 0 {
         return Err(());
     }
-    match input[0] {
-        0 => no_decompress(&input[1..], output),
-        2 => zlib_decompress(&input[1..], output),
-        16 => bz2_decompress(&input[1..], output),
-        _ => Err(()),
+    let mask = input[0];
+    if mask == MPQ_COMPRESSION_LZMA {
+        return lzma_decompress(&input[1..], output);
+    }
+    let known = DECOMPRESSORS.iter().fold(0, |acc, &(bit, _)| acc | bit);
+    if (mask & !known) > 0 {
+        return Err(());
+    }
+
+    let mut buf = input[1..].to_vec();
+    for &(bit, decompressor) in DECOMPRESSORS.iter() {
+        if (mask & bit) > 0 {
+            let mut next = Vec::new();
+            try!(decompressor(&buf, &mut next));
+            buf = next;
+        }
     }
+    let length = buf.len();
+    output.extend(buf.into_iter());
+    Ok(length)
 }
 
-fn no_decompress(input: &[u8], output: &mut Vec<u8>) -> Result<usize, ()> {
-    output.extend(input.iter().cloned());
-    Ok(input.len())
+fn zlib_decompress(input: &[u8], output: &mut Vec<u8>) -> ::std::result::Result<usize, ()> {
+    // decoded apart so a bad stream leaves nothing behind in `output`
+    let mut buf = Vec::new();
+    try!(flate2::read::ZlibDecoder::new(input).read_to_end(&mut buf).map_err(|_| ()));
+    let length = buf.len();
+    output.extend(buf.into_iter());
+    Ok(length)
 }
 
-fn zlib_decompress(input: &[u8], output: &mut Vec<u8>) -> Result<usize, ()> {
-    panic!("unimplemented: zlib");
+fn explode_decompress(input: &[u8], output: &mut Vec<u8>) -> ::std::result::Result<usize, ()> {
+    let mut buf = Vec::new();
+    try!(explode::explode(input, &mut buf).map_err(|_| ()));
+    let length = buf.len();
+    output.extend(buf.into_iter());
+    Ok(length)
 }
 
-fn bz2_decompress(input: &[u8], output: &mut Vec<u8>) -> Result<usize, ()> {
+/// Storm's adaptive Huffman coding isn't supported: its trees are built
+/// from per-type byte weight tables that this crate doesn't have.
+fn huffman_decompress(_input: &[u8], _output: &mut Vec<u8>) -> ::std::result::Result<usize, ()> {
+    Err(())
+}
+
+fn adpcm_mono_decompress(input: &[u8], output: &mut Vec<u8>) -> ::std::result::Result<usize, ()> {
+    adpcm::decompress(input, 1, output).ok_or(())
+}
+
+fn adpcm_stereo_decompress(input: &[u8], output: &mut Vec<u8>) -> ::std::result::Result<usize, ()> {
+    adpcm::decompress(input, 2, output).ok_or(())
+}
+
+/// Runs of zeros and of stored bytes, after the big endian unpacked size.
+fn sparse_decompress(input: &[u8], output: &mut Vec<u8>) -> ::std::result::Result<usize, ()> {
+    if input.len() < 4 {
+        return Err(());
+    }
+    let size = (&input[..4]).read_u32::<BigEndian>().unwrap() as usize;
+    let start = output.len();
+    let mut position = 4;
+    while position < input.len() {
+        let run = input[position];
+        position += 1;
+        if (run & 0x80) > 0 {
+            let length = (run & 0x7F) as usize + 1;
+            if input.len() < position + length {
+                return Err(());
+            }
+            output.extend(input[position..position + length].iter().cloned());
+            position += length;
+        } else {
+            let length = (run & 0x7F) as usize + 3;
+            output.extend((0..length).map(|_| 0));
+        }
+    }
+    // the last run of zeros may be cut short
+    if output.len() - start < size {
+        return Err(());
+    }
+    output.truncate(start + size);
+    Ok(size)
+}
+
+/// A zero filter byte, then an LZMA stream with its 13 byte header of
+/// properties and unpacked size.
+fn lzma_decompress(input: &[u8], output: &mut Vec<u8>) -> ::std::result::Result<usize, ()> {
+    if input.len() == 0 || input[0] != 0 {
+        return Err(());
+    }
+    let stream = try!(xz2::stream::Stream::new_lzma_decoder(u64::max_value()).map_err(|_| ()));
+    let mut buf = Vec::new();
+    try!(xz2::read::XzDecoder::new_stream(&input[1..], stream).read_to_end(&mut buf).map_err(|_| ()));
+    let length = buf.len();
+    output.extend(buf.into_iter());
+    Ok(length)
+}
+
+fn bz2_decompress(input: &[u8], output: &mut Vec<u8>) -> ::std::result::Result<usize, ()> {
     let buf = bzip2::decompress(input);
     let length = buf.len();
     output.extend(buf.into_iter());
@@ -342,53 +864,159 @@ fn string_hash_normalize(ch: u8) -> Wrapping<u32> {
     }
 }
 
+/// A table of `size` bytes stored in `stored` bytes at `offset`, decrypted
+/// with `key` if it has one and decompressed if it is stored in fewer bytes
+/// than it has.
+fn read_table<R: Read+Seek>(
+    reader: &mut R,
+    offset: u64,
+    stored: u64,
+    size: u64,
+    key: Option<u32>,
+    file_len: u64,
+    name: &'static str,
+) -> Result<Vec<u8>> {
+    match offset.checked_add(stored) {
+        Some(end) if end <= file_len => {},
+        _ => return Err(Error::InvalidTable(name)),
+    }
+    let mut buffer = vec![0; stored as usize];
+    try!(reader.seek(SeekFrom::Start(offset)));
+    try!(read_exact(reader, &mut buffer));
+    if let Some(key) = key {
+        decrypt(ENCRYPTION_TABLE, key, &mut buffer);
+    }
+    if size <= stored {
+        buffer.truncate(size as usize);
+        return Ok(buffer);
+    }
+    // `size` comes from the header, so the table grows with what actually
+    // decompresses rather than being allocated up front
+    let mut table = Vec::new();
+    try!(decompress(&buffer, &mut table).map_err(|_| Error::InvalidTable(name)));
+    if table.len() as u64 != size {
+        return Err(Error::InvalidTable(name));
+    }
+    Ok(table)
+}
+
+/// A HET or BET table, whose data after its 12-byte header is encrypted
+/// with `key` and may be compressed.  Version 3 headers don't give the
+/// tables' sizes, so those tables are taken to be as long as their headers
+/// say.
+fn read_ext_table<R: Read+Seek>(
+    reader: &mut R,
+    offset: u64,
+    stored: u64,
+    key: u32,
+    file_len: u64,
+    name: &'static str,
+) -> Result<Vec<u8>> {
+    let mut table = try!(read_table(reader, offset, 12, 12, None, file_len, name));
+    let data_size = try!(Cursor::new(&table[8..]).read_u32::<LittleEndian>()) as u64;
+    let stored = if stored == 0 { 12 + data_size } else { stored };
+    if stored < 12 {
+        return Err(Error::InvalidTable(name));
+    }
+    let data = try!(read_table(reader, offset + 12, stored - 12, data_size, Some(key), file_len, name));
+    table.extend(data.into_iter());
+    Ok(table)
+}
+
 fn read_hash_table<R: Read+Seek>(
     reader: &mut R,
     header: &FileHeader,
     header_offset: u32,
-) -> io::Result<HashMap<HashTableKey, HashTableValue>> {
-    let table_offset = header.hash_table_offset + header_offset;
-    if table_offset != 205652 { panic!("bad offset {}", table_offset) }
+    file_len: u64,
+) -> Result<Vec<(HashTableKey, HashTableValue)>> {
     let table_entries = header.hash_table_entries;
-
-    let mut buffer = vec![0; 16 * table_entries as usize];
-    try!(reader.seek(SeekFrom::Start(table_offset as u64)));
-    try!(read_exact(reader, &mut buffer));
     let key = string_hash(ENCRYPTION_TABLE, b"(hash table)", StringHashType::Table);
-    decrypt(ENCRYPTION_TABLE, key, &mut buffer);
+    let buffer = try!(read_table(
+        reader,
+        header_offset as u64 + header.hash_table_offset,
+        header.hash_table_size,
+        16 * table_entries as u64,
+        Some(key),
+        file_len,
+        "hash table"));
 
     let mut entry_rdr = Cursor::new(&buffer[..]);
-    let mut out = HashMap::new();
+    let mut out = Vec::with_capacity(table_entries as usize);
     for _ in 0..table_entries {
-        let (key, val) = try!(HashTableValue::from_reader(&mut entry_rdr));
-        out.insert(key, val);
+        out.push(try!(HashTableValue::from_reader(&mut entry_rdr)));
     }
     Ok(out)
 }
 
+/// The block table, with the high bits of the offsets from the hi-block
+/// table if there is one.
 fn read_block_table<R: Read+Seek>(
     reader: &mut R,
     header: &FileHeader,
     header_offset: u32,
-) -> io::Result<Vec<BlockTableEntry>> {
-    let table_offset = header.block_table_offset + header_offset;
-    if table_offset != 205908 { panic!("bad offset {}", table_offset) }
+    file_len: u64,
+) -> Result<Vec<BlockTableEntry>> {
     let table_entries = header.block_table_entries;
-
-    let mut buffer = vec![0; 16 * table_entries as usize];
-    try!(reader.seek(SeekFrom::Start(table_offset as u64)));
-    try!(read_exact(reader, &mut buffer));
     let key = string_hash(ENCRYPTION_TABLE, b"(block table)", StringHashType::Table);
-    decrypt(ENCRYPTION_TABLE, key, &mut buffer);
+    let buffer = try!(read_table(
+        reader,
+        header_offset as u64 + header.block_table_offset,
+        header.block_table_size,
+        16 * table_entries as u64,
+        Some(key),
+        file_len,
+        "block table"));
 
     let mut entry_rdr = Cursor::new(&buffer[..]);
     let mut out = Vec::with_capacity(table_entries as usize);
     for _ in 0..table_entries {
         out.push(try!(BlockTableEntry::from_reader(&mut entry_rdr)));
     }
+
+    if header.hi_block_table_offset != 0 {
+        let buffer = try!(read_table(
+            reader,
+            header_offset as u64 + header.hi_block_table_offset,
+            header.hi_block_table_size,
+            2 * table_entries as u64,
+            None,
+            file_len,
+            "hi-block table"));
+        let mut hi_rdr = Cursor::new(&buffer[..]);
+        for entry in out.iter_mut() {
+            entry.offset |= (try!(hi_rdr.read_u16::<LittleEndian>()) as u64) << 32;
+        }
+    }
     Ok(out)
 }
 
+/// The HET table and the BET table's files, if the archive has them.
+fn read_het_bet_tables<R: Read+Seek>(
+    reader: &mut R,
+    header: &FileHeader,
+    header_offset: u32,
+    file_len: u64,
+) -> Result<Option<(HetTable, Vec<BlockTableEntry>)>> {
+    if header.het_table_offset == 0 || header.bet_table_offset == 0 {
+        return Ok(None);
+    }
+    let het = try!(read_ext_table(
+        reader,
+        header_offset as u64 + header.het_table_offset,
+        header.het_table_size,
+        string_hash(ENCRYPTION_TABLE, b"(hash table)", StringHashType::Table),
+        file_len,
+        "HET table"));
+    let bet = try!(read_ext_table(
+        reader,
+        header_offset as u64 + header.bet_table_offset,
+        header.bet_table_size,
+        string_hash(ENCRYPTION_TABLE, b"(block table)", StringHashType::Table),
+        file_len,
+        "BET table"));
+    hetbet::read_tables(&het, &bet).map(Some).map_err(Error::InvalidTable)
+}
+
 fn read_exact<R: Read>(rdr: &mut R, buf: &mut [u8]) -> io::Result<()> {
     let to_read = buf.len();
     let mut offset = 0;
@@ -407,12 +1035,261 @@ fn read_exact<R: Read>(rdr: &mut R, buf: &mut [u8]) -> io::Result<()> {
 
 #[cfg(test)]
 mod tests {
-    use std::io::Cursor;
-    use super::{Archive, BlockTableEntry, HashTableValue};
+    use std::num::Wrapping;
+    use std::io::{Cursor, Write};
+    use byteorder::{ByteOrder, WriteBytesExt, BigEndian, LittleEndian};
+    use flate2::write::ZlibEncoder;
+    use xz2::stream::{LzmaOptions, Stream};
+    use xz2::write::XzEncoder;
+    use super::{Archive, Compression, Error, BlockTableEntry, HashTableValue, decompress, explode, find_hash_entry, read_sectors, string_hash};
+    use super::{StringHashType, ENCRYPTION_TABLE, LOCALE_NEUTRAL, MPQ_HEADER_FILE_MAGIC};
+    use super::{MPQ_HASH_ENTRY_DELETED, MPQ_HASH_ENTRY_EMPTY};
+    use super::{MPQ_FILE_COMPRESS, MPQ_FILE_ENCRYPTED, MPQ_FILE_EXISTS, MPQ_FILE_FIX_KEY, MPQ_FILE_IMPLODE};
+    use super::{MPQ_FILE_SECTOR_CRC, MPQ_FILE_SINGLE_UNIT};
+    use super::hetbet::jenkins_hash;
 
     static REPLAY_DETAILS: &'static [u8] = include_bytes!("../../testdata/base_build_15405/replay.details");
+    static REPLAY_GAME_EVENTS: &'static [u8] = include_bytes!("../../testdata/base_build_15405/replay.game.events");
     static SC2_REPLAY: &'static [u8] = include_bytes!("../../testdata/test.SC2Replay");
 
+    // b"AIAIAIAIAIAIA", imploded
+    static IMPLODED: &'static [u8] = b"\x00\x04\x82\x24\x25\x8f\x80\x7f";
+    // 64 bytes: 0x01, 20 zeros, 0x02 0x03, 41 zeros
+    static SPARSE: &'static [u8] = b"\x00\x00\x00\x40\x80\x01\x11\x81\x02\x03\x7f";
+
+    // bzip2 of b"ab" repeated 256 times
+    static BZ2_SECTOR: &'static [u8] = b"\x42\x5a\x68\x39\x31\x41\x59\x26\x53\x59\x93\xa3\xc6\x52\x00\x00\x7f\x81\x00\x30\x00\x20\x00\x30\x80\x2a\x69\x00\x40\x1c\x5d\xc9\x14\xe1\x42\x42\x4e\x8f\x19\x48";
+
+    const HASH_TABLE_ENTRIES: usize = 16;
+
+    struct ArchivedFile<'a> {
+        name: &'a [u8],
+        data: Vec<u8>,
+        size: u32,
+        flags: u32,
+    }
+
+    fn encrypt(key: u32, buf: &mut [u8]) {
+        let mut seed1 = Wrapping(key);
+        let mut seed2 = Wrapping(0xEEEEEEEE);
+        for word in buf.chunks_mut(4) {
+            if word.len() < 4 {
+                break;
+            }
+            let value = Wrapping(LittleEndian::read_u32(word));
+            seed2 = seed2 + ENCRYPTION_TABLE[0x400 + (seed1.0 & 0xFF) as usize];
+            LittleEndian::write_u32(word, (value ^ (seed1 + seed2)).0);
+            seed1 = (((seed1 ^ Wrapping(0xFFFFFFFF)) << 0x15) + Wrapping(0x11111111)) | (seed1 >> 0x0B);
+            seed2 = value + seed2 + (seed2 << 5) + Wrapping(3);
+        }
+    }
+
+    /// A version 1 archive holding `files`, with the file data straight
+    /// after the header and the tables after the data.
+    fn build_archive(files: &[ArchivedFile], sector_size_shift: u16) -> Vec<u8> {
+        let locales: Vec<u16> = files.iter().map(|_| LOCALE_NEUTRAL).collect();
+        build_localized_archive(files, &locales, sector_size_shift)
+    }
+
+    /// `build_archive` with each file in the locale at the same index of
+    /// `locales`.
+    fn build_localized_archive(files: &[ArchivedFile], locales: &[u16], sector_size_shift: u16) -> Vec<u8> {
+        let mut data = Vec::new();
+        let mut block_table = Vec::new();
+        let mut hash_table = vec![0xFF; 16 * HASH_TABLE_ENTRIES];
+        for (idx, file) in files.iter().enumerate() {
+            block_table.write_u32::<LittleEndian>(32 + data.len() as u32).unwrap();
+            block_table.write_u32::<LittleEndian>(file.data.len() as u32).unwrap();
+            block_table.write_u32::<LittleEndian>(file.size).unwrap();
+            block_table.write_u32::<LittleEndian>(file.flags).unwrap();
+            data.extend(file.data.iter().cloned());
+
+            let mut slot = string_hash(ENCRYPTION_TABLE, file.name, StringHashType::TableOffset) as usize;
+            while hash_table[16 * (slot % HASH_TABLE_ENTRIES)..][..4] != [0xFF; 4] {
+                slot += 1;
+            }
+            let mut entry = &mut hash_table[16 * (slot % HASH_TABLE_ENTRIES)..][..16];
+            entry.write_u32::<LittleEndian>(string_hash(ENCRYPTION_TABLE, file.name, StringHashType::HashA)).unwrap();
+            entry.write_u32::<LittleEndian>(string_hash(ENCRYPTION_TABLE, file.name, StringHashType::HashB)).unwrap();
+            entry.write_u16::<LittleEndian>(locales[idx]).unwrap();
+            entry.write_u16::<LittleEndian>(0).unwrap();
+            entry.write_u32::<LittleEndian>(idx as u32).unwrap();
+        }
+        encrypt(string_hash(ENCRYPTION_TABLE, b"(hash table)", StringHashType::Table), &mut hash_table);
+        encrypt(string_hash(ENCRYPTION_TABLE, b"(block table)", StringHashType::Table), &mut block_table);
+
+        let hash_table_offset = 32 + data.len();
+        let mut archive = Vec::new();
+        archive.write_u32::<BigEndian>(MPQ_HEADER_FILE_MAGIC).unwrap();
+        archive.write_u32::<LittleEndian>(32).unwrap();
+        archive.write_u32::<LittleEndian>((hash_table_offset + hash_table.len() + block_table.len()) as u32).unwrap();
+        archive.write_u16::<LittleEndian>(0).unwrap();
+        archive.write_u16::<LittleEndian>(sector_size_shift).unwrap();
+        archive.write_u32::<LittleEndian>(hash_table_offset as u32).unwrap();
+        archive.write_u32::<LittleEndian>((hash_table_offset + hash_table.len()) as u32).unwrap();
+        archive.write_u32::<LittleEndian>(HASH_TABLE_ENTRIES as u32).unwrap();
+        archive.write_u32::<LittleEndian>(files.len() as u32).unwrap();
+        archive.extend(data.into_iter());
+        archive.extend(hash_table.into_iter());
+        archive.extend(block_table.into_iter());
+        archive
+    }
+
+    /// A version 3 or 4 archive holding `files`, with HET and BET tables
+    /// but no hash or block table.  Only version 4 headers say how long the
+    /// tables are, so only they can have the HET table compressed.
+    fn build_het_bet_archive(files: &[ArchivedFile], format_version: u16, compress_het: bool) -> Vec<u8> {
+        let header_size = if format_version == 3 { 208 } else { 68 };
+        let slots = 8;
+
+        // 64-bit name hashes, so the BET table has the low 56 bits
+        let mut data = Vec::new();
+        let mut name_hashes = vec![0u8; slots];
+        let mut indexes = vec![0xFFu8; slots];
+        let mut bet_entries = Vec::new();
+        let mut bet_hashes = Vec::new();
+        let mut bet_flags = Vec::new();
+        for (idx, file) in files.iter().enumerate() {
+            let hash = jenkins_hash(file.name) | (1 << 63);
+            let mut slot = (hash % slots as u64) as usize;
+            while name_hashes[slot] != 0 {
+                slot = (slot + 1) % slots;
+            }
+            name_hashes[slot] = (hash >> 56) as u8;
+            indexes[slot] = idx as u8;
+
+            bet_entries.write_u32::<LittleEndian>((header_size + data.len()) as u32).unwrap();
+            bet_entries.write_u32::<LittleEndian>(file.size).unwrap();
+            bet_entries.write_u32::<LittleEndian>(file.data.len() as u32).unwrap();
+            bet_entries.write_u8(idx as u8).unwrap();
+            bet_hashes.extend((0..7).map(|byte| (hash >> (8 * byte)) as u8));
+            bet_flags.write_u32::<LittleEndian>(file.flags).unwrap();
+            data.extend(file.data.iter().cloned());
+        }
+
+        let mut het = Vec::new();
+        for &value in [0, 0, slots, 64, 8, 0, 8, slots].iter() {
+            het.write_u32::<LittleEndian>(value as u32).unwrap();
+        }
+        het.extend(name_hashes.into_iter());
+        het.extend(indexes.into_iter());
+
+        let mut bet = Vec::new();
+        // count, unknown, entry size, field positions, field widths, the
+        // name hash widths and array size, and the flags
+        let n = files.len();
+        for &value in [0, n, 0x10, 104, 0, 32, 64, 96, 104, 32, 32, 32, 8, 0, 56, 0, 56, 7 * n, n].iter() {
+            bet.write_u32::<LittleEndian>(value as u32).unwrap();
+        }
+        bet.extend(bet_flags.into_iter());
+        bet.extend(bet_entries.into_iter());
+        bet.extend(bet_hashes.into_iter());
+
+        let ext_table = |signature: &[u8], mut table: Vec<u8>, key: &[u8], compress: bool| {
+            let data_size = table.len();
+            if compress {
+                table = zlib_sector(&table);
+            }
+            encrypt(string_hash(ENCRYPTION_TABLE, key, StringHashType::Table), &mut table);
+            let mut out = signature.to_vec();
+            out.write_u32::<LittleEndian>(1).unwrap();
+            out.write_u32::<LittleEndian>(data_size as u32).unwrap();
+            out.extend(table.into_iter());
+            out
+        };
+        let het = ext_table(b"HET\x1a", het, b"(hash table)", compress_het);
+        let bet = ext_table(b"BET\x1a", bet, b"(block table)", false);
+
+        let het_offset = (header_size + data.len()) as u64;
+        let bet_offset = het_offset + het.len() as u64;
+        let archive_size = bet_offset + bet.len() as u64;
+        let mut archive = Vec::new();
+        archive.write_u32::<BigEndian>(MPQ_HEADER_FILE_MAGIC).unwrap();
+        archive.write_u32::<LittleEndian>(header_size as u32).unwrap();
+        archive.write_u32::<LittleEndian>(archive_size as u32).unwrap();
+        archive.write_u16::<LittleEndian>(format_version).unwrap();
+        archive.write_u16::<LittleEndian>(3).unwrap();
+        // no hash, block or hi-block table
+        archive.extend([0; 28].iter().cloned());
+        archive.write_u64::<LittleEndian>(archive_size).unwrap();
+        archive.write_u64::<LittleEndian>(bet_offset).unwrap();
+        archive.write_u64::<LittleEndian>(het_offset).unwrap();
+        if format_version == 3 {
+            for &size in [0, 0, 0, het.len() as u64, bet.len() as u64].iter() {
+                archive.write_u64::<LittleEndian>(size).unwrap();
+            }
+            // the raw chunk size and MD5s
+            archive.extend([0; 100].iter().cloned());
+        }
+        assert_eq!(archive.len(), header_size);
+        archive.extend(data.into_iter());
+        archive.extend(het.into_iter());
+        archive.extend(bet.into_iter());
+        archive
+    }
+
+    /// A sector offset table followed by `sectors`, encrypted with `key` if
+    /// there is one.
+    fn pack_sectors(sectors: &[Vec<u8>], key: Option<u32>) -> Vec<u8> {
+        let mut file_data = Vec::new();
+        let mut position = 4 * (sectors.len() + 1);
+        file_data.write_u32::<LittleEndian>(position as u32).unwrap();
+        for sector in sectors.iter() {
+            position += sector.len();
+            file_data.write_u32::<LittleEndian>(position as u32).unwrap();
+        }
+        if let Some(key) = key {
+            encrypt(key.wrapping_sub(1), &mut file_data);
+        }
+        for (idx, sector) in sectors.iter().enumerate() {
+            let mut sector = sector.clone();
+            if let Some(key) = key {
+                encrypt(key.wrapping_add(idx as u32), &mut sector);
+            }
+            file_data.extend(sector.into_iter());
+        }
+        file_data
+    }
+
+    /// `data` compressed as an MPQ zlib sector.
+    fn zlib_sector(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(vec![2], ::flate2::Compression::Default);
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    /// Three 512-byte sectors holding 1300 bytes: a bzip2 sector, a
+    /// stored one and a short stored one.
+    fn sectored_file(crc: bool) -> (Vec<u8>, Vec<u8>, BlockTableEntry) {
+        let mut expected = Vec::new();
+        for _ in 0..256 {
+            expected.extend(b"ab".iter().cloned());
+        }
+        expected.extend((0..788).map(|x| x as u8));
+
+        let mut sectors = vec![b"\x10".to_vec()];
+        sectors[0].extend(BZ2_SECTOR.iter().cloned());
+        sectors.push(expected[512..1024].to_vec());
+        sectors.push(expected[1024..].to_vec());
+        if crc {
+            sectors.push(vec![0; 12]);
+        }
+
+        let file_data = pack_sectors(&sectors, None);
+
+        let mut flags = MPQ_FILE_EXISTS | MPQ_FILE_COMPRESS;
+        if crc {
+            flags |= MPQ_FILE_SECTOR_CRC;
+        }
+        let block_entry = BlockTableEntry {
+            offset: 0,
+            archived_size: file_data.len() as u32,
+            size: expected.len() as u32,
+            flags: flags,
+        };
+        (file_data, expected, block_entry)
+    }
+
     #[test]
     fn test_header_reader() {
         let archive = Archive::load(Cursor::new(SC2_REPLAY)).unwrap();
@@ -450,19 +1327,23 @@ mod tests {
     #[test]
     fn test_hash_table() {
         let mut archive = Archive::load(Cursor::new(SC2_REPLAY)).ok().expect("load fail");
-        let entries = archive.hash_table;
+        let table = archive.hash_table;
+        assert_eq!(table.len(), 16);
+        let entries = |key: (u32, u32)| {
+            &table.iter().find(|&&(entry_key, _)| entry_key == key).unwrap().1
+        };
 
-        assert_eq!(entries[&(0xD38437CB, 0x07DFEAEC)].to_tuple(), (0x0000, 0x0000, 0x00000009));
-        assert_eq!(entries[&(0xAAC2A54B, 0xF4762B95)].to_tuple(), (0x0000, 0x0000, 0x00000002));
-        assert_eq!(entries[&(0xC9E5B770, 0x3B18F6B6)].to_tuple(), (0x0000, 0x0000, 0x00000005));
-        assert_eq!(entries[&(0x343C087B, 0x278E3682)].to_tuple(), (0x0000, 0x0000, 0x00000004));
-        assert_eq!(entries[&(0x3B2B1EA0, 0xB72EF057)].to_tuple(), (0x0000, 0x0000, 0x00000006));
-        assert_eq!(entries[&(0x5A7E8BDC, 0xFF253F5C)].to_tuple(), (0x0000, 0x0000, 0x00000001));
-        assert_eq!(entries[&(0xFD657910, 0x4E9B98A7)].to_tuple(), (0x0000, 0x0000, 0x00000008));
-        assert_eq!(entries[&(0xD383C29C, 0xEF402E92)].to_tuple(), (0x0000, 0x0000, 0x00000000));
-        assert_eq!(entries[&(0xFFFFFFFF, 0xFFFFFFFF)].to_tuple(), (0xFFFF, 0xFFFF, 0xFFFFFFFF));
-        assert_eq!(entries[&(0x1DA8B0CF, 0xA2CEFF28)].to_tuple(), (0x0000, 0x0000, 0x00000007));
-        assert_eq!(entries[&(0x31952289, 0x6A5FFAA3)].to_tuple(), (0x0000, 0x0000, 0x00000003));
+        assert_eq!(entries((0xD38437CB, 0x07DFEAEC)).to_tuple(), (0x0000, 0x0000, 0x00000009));
+        assert_eq!(entries((0xAAC2A54B, 0xF4762B95)).to_tuple(), (0x0000, 0x0000, 0x00000002));
+        assert_eq!(entries((0xC9E5B770, 0x3B18F6B6)).to_tuple(), (0x0000, 0x0000, 0x00000005));
+        assert_eq!(entries((0x343C087B, 0x278E3682)).to_tuple(), (0x0000, 0x0000, 0x00000004));
+        assert_eq!(entries((0x3B2B1EA0, 0xB72EF057)).to_tuple(), (0x0000, 0x0000, 0x00000006));
+        assert_eq!(entries((0x5A7E8BDC, 0xFF253F5C)).to_tuple(), (0x0000, 0x0000, 0x00000001));
+        assert_eq!(entries((0xFD657910, 0x4E9B98A7)).to_tuple(), (0x0000, 0x0000, 0x00000008));
+        assert_eq!(entries((0xD383C29C, 0xEF402E92)).to_tuple(), (0x0000, 0x0000, 0x00000000));
+        assert_eq!(entries((0xFFFFFFFF, 0xFFFFFFFF)).to_tuple(), (0xFFFF, 0xFFFF, 0xFFFFFFFF));
+        assert_eq!(entries((0x1DA8B0CF, 0xA2CEFF28)).to_tuple(), (0x0000, 0x0000, 0x00000007));
+        assert_eq!(entries((0x31952289, 0x6A5FFAA3)).to_tuple(), (0x0000, 0x0000, 0x00000003));
     }
 
     #[test]
@@ -474,4 +1355,701 @@ mod tests {
         assert_eq!(bytes_read, REPLAY_DETAILS.len());
         assert_eq!(&buffer[..], REPLAY_DETAILS);
     }
+
+    #[test]
+    fn test_read_game_events() {
+        let mut archive = Archive::load(Cursor::new(SC2_REPLAY)).ok().expect("load fail");
+
+        let mut buffer = Vec::new();
+        let bytes_read = archive.read_file(b"replay.game.events", &mut buffer).unwrap();
+        assert_eq!(bytes_read, REPLAY_GAME_EVENTS.len());
+        assert_eq!(&buffer[..], REPLAY_GAME_EVENTS);
+    }
+
+    #[test]
+    fn test_read_sectors() {
+        for &crc in [false, true].iter() {
+            let (file_data, expected, block_entry) = sectored_file(crc);
+            let mut buffer = Vec::new();
+            let bytes_read = read_sectors(&file_data, &block_entry, 0, None, &mut buffer).unwrap();
+            assert_eq!(bytes_read, 1300);
+            assert_eq!(buffer, expected);
+        }
+    }
+
+    #[test]
+    fn test_read_sectors_uncompressed() {
+        let (_, expected, mut block_entry) = sectored_file(false);
+        block_entry.flags = MPQ_FILE_EXISTS;
+        let mut buffer = Vec::new();
+        assert_eq!(read_sectors(&expected, &block_entry, 0, None, &mut buffer).unwrap(), 1300);
+        assert_eq!(buffer, expected);
+    }
+
+    #[test]
+    fn test_read_sectors_truncated() {
+        let (file_data, _, block_entry) = sectored_file(false);
+        let mut buffer = Vec::new();
+        assert!(read_sectors(&file_data[..8], &block_entry, 0, None, &mut buffer).is_err());
+        assert!(read_sectors(&file_data[..file_data.len() - 1], &block_entry, 0, None, &mut buffer).is_err());
+        // with larger sectors, the sectors unpack to the wrong size
+        assert!(read_sectors(&file_data, &block_entry, 1, None, &mut buffer).is_err());
+    }
+
+    #[test]
+    fn test_zlib_single_unit() {
+        let contents: Vec<u8> = (0..3000).map(|x| (x / 7) as u8).collect();
+        let archive = build_archive(&[ArchivedFile {
+            name: b"replay.game.events",
+            data: zlib_sector(&contents),
+            size: contents.len() as u32,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_COMPRESS | MPQ_FILE_SINGLE_UNIT,
+        }], 3);
+        let mut archive = Archive::load(Cursor::new(archive)).ok().expect("load fail");
+
+        let mut buffer = Vec::new();
+        assert_eq!(archive.read_file(b"replay.game.events", &mut buffer).unwrap(), 3000);
+        assert_eq!(buffer, contents);
+    }
+
+    #[test]
+    fn test_zlib_sectors() {
+        let contents: Vec<u8> = (0..10000).map(|x| (x % 251) as u8).collect();
+        let sectors: Vec<Vec<u8>> = contents.chunks(4096).map(zlib_sector).collect();
+        let archive = build_archive(&[
+            ArchivedFile {
+                name: b"replay.details",
+                data: zlib_sector(REPLAY_DETAILS),
+                size: REPLAY_DETAILS.len() as u32,
+                flags: MPQ_FILE_EXISTS | MPQ_FILE_COMPRESS | MPQ_FILE_SINGLE_UNIT,
+            },
+            ArchivedFile {
+                name: b"replay.game.events",
+                data: pack_sectors(&sectors, None),
+                size: contents.len() as u32,
+                flags: MPQ_FILE_EXISTS | MPQ_FILE_COMPRESS,
+            },
+        ], 3);
+        let mut archive = Archive::load(Cursor::new(archive)).ok().expect("load fail");
+
+        let mut buffer = Vec::new();
+        assert_eq!(archive.read_file(b"replay.game.events", &mut buffer).unwrap(), 10000);
+        assert_eq!(buffer, contents);
+
+        let mut buffer = Vec::new();
+        archive.read_file(b"replay.details", &mut buffer).unwrap();
+        assert_eq!(&buffer[..], REPLAY_DETAILS);
+    }
+
+    #[test]
+    fn test_zlib_malformed() {
+        let contents = vec![0; 4096];
+        let mut sector = zlib_sector(&contents);
+        let len = sector.len();
+        sector[len / 2] ^= 0xFF;
+        sector.truncate(len - 2);
+        let archive = build_archive(&[ArchivedFile {
+            name: b"replay.game.events",
+            data: sector,
+            size: contents.len() as u32,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_COMPRESS | MPQ_FILE_SINGLE_UNIT,
+        }], 3);
+        let mut archive = Archive::load(Cursor::new(archive)).ok().expect("load fail");
+
+        let mut buffer = Vec::new();
+        assert!(archive.read_file(b"replay.game.events", &mut buffer).is_err());
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_decompress_mask() {
+        let mut sparse = vec![0; 64];
+        sparse[0] = 1;
+        sparse[21] = 2;
+        sparse[22] = 3;
+
+        let mut output = Vec::new();
+        let mut input = vec![0x20];
+        input.extend(SPARSE.iter().cloned());
+        assert_eq!(decompress(&input, &mut output), Ok(64));
+        assert_eq!(output, sparse);
+
+        // sparse, then zlib
+        let mut output = Vec::new();
+        let mut input = zlib_sector(SPARSE);
+        input[0] |= 0x20;
+        assert_eq!(decompress(&input, &mut output), Ok(64));
+        assert_eq!(output, sparse);
+
+        let mut output = Vec::new();
+        let mut input = vec![0x08];
+        input.extend(IMPLODED.iter().cloned());
+        assert_eq!(decompress(&input, &mut output), Ok(13));
+        assert_eq!(&output[..], b"AIAIAIAIAIAIA");
+
+        let mut output = vec![b'x'];
+        assert_eq!(decompress(b"\x00stored", &mut output), Ok(6));
+        assert_eq!(&output[..], b"xstored");
+    }
+
+    #[test]
+    fn test_decompress_lzma() {
+        let contents: Vec<u8> = (0..5000).map(|x| (x % 13) as u8).collect();
+        let stream = Stream::new_lzma_encoder(&LzmaOptions::new_preset(6).unwrap()).unwrap();
+        let mut encoder = XzEncoder::new_stream(vec![0x12, 0x00], stream);
+        encoder.write_all(&contents).unwrap();
+        let input = encoder.finish().unwrap();
+
+        let mut output = Vec::new();
+        assert_eq!(decompress(&input, &mut output), Ok(5000));
+        assert_eq!(output, contents);
+
+        // a filter byte other than zero
+        let mut input = input;
+        input[1] = 1;
+        assert!(decompress(&input, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_decompress_errors() {
+        let mut output = Vec::new();
+        assert!(decompress(b"", &mut output).is_err());
+        // 0x04 is not a compression bit
+        assert!(decompress(b"\x04data", &mut output).is_err());
+        // a sparse run past the end of the input
+        assert!(decompress(b"\x20\x00\x00\x00\x10\x85\x01", &mut output).is_err());
+        // a sparse stream short of its size
+        assert!(decompress(b"\x20\x00\x00\x01\x00\x7f", &mut output).is_err());
+        assert!(decompress(b"\x08\x00\x04\x82", &mut output).is_err());
+        assert!(decompress(b"\x40\x00\x01", &mut output).is_err());
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn test_imploded_files() {
+        let mut contents = Vec::new();
+        while contents.len() < 1300 {
+            contents.extend(b"eat tea at the east gate ".iter().cloned());
+        }
+        contents.truncate(1300);
+        let sectors: Vec<Vec<u8>> = contents.chunks(512).map(explode::implode_literals).collect();
+        assert!(sectors[0].len() < 512);
+        let archive = build_archive(&[
+            ArchivedFile {
+                name: b"single",
+                data: IMPLODED.to_vec(),
+                size: 13,
+                flags: MPQ_FILE_EXISTS | MPQ_FILE_IMPLODE | MPQ_FILE_SINGLE_UNIT,
+            },
+            ArchivedFile {
+                name: b"sectors",
+                data: pack_sectors(&sectors, None),
+                size: contents.len() as u32,
+                flags: MPQ_FILE_EXISTS | MPQ_FILE_IMPLODE,
+            },
+        ], 0);
+        let mut archive = Archive::load(Cursor::new(archive)).ok().expect("load fail");
+
+        let mut buffer = Vec::new();
+        assert_eq!(archive.read_file(b"single", &mut buffer).unwrap(), 13);
+        assert_eq!(&buffer[..], b"AIAIAIAIAIAIA");
+
+        let mut buffer = Vec::new();
+        assert_eq!(archive.read_file(b"sectors", &mut buffer).unwrap(), 1300);
+        assert_eq!(buffer, contents);
+    }
+
+    #[test]
+    fn test_encrypted_files() {
+        let key = |name: &[u8]| string_hash(ENCRYPTION_TABLE, name, StringHashType::Table);
+        let contents: Vec<u8> = (0..1299).map(|x| (x / 8) as u8).collect();
+
+        let mut single = zlib_sector(&contents);
+        encrypt(key(b"single.txt"), &mut single);
+
+        // the second file's data follows the first's, after the header
+        let offset = 32 + single.len() as u32;
+        let fixed_key = key(b"sectored").wrapping_add(offset) ^ contents.len() as u32;
+        let sectors: Vec<Vec<u8>> = contents.chunks(512).map(zlib_sector).collect();
+        let sectored = pack_sectors(&sectors, Some(fixed_key));
+
+        // stored sectors have no offset table, and the last three bytes are
+        // past the last whole word
+        let mut stored = Vec::new();
+        for (idx, sector) in contents.chunks(512).enumerate() {
+            let mut sector = sector.to_vec();
+            encrypt(key(b"stored").wrapping_add(idx as u32), &mut sector);
+            stored.extend(sector.into_iter());
+        }
+        assert_eq!(&stored[1296..], &contents[1296..]);
+
+        let archive = build_archive(&[
+            ArchivedFile {
+                name: b"Maps\\single.txt",
+                data: single,
+                size: contents.len() as u32,
+                flags: MPQ_FILE_EXISTS | MPQ_FILE_COMPRESS | MPQ_FILE_SINGLE_UNIT | MPQ_FILE_ENCRYPTED,
+            },
+            ArchivedFile {
+                name: b"sectored",
+                data: sectored,
+                size: contents.len() as u32,
+                flags: MPQ_FILE_EXISTS | MPQ_FILE_COMPRESS | MPQ_FILE_ENCRYPTED | MPQ_FILE_FIX_KEY,
+            },
+            ArchivedFile {
+                name: b"stored",
+                data: stored,
+                size: contents.len() as u32,
+                flags: MPQ_FILE_EXISTS | MPQ_FILE_ENCRYPTED,
+            },
+        ], 0);
+        let mut archive = Archive::load(Cursor::new(archive)).ok().expect("load fail");
+
+        for name in [&b"Maps\\single.txt"[..], b"sectored", b"stored"].iter() {
+            let mut buffer = Vec::new();
+            assert_eq!(archive.read_file(name, &mut buffer).unwrap(), 1299);
+            assert_eq!(buffer, contents);
+        }
+    }
+
+    fn load_error(archive: Vec<u8>) -> Error {
+        match Archive::load(Cursor::new(archive)) {
+            Ok(_) => panic!("loaded a bad archive"),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let file = || vec![ArchivedFile {
+            name: b"replay.details",
+            data: REPLAY_DETAILS.to_vec(),
+            size: REPLAY_DETAILS.len() as u32,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT,
+        }];
+
+        let mut archive = build_archive(&file(), 3);
+        archive[3] = b'X';
+        match load_error(archive) {
+            Error::BadMagic(0x4d505158) => {},
+            e => panic!("unexpected {:?}", e),
+        }
+
+        // the block table ends the archive
+        let mut archive = build_archive(&file(), 3);
+        archive.pop();
+        match load_error(archive) {
+            Error::InvalidTable("block table") => {},
+            e => panic!("unexpected {:?}", e),
+        }
+
+        let mut archive = build_archive(&file(), 3);
+        archive[27] = 0xFF;
+        match load_error(archive) {
+            Error::InvalidTable("hash table") => {},
+            e => panic!("unexpected {:?}", e),
+        }
+
+        let mut archive = build_archive(&file(), 3);
+        archive[4] = 12;
+        match load_error(archive) {
+            Error::InvalidHeader("header_size") => {},
+            e => panic!("unexpected {:?}", e),
+        }
+
+        // user data whose archive header is past the end
+        let mut archive = SC2_REPLAY[..1024].to_vec();
+        archive.extend(build_archive(&file(), 3).into_iter());
+        archive[11] = 0xFF;
+        match load_error(archive) {
+            Error::InvalidHeader("mpq_header_offset") => {},
+            e => panic!("unexpected {:?}", e),
+        }
+
+        match load_error(b"MPQ".to_vec()) {
+            Error::Io(_) => {},
+            e => panic!("unexpected {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_user_data_offset() {
+        // the archive's header after 1024 bytes of user data
+        let mut archive = SC2_REPLAY[..1024].to_vec();
+        archive.extend(build_archive(&[ArchivedFile {
+            name: b"replay.details",
+            data: REPLAY_DETAILS.to_vec(),
+            size: REPLAY_DETAILS.len() as u32,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT,
+        }], 3).into_iter());
+        let mut archive = Archive::load(Cursor::new(archive)).ok().expect("load fail");
+        assert_eq!(archive.header_offset, 1024);
+        assert_eq!(archive.user_data(), Some(&SC2_REPLAY[16..76]));
+
+        let mut buffer = Vec::new();
+        archive.read_file(b"replay.details", &mut buffer).unwrap();
+        assert_eq!(&buffer[..], REPLAY_DETAILS);
+        match archive.read_file(b"replay.initData", &mut buffer) {
+            Err(Error::FileNotFound) => {},
+            r => panic!("unexpected {:?}", r),
+        }
+    }
+
+    #[test]
+    fn test_het_bet_tables() {
+        let mut events = Vec::new();
+        for chunk in REPLAY_GAME_EVENTS.chunks(4096) {
+            events.push(zlib_sector(chunk));
+        }
+        let files = [ArchivedFile {
+            name: b"replay.details",
+            data: REPLAY_DETAILS.to_vec(),
+            size: REPLAY_DETAILS.len() as u32,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT,
+        }, ArchivedFile {
+            name: b"replay.game.events",
+            data: pack_sectors(&events, None),
+            size: REPLAY_GAME_EVENTS.len() as u32,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_COMPRESS,
+        }];
+
+        for &(format_version, compress_het) in [(2, false), (3, false), (3, true)].iter() {
+            let archive = build_het_bet_archive(&files, format_version, compress_het);
+            let mut archive = Archive::load(Cursor::new(archive)).ok().expect("load fail");
+            assert_eq!(archive.header.format_version, format_version);
+            assert_eq!(archive.block_table.len(), 2);
+
+            let mut buffer = Vec::new();
+            archive.read_file(b"Replay.Details", &mut buffer).unwrap();
+            assert_eq!(&buffer[..], REPLAY_DETAILS);
+            buffer.clear();
+            archive.read_file(b"replay.game.events", &mut buffer).unwrap();
+            assert_eq!(&buffer[..], REPLAY_GAME_EVENTS);
+            match archive.read_file(b"replay.initData", &mut buffer) {
+                Err(Error::FileNotFound) => {},
+                r => panic!("unexpected {:?}", r),
+            }
+        }
+    }
+
+    #[test]
+    fn test_het_before_hash_table() {
+        let files = [ArchivedFile {
+            name: b"replay.details",
+            data: REPLAY_DETAILS.to_vec(),
+            size: REPLAY_DETAILS.len() as u32,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT,
+        }, ArchivedFile {
+            name: b"(listfile)",
+            data: b"replay.details\r\n".to_vec(),
+            size: 16,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT,
+        }];
+        let archive = build_het_bet_archive(&files, 3, false);
+        let mut archive = Archive::load(Cursor::new(archive)).ok().expect("load fail");
+
+        // a hash table that disagrees with the HET table about
+        // replay.details and knows a name the HET table doesn't
+        let mut hash_table: Vec<_> = (0..4).map(|_| ((0, 0), HashTableValue {
+            locale: 0,
+            platform: 0,
+            block_table_index: MPQ_HASH_ENTRY_EMPTY,
+        })).collect();
+        for &(name, block_index) in [(&b"replay.details"[..], 1), (&b"(attributes)"[..], 0)].iter() {
+            let key = (
+                string_hash(ENCRYPTION_TABLE, name, StringHashType::HashA),
+                string_hash(ENCRYPTION_TABLE, name, StringHashType::HashB),
+            );
+            let mut slot = string_hash(ENCRYPTION_TABLE, name, StringHashType::TableOffset) as usize % 4;
+            while hash_table[slot].1.block_table_index != MPQ_HASH_ENTRY_EMPTY {
+                slot = (slot + 1) % 4;
+            }
+            hash_table[slot].0 = key;
+            hash_table[slot].1.block_table_index = block_index;
+        }
+        archive.hash_table = hash_table;
+
+        let mut buffer = Vec::new();
+        archive.read_file(b"replay.details", &mut buffer).unwrap();
+        assert_eq!(&buffer[..], REPLAY_DETAILS);
+        buffer.clear();
+        archive.read_file(b"(attributes)", &mut buffer).unwrap();
+        assert_eq!(&buffer[..], REPLAY_DETAILS);
+        assert_eq!(archive.block_indexes(b"replay.details"), vec![0]);
+        assert_eq!(archive.block_indexes(b"(attributes)"), vec![0]);
+    }
+
+    #[test]
+    fn test_het_bet_errors() {
+        let files = [ArchivedFile {
+            name: b"replay.details",
+            data: REPLAY_DETAILS.to_vec(),
+            size: REPLAY_DETAILS.len() as u32,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT,
+        }];
+        let archive = build_het_bet_archive(&files, 3, false);
+        let het_offset = 208 + REPLAY_DETAILS.len();
+
+        let mut bad = archive.clone();
+        bad[het_offset] = b'X';
+        match load_error(bad) {
+            Error::InvalidTable("HET table") => {},
+            e => panic!("unexpected {:?}", e),
+        }
+
+        // a BET table that runs past the end
+        let mut bad = archive.clone();
+        let len = bad.len();
+        bad.truncate(len - 1);
+        match load_error(bad) {
+            Error::InvalidTable("BET table") => {},
+            e => panic!("unexpected {:?}", e),
+        }
+
+        let mut bad = archive.clone();
+        bad[4] = 68;
+        match load_error(bad) {
+            Error::InvalidHeader("header_size") => {},
+            e => panic!("unexpected {:?}", e),
+        }
+
+        let mut bad = archive.clone();
+        bad[12] = 4;
+        match load_error(bad) {
+            Error::InvalidHeader("format_version") => {},
+            e => panic!("unexpected {:?}", e),
+        }
+
+        // a compressed hash table that claims far more entries than its
+        // 16 bytes can hold
+        let mut bad = archive.clone();
+        LittleEndian::write_u32(&mut bad[16..], 208);
+        LittleEndian::write_u32(&mut bad[24..], 0x0FFFFFFF);
+        LittleEndian::write_u64(&mut bad[68..], 16);
+        match load_error(bad) {
+            Error::InvalidTable("hash table") => {},
+            e => panic!("unexpected {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_hi_block_table() {
+        // a version 2 header, the hi-block table and a two entry block
+        // table, the second entry's offset past 4GiB
+        let mut block_table = Vec::new();
+        for &offset in [0x2C, 0x400].iter() {
+            block_table.write_u32::<LittleEndian>(offset).unwrap();
+            block_table.write_u32::<LittleEndian>(16).unwrap();
+            block_table.write_u32::<LittleEndian>(16).unwrap();
+            block_table.write_u32::<LittleEndian>(MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT).unwrap();
+        }
+        encrypt(string_hash(ENCRYPTION_TABLE, b"(block table)", StringHashType::Table), &mut block_table);
+
+        let mut archive = Vec::new();
+        archive.write_u32::<BigEndian>(MPQ_HEADER_FILE_MAGIC).unwrap();
+        archive.write_u32::<LittleEndian>(44).unwrap();
+        archive.write_u32::<LittleEndian>(80).unwrap();
+        archive.write_u16::<LittleEndian>(1).unwrap();
+        archive.write_u16::<LittleEndian>(3).unwrap();
+        archive.write_u32::<LittleEndian>(48).unwrap();
+        archive.write_u32::<LittleEndian>(48).unwrap();
+        archive.write_u32::<LittleEndian>(0).unwrap();
+        archive.write_u32::<LittleEndian>(2).unwrap();
+        archive.write_u64::<LittleEndian>(44).unwrap();
+        archive.write_u16::<LittleEndian>(0).unwrap();
+        archive.write_u16::<LittleEndian>(0).unwrap();
+        archive.extend([0, 0, 1, 0].iter().cloned());
+        archive.extend(block_table.into_iter());
+
+        let archive = Archive::load(Cursor::new(archive)).ok().expect("load fail");
+        assert_eq!(archive.block_table[0].to_tuple(), (0x2C, 16, 16, 0x81000000));
+        assert_eq!(archive.block_table[1].to_tuple(), (0x100000400, 16, 16, 0x81000000));
+    }
+
+    #[test]
+    fn test_hash_table_probing() {
+        let name = b"replay.details";
+        let key = (
+            string_hash(ENCRYPTION_TABLE, name, StringHashType::HashA),
+            string_hash(ENCRYPTION_TABLE, name, StringHashType::HashB),
+        );
+        let start = string_hash(ENCRYPTION_TABLE, name, StringHashType::TableOffset) as usize % 4;
+        let entry = |key: (u32, u32), locale: u16, block_table_index: u32| {
+            (key, HashTableValue { locale: locale, platform: 0, block_table_index: block_table_index })
+        };
+
+        // a deleted slot and another file's before the name's own
+        let mut table: Vec<_> = (0..4).map(|_| entry((!0, !0), 0xFFFF, MPQ_HASH_ENTRY_EMPTY)).collect();
+        table[start] = entry((1, 2), 0, MPQ_HASH_ENTRY_DELETED);
+        table[(start + 1) % 4] = entry((3, 4), 0, 0);
+        table[(start + 2) % 4] = entry(key, 0x407, 1);
+        assert_eq!(find_hash_entry(&table, name, 0x407).unwrap().block_table_index, 1);
+        assert_eq!(find_hash_entry(&table, name, LOCALE_NEUTRAL).unwrap().block_table_index, 1);
+        assert!(find_hash_entry(&table, b"replay.initData", LOCALE_NEUTRAL).is_none());
+        assert!(find_hash_entry(&[], name, LOCALE_NEUTRAL).is_none());
+
+        // an empty slot ends the lookup
+        table[(start + 1) % 4].1.block_table_index = MPQ_HASH_ENTRY_EMPTY;
+        assert!(find_hash_entry(&table, name, 0x407).is_none());
+    }
+
+    #[test]
+    fn test_read_file_with_locale() {
+        let file = |data: &[u8]| ArchivedFile {
+            name: b"replay.details",
+            data: data.to_vec(),
+            size: data.len() as u32,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT,
+        };
+        let files = [file(b"neutral"), file(b"deDE"), file(b"frFR")];
+        let archive = build_localized_archive(&files, &[LOCALE_NEUTRAL, 0x407, 0x40C], 3);
+        let mut archive = Archive::load(Cursor::new(archive)).ok().expect("load fail");
+
+        let mut read = |locale: u16| {
+            let mut buffer = Vec::new();
+            archive.read_file_with_locale(b"replay.details", locale, &mut buffer).unwrap();
+            buffer
+        };
+        assert_eq!(&read(0x407)[..], b"deDE");
+        assert_eq!(&read(0x40C)[..], b"frFR");
+        assert_eq!(&read(0x409)[..], b"neutral");
+        assert_eq!(&read(LOCALE_NEUTRAL)[..], b"neutral");
+
+        // with no neutral version, the first one there is
+        let files = [file(b"deDE"), file(b"frFR")];
+        let archive = build_localized_archive(&files, &[0x407, 0x40C], 3);
+        let mut archive = Archive::load(Cursor::new(archive)).ok().expect("load fail");
+        let mut buffer = Vec::new();
+        archive.read_file(b"replay.details", &mut buffer).unwrap();
+        assert_eq!(&buffer[..], b"deDE");
+    }
+
+    #[test]
+    fn test_entries() {
+        let mut archive = Archive::load(Cursor::new(SC2_REPLAY)).ok().expect("load fail");
+        let entries = archive.entries().unwrap();
+        let names: Vec<&[u8]> = entries.iter().map(|entry| &entry.name.as_ref().unwrap()[..]).collect();
+        assert_eq!(names, vec![
+            &b"replay.details"[..], b"replay.initData", b"replay.game.events",
+            b"replay.message.events", b"replay.load.info", b"replay.sync.events",
+            b"replay.smartcam.events", b"replay.attributes.events", b"(listfile)",
+            b"(attributes)",
+        ]);
+
+        let events = &entries[2];
+        assert_eq!(events.block_index, 2);
+        assert_eq!((events.offset, events.archived_size, events.size), (1572, 194096, 479869));
+        assert_eq!(events.flags, MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT | MPQ_FILE_COMPRESS);
+        assert_eq!(events.compression, Compression::Compressed);
+        assert!(!events.is_encrypted());
+    }
+
+    #[test]
+    fn test_entries_unnamed() {
+        let details = ArchivedFile {
+            name: b"replay.details",
+            data: REPLAY_DETAILS.to_vec(),
+            size: REPLAY_DETAILS.len() as u32,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT,
+        };
+        let imploded = ArchivedFile {
+            name: b"imploded",
+            data: IMPLODED.to_vec(),
+            size: 13,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT | MPQ_FILE_IMPLODE,
+        };
+        let deleted = ArchivedFile {
+            name: b"deleted",
+            data: Vec::new(),
+            size: 0,
+            flags: 0,
+        };
+
+        let archive = build_archive(&[details, deleted, imploded], 3);
+        let mut archive = Archive::load(Cursor::new(archive)).ok().expect("load fail");
+        let entries = archive.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].name.clone(), entries[0].block_index), (None, 0));
+        assert_eq!(entries[0].compression, Compression::Stored);
+        assert_eq!((entries[1].name.clone(), entries[1].block_index), (None, 2));
+        assert_eq!(entries[1].compression, Compression::Imploded);
+        assert_eq!((entries[1].offset, entries[1].size), (32 + REPLAY_DETAILS.len() as u64, 13));
+
+        let entries = archive.entries_with_listfile(b"imploded\r\nreplay.initData;replay.details\n").unwrap();
+        assert_eq!(entries[0].name, Some(b"replay.details".to_vec()));
+        assert_eq!(entries[1].name, Some(b"imploded".to_vec()));
+    }
+
+    #[test]
+    fn test_entries_listfile() {
+        let listfile = b"replay.details\r\n(listfile)\r\n";
+        let files = [ArchivedFile {
+            name: b"replay.details",
+            data: REPLAY_DETAILS.to_vec(),
+            size: REPLAY_DETAILS.len() as u32,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT,
+        }, ArchivedFile {
+            name: b"(listfile)",
+            data: listfile.to_vec(),
+            size: listfile.len() as u32,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT,
+        }];
+
+        // looked up by the hash table, and by the HET table
+        let archives = vec![build_archive(&files, 3), build_het_bet_archive(&files, 3, false)];
+        for archive in archives.into_iter() {
+            let mut archive = Archive::load(Cursor::new(archive)).ok().expect("load fail");
+            let names: Vec<_> = archive.entries().unwrap().into_iter().map(|entry| entry.name).collect();
+            assert_eq!(names, vec![Some(b"replay.details".to_vec()), Some(b"(listfile)".to_vec())]);
+        }
+    }
+
+    #[test]
+    fn test_entries_corrupt_listfile() {
+        let files = [ArchivedFile {
+            name: b"replay.details",
+            data: REPLAY_DETAILS.to_vec(),
+            size: REPLAY_DETAILS.len() as u32,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT,
+        }, ArchivedFile {
+            name: b"(listfile)",
+            data: b"\x02 not a zlib stream".to_vec(),
+            size: 64,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT | MPQ_FILE_COMPRESS,
+        }];
+        let archive = build_archive(&files, 3);
+        let mut archive = Archive::load(Cursor::new(archive)).ok().expect("load fail");
+        let mut buffer = Vec::new();
+        assert!(archive.read_file(b"(listfile)", &mut buffer).is_err());
+
+        let names: Vec<_> = archive.entries().unwrap().into_iter().map(|entry| entry.name).collect();
+        assert_eq!(names, vec![None, Some(b"(listfile)".to_vec())]);
+    }
+
+    #[test]
+    fn test_huffman_unsupported() {
+        let mut output = Vec::new();
+        assert!(decompress(b"\x01\x00\x00", &mut output).is_err());
+
+        // a Huffman and ADPCM mono sector, as the game writes WAVE files
+        let (mut file_data, _, block_entry) = sectored_file(false);
+        // the first sector's mask, after the four offsets
+        file_data[16] = 0x41;
+        match read_sectors(&file_data, &block_entry, 0, None, &mut output) {
+            Err(Error::UnsupportedCompression(0x41)) => {},
+            r => panic!("unexpected {:?}", r),
+        }
+
+        let files = [ArchivedFile {
+            name: b"sound.wav",
+            data: b"\x81\x00\x00\x00".to_vec(),
+            size: 64,
+            flags: MPQ_FILE_EXISTS | MPQ_FILE_SINGLE_UNIT | MPQ_FILE_COMPRESS,
+        }];
+        let mut archive = Archive::load(Cursor::new(build_archive(&files, 3))).ok().expect("load fail");
+        match archive.read_file(b"sound.wav", &mut output) {
+            Err(Error::UnsupportedCompression(0x81)) => {},
+            r => panic!("unexpected {:?}", r),
+        }
+    }
 }
@@ -120,6 +120,9 @@ if __name__ == '__main__':
     print('''''')
 
     print('''pub static REPLAY_HEADER_TYPEID: u32 = {};'''.format(protocol.replay_header_typeid))
+    print('''pub static REPLAY_INITDATA_TYPEID: u32 = {};'''.format(protocol.replay_initdata_typeid))
+    print('''pub static SVARUINT32_TYPEID: u32 = {};'''.format(protocol.svaruint32_typeid))
+    print('''pub static REPLAY_USERID_TYPEID: u32 = {};'''.format(protocol.replay_userid_typeid))
     print('''''')
 
     print('''pub static GAME_EVENTID_TYPEID: u32 = {};'''.format(protocol.game_eventid_typeid))
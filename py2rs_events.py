@@ -0,0 +1,268 @@
+import sys
+import re
+
+from py2rs import load_protocol
+
+
+RUST_KEYWORDS = set([
+    'abstract', 'as', 'box', 'break', 'const', 'continue', 'crate', 'do',
+    'else', 'enum', 'extern', 'false', 'final', 'fn', 'for', 'if', 'impl',
+    'in', 'let', 'loop', 'macro', 'match', 'mod', 'move', 'mut', 'override',
+    'priv', 'pub', 'ref', 'return', 'self', 'static', 'struct', 'super',
+    'trait', 'true', 'type', 'typeof', 'unsafe', 'unsized', 'use', 'virtual',
+    'where', 'while', 'yield',
+])
+
+
+# Fields whose integers have a hand-written type in typed.rs.
+FIELD_TYPES = {
+    'm_recipient': 'Recipient',
+}
+
+SUPPORT_TYPES = ['Blob', 'BitArray', 'Recipient']
+
+
+def strip_prefix(name):
+    if name.startswith('m_'):
+        return name[2:]
+    return name.lstrip('_')
+
+
+def field_ident(name):
+    name = strip_prefix(name)
+    name = re.sub(r'([a-z0-9])([A-Z])', r'\1_\2', name)
+    name = re.sub(r'([A-Z])([A-Z][a-z])', r'\1_\2', name)
+    name = name.lower()
+    if name in RUST_KEYWORDS:
+        name += '_'
+    return name
+
+
+def type_ident(name):
+    name = strip_prefix(name)
+    return name[:1].upper() + name[1:]
+
+
+def event_ident(name):
+    return name.split('.')[-1]
+
+
+def int_type(bounds):
+    (minimum, bits) = bounds
+    maximum = minimum + (1 << bits) - 1
+    if minimum < 0:
+        for width in (8, 16, 32, 64):
+            if -(1 << (width - 1)) <= minimum and maximum < (1 << (width - 1)):
+                return 'i{}'.format(width)
+    else:
+        for width in (8, 16, 32, 64):
+            if maximum < (1 << width):
+                return 'u{}'.format(width)
+    raise ValueError(bounds)
+
+
+class Generator(object):
+    def __init__(self, protocol):
+        self.typeinfos = protocol.typeinfos
+        # typeid -> rust name, for structs and choices
+        self.names = {}
+        self.taken = set()
+        self.pending = []
+
+    def struct_fields(self, typeid):
+        """Fields as the decoders present them: parents are merged in."""
+        (kind, args) = self.typeinfos[typeid]
+        assert kind == '_struct'
+        out = []
+        for (name, field_typeid, _tag) in args[0]:
+            if name == '__parent' and self.typeinfos[field_typeid][0] == '_struct':
+                out.extend(self.struct_fields(field_typeid))
+            else:
+                out.append((name, field_typeid))
+        return out
+
+    def lone_parent(self, typeid):
+        (kind, args) = self.typeinfos[typeid]
+        fields = args[0] if kind == '_struct' else None
+        if fields and len(fields) == 1 and fields[0][0] == '__parent':
+            return fields[0][1]
+        return None
+
+    def name_for(self, typeid, hint):
+        if typeid in self.names:
+            return self.names[typeid]
+        name = hint
+        if name in self.taken:
+            name = '{}{}'.format(hint, typeid)
+        self.names[typeid] = name
+        self.taken.add(name)
+        self.pending.append(typeid)
+        return name
+
+    def rust_type(self, typeid, hint):
+        (kind, args) = self.typeinfos[typeid]
+        if kind == '_array':
+            item_hint = hint[:-1] if hint.endswith('s') and not hint.endswith('ss') else hint
+            return 'Vec<{}>'.format(self.rust_type(args[1], item_hint))
+        if kind == '_bitarray':
+            return 'BitArray'
+        if kind == '_blob':
+            return 'Blob'
+        if kind == '_bool':
+            return 'bool'
+        if kind == '_choice':
+            return self.name_for(typeid, hint)
+        if kind == '_fourcc':
+            return '[u8; 4]'
+        if kind == '_int':
+            return int_type(args[0])
+        if kind == '_null':
+            return '()'
+        if kind == '_optional':
+            return 'Option<{}>'.format(self.rust_type(args[0], hint))
+        if kind == '_real32':
+            return 'f32'
+        if kind == '_real64':
+            return 'f64'
+        if kind == '_struct':
+            parent = self.lone_parent(typeid)
+            if parent is not None:
+                return self.rust_type(parent, hint)
+            return self.name_for(typeid, hint)
+        raise ValueError(kind)
+
+    def struct_to_rs(self, name, typeid):
+        fields = [
+            (field_ident(fname), fname,
+             FIELD_TYPES.get(fname) or self.rust_type(ftypeid, type_ident(fname)))
+            for (fname, ftypeid) in self.struct_fields(typeid)]
+        yield '#[derive(Clone, Debug, PartialEq)]'
+        if not fields:
+            yield 'pub struct {};'.format(name)
+            yield ''
+            yield 'impl FromValue for {} {{'.format(name)
+            yield '    fn from_value(value: &Value) -> Result<{}> {{'.format(name)
+            yield '        try!(value.as_dict().map_err(|_| Error::InvalidType));'
+            yield '        Ok({})'.format(name)
+            yield '    }'
+            yield '}'
+            return
+        yield 'pub struct {} {{'.format(name)
+        for (ident, _, rtype) in fields:
+            yield '    pub {}: {},'.format(ident, rtype)
+        yield '}'
+        yield ''
+        yield 'impl FromValue for {} {{'.format(name)
+        yield '    fn from_value(value: &Value) -> Result<{}> {{'.format(name)
+        yield '        Ok({} {{'.format(name)
+        for (ident, fname, _) in fields:
+            yield '            {}: try!(field(value, "{}")),'.format(ident, fname)
+        yield '        })'
+        yield '    }'
+        yield '}'
+
+    def choice_to_rs(self, name, typeid):
+        (_kind, (_bounds, variants)) = self.typeinfos[typeid]
+        arms = []
+        for tag in sorted(variants):
+            (vname, vtypeid) = variants[tag]
+            ident = type_ident(vname)
+            if self.typeinfos[vtypeid][0] == '_null':
+                arms.append((vname, ident, None))
+            else:
+                arms.append((vname, ident, self.rust_type(vtypeid, ident)))
+        yield '#[derive(Clone, Debug, PartialEq)]'
+        yield 'pub enum {} {{'.format(name)
+        for (_, ident, rtype) in arms:
+            if rtype is None:
+                yield '    {},'.format(ident)
+            else:
+                yield '    {}({}),'.format(ident, rtype)
+        yield '}'
+        yield ''
+        yield 'impl FromValue for {} {{'.format(name)
+        yield '    fn from_value(value: &Value) -> Result<{}> {{'.format(name)
+        yield '        let (variant, inner) = try!(value.as_choice().map_err(|_| Error::InvalidType));'
+        yield '        match variant {'
+        for (vname, ident, rtype) in arms:
+            if rtype is None:
+                yield '            "{}" => Ok({}::{}),'.format(vname, name, ident)
+            else:
+                yield '            "{}" => Ok({}::{}(try!(FromValue::from_value(inner)))),'.format(vname, name, ident)
+        yield '            _ => Err(Error::InvalidType),'
+        yield '        }'
+        yield '    }'
+        yield '}'
+
+    def events_to_rs(self, enum_name, event_types):
+        events = sorted(event_types.items())
+        blocks = []
+        for (_eventid, (typeid, name)) in events:
+            ident = event_ident(name)
+            self.taken.add(ident)
+            blocks.append(list(self.struct_to_rs(ident, typeid)))
+
+        yield '#[derive(Clone, Debug, PartialEq)]'
+        yield 'pub enum {} {{'.format(enum_name)
+        for (_eventid, (_typeid, name)) in events:
+            yield '    {0}({0}),'.format(event_ident(name))
+        yield '}'
+        yield ''
+        yield 'impl {} {{'.format(enum_name)
+        yield '    pub fn name(&self) -> &\'static str {'
+        yield '        match *self {'
+        for (_eventid, (_typeid, name)) in events:
+            yield '            {}::{}(_) => "{}",'.format(enum_name, event_ident(name), name)
+        yield '        }'
+        yield '    }'
+        yield '}'
+        yield ''
+        yield 'impl FromEvent for {} {{'.format(enum_name)
+        yield '    fn from_event(event: &Event) -> Result<{}> {{'.format(enum_name)
+        yield '        let value = &event.value;'
+        yield '        match event.event_id {'
+        for (eventid, (_typeid, name)) in events:
+            yield '            {} => Ok({}::{}(try!(FromValue::from_value(value)))),'.format(
+                eventid, enum_name, event_ident(name))
+        yield '            other => Err(Error::UnknownEvent(other)),'
+        yield '        }'
+        yield '    }'
+        yield '}'
+        for block in blocks:
+            yield ''
+            for line in block:
+                yield line
+
+        while self.pending:
+            typeid = self.pending.pop(0)
+            name = self.names[typeid]
+            yield ''
+            if self.typeinfos[typeid][0] == '_choice':
+                lines = self.choice_to_rs(name, typeid)
+            else:
+                lines = self.struct_to_rs(name, typeid)
+            for line in lines:
+                yield line
+
+
+if __name__ == '__main__':
+    protocol = load_protocol(sys.argv[1])
+    enum_name = sys.argv[2] if len(sys.argv) > 2 else 'GameEvent'
+    event_types = {
+        'GameEvent': protocol.game_event_types,
+        'MessageEvent': protocol.message_event_types,
+    }[enum_name]
+
+    lines = list(Generator(protocol).events_to_rs(enum_name, event_types))
+    support = [name for name in SUPPORT_TYPES
+               if any(re.search(r'\b{}\b'.format(name), line) for line in lines)]
+
+    print('''// Generated by py2rs_events.py; do not edit.''')
+    print('''''')
+    print('''use events::Event;''')
+    print('''use typed::{{FromValue, FromEvent, Error, Result, {}field}};'''.format(
+        ''.join(name + ', ' for name in support)))
+    print('''use value::Value;''')
+    print('''''')
+    for line in lines:
+        print(line)
@@ -1,22 +1,17 @@
 
 extern crate mpq;
-extern crate serde;
 extern crate serde_s2proto;
 
 use std::{env, fs};
-use std::io::{self, Read, Write};
-
-use serde::de;
 
+use serde_s2proto::apm::{ApmReport, ApmOptions};
+use serde_s2proto::common::{GameLoop, GameSpeed};
 use serde_s2proto::format::protocol15405;
 use serde_s2proto::VersionedDeserializer;
 
-mod value;
-
 fn main() {
 	let filename = env::args_os().nth(1).unwrap();
-	let mut file = fs::File::open(&filename).unwrap();
-    let mut archive = mpq::Archive::load(file).unwrap();
+    let mut archive = mpq::Archive::load(fs::File::open(&filename).unwrap()).unwrap();
 
     let mut replay_details = Vec::new();
     archive.read_file(b"replay.details", &mut replay_details).unwrap();
@@ -26,7 +21,7 @@ fn main() {
     	protocol15405::TYPEINFOS,
     	protocol15405::GAME_DETAILS_TYPEID);
 
-    let val: value::Value = de::Deserialize::deserialize(&mut des).unwrap();
+    let val = des.read_value().unwrap();
 
     let title = val.get_path(&["m_title"]).and_then(|x| x.as_str()).unwrap();
     println!("Map Title : {}", title);
@@ -40,4 +35,38 @@ fn main() {
     	let race = player.get_path(&["m_race"]).and_then(|x| x.as_str()).unwrap();
     	println!("  Team {}: {} ({})", team, name, race);
     }
+
+    let mut game_events = Vec::new();
+    archive.read_file(b"replay.game.events", &mut game_events).unwrap();
+
+    // the replay header lives in the archive's user data block
+    let header = archive.user_data().expect("replay has no header");
+    let header = VersionedDeserializer::new(
+        header,
+        protocol15405::TYPEINFOS,
+        protocol15405::REPLAY_HEADER_TYPEID).read_value().unwrap();
+    let elapsed = header.get_path(&["m_elapsedGameLoops"]).and_then(|x| x.as_u64()).unwrap();
+    let end = GameLoop(elapsed as u32);
+    let game_speed = val.get_path(&["m_gameSpeed"])
+        .and_then(|x| x.as_u64())
+        .ok()
+        .and_then(|x| GameSpeed::from_u8(x as u8));
+    let options = ApmOptions {
+        game_speed: game_speed,
+        ..ApmOptions::default()
+    };
+    let report = ApmReport::from_game_events(&game_events[..], end, &options).unwrap();
+
+    println!("apm:");
+    for (&user_id, apm) in report.players() {
+        // game event user ids count players from 1
+        let name = (user_id as usize).checked_sub(1)
+            .and_then(|idx| player_list.get(idx))
+            .and_then(|p| p.get_path(&["m_name"]).ok())
+            .and_then(|x| x.as_str().ok())
+            .unwrap_or("?");
+        println!("  {}: {:.0} APM, {:.0} EPM", name, apm.apm(), apm.epm());
+        let minutes: Vec<String> = apm.per_minute.iter().map(|m| m.actions.to_string()).collect();
+        println!("    per minute: {}", minutes.join(" "));
+    }
 }
@@ -0,0 +1,259 @@
+//! Actions and effective actions per minute from game events.
+//!
+//! An action is a command, a change to the active selection or a control
+//! group hotkey; camera movement can be counted too but is off by default.
+//! Effective actions drop the spam that inflates APM:
+//!
+//! * a command repeating the player's previous ability within
+//!   `REPEAT_WINDOW`,
+//! * a selection change replaced by another within `SELECTION_WINDOW`,
+//! * a control group hotkey repeating the previous one within
+//!   `REPEAT_WINDOW`, such as double tapping to centre the camera.
+
+use std::cmp;
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+use std::time::Duration;
+
+use common::{GameLoop, GameSpeed};
+use typed::{self, GameEvent, TypedEvent};
+use selection::ACTIVE_SELECTION;
+
+/// Gameloops, about 0.8 seconds of real time at Faster.
+pub const REPEAT_WINDOW: u32 = 18;
+
+/// Gameloops, about 0.25 seconds of real time at Faster.
+pub const SELECTION_WINDOW: u32 = 6;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ActionClass {
+    Command,
+    Selection,
+    ControlGroup,
+    Camera,
+}
+
+impl ActionClass {
+    /// The class of action an event is, or `None` if it is not one.
+    pub fn of(event: &GameEvent) -> Option<ActionClass> {
+        match *event {
+            GameEvent::SCmdEvent(_) => Some(ActionClass::Command),
+            GameEvent::SSelectionDeltaEvent(_) => Some(ActionClass::Selection),
+            GameEvent::SControlGroupUpdateEvent(_) => Some(ActionClass::ControlGroup),
+            GameEvent::SCameraUpdateEvent(_) => Some(ActionClass::Camera),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ApmOptions {
+    pub commands: bool,
+    pub selections: bool,
+    pub control_groups: bool,
+    pub camera: bool,
+    /// Minutes are real minutes at this speed, usually the replay's
+    /// `m_gameSpeed`, or minutes of game time if `None`.
+    pub game_speed: Option<GameSpeed>,
+}
+
+impl Default for ApmOptions {
+    fn default() -> ApmOptions {
+        ApmOptions {
+            commands: true,
+            selections: true,
+            control_groups: true,
+            camera: false,
+            game_speed: None,
+        }
+    }
+}
+
+impl ApmOptions {
+    pub fn includes(&self, class: ActionClass) -> bool {
+        match class {
+            ActionClass::Command => self.commands,
+            ActionClass::Selection => self.selections,
+            ActionClass::ControlGroup => self.control_groups,
+            ActionClass::Camera => self.camera,
+        }
+    }
+
+    fn time(&self, gameloop: GameLoop) -> Duration {
+        match self.game_speed {
+            Some(speed) => gameloop.real_time(speed),
+            None => gameloop.game_time(),
+        }
+    }
+
+    fn minutes(&self, gameloop: GameLoop) -> f64 {
+        let time = self.time(gameloop);
+        (time.as_secs() as f64 + time.subsec_nanos() as f64 / 1e9) / 60.0
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct MinuteApm {
+    pub actions: u32,
+    pub effective: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerApm {
+    pub actions: u32,
+    pub effective: u32,
+    /// Counts for each whole minute, the last one possibly partial.
+    pub per_minute: Vec<MinuteApm>,
+    minutes: f64,
+}
+
+impl PlayerApm {
+    pub fn apm(&self) -> f64 {
+        per_minute(self.actions, self.minutes)
+    }
+
+    pub fn epm(&self) -> f64 {
+        per_minute(self.effective, self.minutes)
+    }
+}
+
+fn per_minute(count: u32, minutes: f64) -> f64 {
+    if minutes == 0.0 {
+        return 0.0;
+    }
+    count as f64 / minutes
+}
+
+/// APM for every player that made an action, in user id order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApmReport {
+    pub minutes: f64,
+    players: BTreeMap<u32, PlayerApm>,
+}
+
+impl ApmReport {
+    /// Counts actions made before `end`, which is usually the game's
+    /// `m_elapsedGameLoops`.
+    pub fn from_events<I>(events: I, end: GameLoop, options: &ApmOptions) -> typed::Result<ApmReport>
+        where I: Iterator<Item=typed::Result<TypedEvent<GameEvent>>>,
+    {
+        let mut players: BTreeMap<u32, PlayerActions> = BTreeMap::new();
+        for event in events {
+            let event = try!(event);
+            if end <= event.gameloop {
+                break;
+            }
+            let player = match event.user_id {
+                Some(player) => player,
+                None => continue,
+            };
+            match ActionClass::of(&event.event) {
+                Some(class) if options.includes(class) => {
+                    players.entry(player).or_insert_with(PlayerActions::default).push(&event);
+                },
+                _ => {},
+            }
+        }
+
+        let minutes = options.minutes(end);
+        let buckets = minutes.ceil() as usize;
+        let players = players.into_iter().map(|(player, actions)| {
+            let mut apm = PlayerApm {
+                actions: 0,
+                effective: 0,
+                per_minute: vec![MinuteApm::default(); buckets],
+                minutes: minutes,
+            };
+            for action in actions.actions.iter() {
+                let minute = (options.time(action.gameloop).as_secs() / 60) as usize;
+                let minute = cmp::min(minute, buckets - 1);
+                apm.actions += 1;
+                apm.per_minute[minute].actions += 1;
+                if action.effective {
+                    apm.effective += 1;
+                    apm.per_minute[minute].effective += 1;
+                }
+            }
+            (player, apm)
+        }).collect();
+
+        Ok(ApmReport {
+            minutes: minutes,
+            players: players,
+        })
+    }
+
+    /// APM from the contents of `replay.game.events`.
+    pub fn from_game_events(buf: &[u8], end: GameLoop, options: &ApmOptions) -> typed::Result<ApmReport> {
+        ApmReport::from_events(typed::game_events(buf), end, options)
+    }
+
+    pub fn player(&self, player: u32) -> Option<&PlayerApm> {
+        self.players.get(&player)
+    }
+
+    pub fn players(&self) -> btree_map::Iter<u32, PlayerApm> {
+        self.players.iter()
+    }
+}
+
+struct Action {
+    gameloop: GameLoop,
+    effective: bool,
+}
+
+#[derive(Default)]
+struct PlayerActions {
+    actions: Vec<Action>,
+    // (gameloop, abil_link, abil_cmd_index)
+    last_command: Option<(GameLoop, u16, u8)>,
+    // (gameloop, control_group_index, control_group_update)
+    last_hotkey: Option<(GameLoop, u8, u8)>,
+    // index of the last selection change
+    last_selection: Option<usize>,
+}
+
+impl PlayerActions {
+    fn push(&mut self, event: &TypedEvent<GameEvent>) {
+        let gameloop = event.gameloop;
+        let within = |since: GameLoop, window: u32| gameloop.0.wrapping_sub(since.0) < window;
+
+        let mut effective = true;
+        let mut changes_selection = false;
+        match event.event {
+            GameEvent::SCmdEvent(ref cmd) => {
+                let key = (cmd.abil_link, cmd.abil_cmd_index);
+                if let Some((since, abil_link, abil_cmd_index)) = self.last_command {
+                    effective = !(within(since, REPEAT_WINDOW) && key == (abil_link, abil_cmd_index));
+                }
+                self.last_command = Some((gameloop, key.0, key.1));
+            },
+            GameEvent::SSelectionDeltaEvent(ref delta) => {
+                changes_selection = delta.control_group_id as usize == ACTIVE_SELECTION;
+            },
+            GameEvent::SControlGroupUpdateEvent(ref update) => {
+                let key = (update.control_group_index, update.control_group_update);
+                if let Some((since, index, kind)) = self.last_hotkey {
+                    effective = !(within(since, REPEAT_WINDOW) && key == (index, kind));
+                }
+                self.last_hotkey = Some((gameloop, key.0, key.1));
+                // getting a group selects it
+                changes_selection = update.control_group_update == 2;
+            },
+            _ => {},
+        }
+
+        if changes_selection {
+            if let Some(idx) = self.last_selection {
+                if within(self.actions[idx].gameloop, SELECTION_WINDOW) {
+                    self.actions[idx].effective = false;
+                }
+            }
+            self.last_selection = Some(self.actions.len());
+        }
+        self.actions.push(Action {
+            gameloop: gameloop,
+            effective: effective,
+        });
+    }
+}
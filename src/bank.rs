@@ -0,0 +1,325 @@
+//! Bank files rebuilt from the bank events a custom map writes, and their
+//! export as SC2 bank XML.
+//!
+//! The events arrive as a flat sequence per player: a file event opens a
+//! bank, section events open a section in it and key events add a key to
+//! the current section.  Value events attach extra named values to the key
+//! before them, which the game uses for keys holding more than one value.
+
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+use std::io::{self, Write};
+
+use typed::{self, Blob, GameEvent, TypedEvent};
+
+/// `m_type` of key and value events, the `c_bankType` constants.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BankValueType {
+    Fixed,
+    Flag,
+    Int,
+    String,
+    Unit,
+    Point,
+    Text,
+    Other(u32),
+}
+
+impl BankValueType {
+    pub fn from_u32(val: u32) -> BankValueType {
+        match val {
+            0 => BankValueType::Fixed,
+            1 => BankValueType::Flag,
+            2 => BankValueType::Int,
+            3 => BankValueType::String,
+            4 => BankValueType::Unit,
+            5 => BankValueType::Point,
+            6 => BankValueType::Text,
+            other => BankValueType::Other(other),
+        }
+    }
+
+    /// The attribute a bank file's `<Value>` element stores this type in.
+    pub fn attribute(&self) -> &'static str {
+        match *self {
+            BankValueType::Fixed => "fixed",
+            BankValueType::Flag => "flag",
+            BankValueType::Int => "int",
+            BankValueType::String => "string",
+            BankValueType::Unit => "unit",
+            BankValueType::Point => "point",
+            BankValueType::Text => "text",
+            BankValueType::Other(_) => "value",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BankValue {
+    pub name: Blob,
+    pub value_type: BankValueType,
+    pub data: Blob,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BankKey {
+    pub name: Blob,
+    pub value_type: BankValueType,
+    pub data: Blob,
+    /// Values from the value events that followed the key.
+    pub values: Vec<BankValue>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BankSection {
+    pub name: Blob,
+    pub keys: Vec<BankKey>,
+}
+
+impl BankSection {
+    pub fn key(&self, name: &[u8]) -> Option<&BankKey> {
+        self.keys.iter().find(|key| key.name.0 == name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BankFile {
+    pub name: Blob,
+    pub sections: Vec<BankSection>,
+}
+
+impl BankFile {
+    pub fn section(&self, name: &[u8]) -> Option<&BankSection> {
+        self.sections.iter().find(|section| section.name.0 == name)
+    }
+
+    /// Writes the bank in the layout the game saves banks in, without the
+    /// signature, which replays do not carry.
+    pub fn write_xml<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        try!(writeln!(writer, "<?xml version=\"1.0\" encoding=\"utf-8\"?>"));
+        try!(writeln!(writer, "<Bank version=\"1\">"));
+        for section in self.sections.iter() {
+            try!(writeln!(writer, "    <Section name=\"{}\">", XmlAttr(&section.name)));
+            for key in section.keys.iter() {
+                try!(writeln!(writer, "        <Key name=\"{}\">", XmlAttr(&key.name)));
+                try!(writeln!(writer, "            <Value {}=\"{}\"/>",
+                    key.value_type.attribute(), XmlAttr(&key.data)));
+                for value in key.values.iter() {
+                    try!(writeln!(writer, "            <Value name=\"{}\" {}=\"{}\"/>",
+                        XmlAttr(&value.name), value.value_type.attribute(), XmlAttr(&value.data)));
+                }
+                try!(writeln!(writer, "        </Key>"));
+            }
+            try!(writeln!(writer, "    </Section>"));
+        }
+        try!(writeln!(writer, "</Bank>"));
+        Ok(())
+    }
+
+    pub fn to_xml(&self) -> String {
+        let mut buf = Vec::new();
+        self.write_xml(&mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+}
+
+/// Escapes a blob for an attribute value, replacing invalid UTF-8.
+struct XmlAttr<'a>(&'a Blob);
+
+impl<'a> ::std::fmt::Display for XmlAttr<'a> {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        let text = String::from_utf8_lossy(&(self.0).0);
+        for ch in text.chars() {
+            try!(match ch {
+                '&' => f.write_str("&amp;"),
+                '<' => f.write_str("&lt;"),
+                '>' => f.write_str("&gt;"),
+                '"' => f.write_str("&quot;"),
+                '\'' => f.write_str("&apos;"),
+                '\n' => f.write_str("&#10;"),
+                '\r' => f.write_str("&#13;"),
+                '\t' => f.write_str("&#9;"),
+                ch => write!(f, "{}", ch),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// One player's banks, in the order they were first written.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlayerBanks {
+    files: Vec<BankFile>,
+    // indices of the file, section and key being written
+    file: Option<usize>,
+    section: Option<usize>,
+    key: Option<usize>,
+}
+
+impl PlayerBanks {
+    pub fn files(&self) -> &[BankFile] {
+        &self.files
+    }
+
+    pub fn file(&self, name: &[u8]) -> Option<&BankFile> {
+        self.files.iter().find(|file| file.name.0 == name)
+    }
+
+    /// Starts writing a bank.  A bank written again replaces the earlier
+    /// copy, keeping its place.
+    fn open_file(&mut self, name: &Blob) {
+        let file = BankFile {
+            name: name.clone(),
+            sections: Vec::new(),
+        };
+        let idx = match self.files.iter().position(|file| file.name == *name) {
+            Some(idx) => {
+                self.files[idx] = file;
+                idx
+            },
+            None => {
+                self.files.push(file);
+                self.files.len() - 1
+            },
+        };
+        self.file = Some(idx);
+        self.section = None;
+        self.key = None;
+    }
+
+    fn open_section(&mut self, name: &Blob) {
+        let idx = {
+            let file = match self.file {
+                Some(idx) => &mut self.files[idx],
+                None => return,
+            };
+            match file.sections.iter().position(|section| section.name == *name) {
+                Some(idx) => idx,
+                None => {
+                    file.sections.push(BankSection {
+                        name: name.clone(),
+                        keys: Vec::new(),
+                    });
+                    file.sections.len() - 1
+                },
+            }
+        };
+        self.section = Some(idx);
+        self.key = None;
+    }
+
+    fn current_section(&mut self) -> Option<&mut BankSection> {
+        match (self.file, self.section) {
+            (Some(file), Some(section)) => Some(&mut self.files[file].sections[section]),
+            _ => None,
+        }
+    }
+
+    /// Adds a key to the open section.  A key written twice keeps its last
+    /// value.
+    fn add_key(&mut self, key: BankKey) {
+        let idx = {
+            let section = match self.current_section() {
+                Some(section) => section,
+                None => return,
+            };
+            match section.keys.iter().position(|old| old.name == key.name) {
+                Some(idx) => {
+                    section.keys[idx] = key;
+                    idx
+                },
+                None => {
+                    section.keys.push(key);
+                    section.keys.len() - 1
+                },
+            }
+        };
+        self.key = Some(idx);
+    }
+
+    fn add_value(&mut self, value: BankValue) {
+        let key = match self.key {
+            Some(key) => key,
+            None => return,
+        };
+        if let Some(section) = self.current_section() {
+            section.keys[key].values.push(value);
+        }
+    }
+}
+
+/// Every player's banks, in user id order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Banks {
+    players: BTreeMap<u32, PlayerBanks>,
+}
+
+impl Banks {
+    pub fn new() -> Banks {
+        Banks { players: BTreeMap::new() }
+    }
+
+    pub fn from_events<I>(events: I) -> typed::Result<Banks>
+        where I: Iterator<Item=typed::Result<TypedEvent<GameEvent>>>,
+    {
+        let mut banks = Banks::new();
+        for event in events {
+            banks.apply(&try!(event));
+        }
+        Ok(banks)
+    }
+
+    /// The banks written in the contents of `replay.game.events`.
+    pub fn from_game_events(buf: &[u8]) -> typed::Result<Banks> {
+        Banks::from_events(typed::game_events(buf))
+    }
+
+    /// Applies one event.  Anything other than a bank event is ignored, as
+    /// are section, key and value events with nothing open to add to.
+    pub fn apply(&mut self, event: &TypedEvent<GameEvent>) {
+        let player = match event.user_id {
+            Some(player) => player,
+            None => return,
+        };
+        match event.event {
+            GameEvent::SBankFileEvent(ref file) => {
+                self.player_mut(player).open_file(&file.name);
+            },
+            GameEvent::SBankSectionEvent(ref section) => {
+                self.player_mut(player).open_section(&section.name);
+            },
+            GameEvent::SBankKeyEvent(ref key) => {
+                self.player_mut(player).add_key(BankKey {
+                    name: key.name.clone(),
+                    value_type: BankValueType::from_u32(key.type_),
+                    data: key.data.clone(),
+                    values: Vec::new(),
+                });
+            },
+            GameEvent::SBankValueEvent(ref value) => {
+                self.player_mut(player).add_value(BankValue {
+                    name: value.name.clone(),
+                    value_type: BankValueType::from_u32(value.type_),
+                    data: value.data.clone(),
+                });
+            },
+            _ => {},
+        }
+    }
+
+    fn player_mut(&mut self, player: u32) -> &mut PlayerBanks {
+        self.players.entry(player).or_insert_with(PlayerBanks::default)
+    }
+
+    pub fn player(&self, player: u32) -> Option<&PlayerBanks> {
+        self.players.get(&player)
+    }
+
+    pub fn players(&self) -> btree_map::Iter<u32, PlayerBanks> {
+        self.players.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+}
@@ -0,0 +1,655 @@
+use std::result;
+use std::collections::BTreeMap;
+
+use serde;
+use serde::de;
+
+use super::format::{
+    TypeInfo,
+    TypeId,
+    Struct,
+    StructField,
+    IntBounds,
+    ChoiceTypeMap,
+};
+use super::versioned_serde::{
+    Error,
+    ErrorCode,
+    Result,
+    StrVisitor,
+};
+use super::value::Value;
+use super::transcode;
+
+/// Bit reader matching s2protocol's `BitPackedBuffer` in big-endian mode:
+/// bits are consumed from the low end of each byte, and earlier bits land
+/// in the more significant positions of the result.
+pub struct BitPackedBuffer {
+    buffer: Vec<u8>,
+    used: usize,
+    next: u8,
+    nextbits: u8,
+}
+
+impl BitPackedBuffer {
+    pub fn new(buf: &[u8]) -> BitPackedBuffer {
+        BitPackedBuffer {
+            buffer: buf.to_vec(),
+            used: 0,
+            next: 0,
+            nextbits: 0,
+        }
+    }
+
+    pub fn done(&self) -> bool {
+        self.nextbits == 0 && self.buffer.len() <= self.used
+    }
+
+    pub fn used_bits(&self) -> usize {
+        self.used * 8 - self.nextbits as usize
+    }
+
+    pub fn byte_align(&mut self) {
+        self.nextbits = 0;
+    }
+
+    fn truncated(&self) -> Error {
+        Error::SyntaxError(ErrorCode::UnexpectedEOF, self.used, self.used)
+    }
+
+    pub fn read_aligned_bytes(&mut self, length: usize) -> Result<&[u8]> {
+        self.byte_align();
+        let start = self.used;
+        if self.buffer.len() < start + length {
+            return Err(self.truncated());
+        }
+        self.used += length;
+        Ok(&self.buffer[start..self.used])
+    }
+
+    /// Reads up to 64 bits.
+    pub fn read_bits(&mut self, bits: u8) -> Result<u64> {
+        assert!(bits <= 64);
+        let mut result: u64 = 0;
+        let mut resultbits: u8 = 0;
+        while resultbits != bits {
+            if self.nextbits == 0 {
+                if self.done() {
+                    return Err(self.truncated());
+                }
+                self.next = self.buffer[self.used];
+                self.used += 1;
+                self.nextbits = 8;
+            }
+            let copybits = ::std::cmp::min(bits - resultbits, self.nextbits);
+            let copy = (self.next as u64) & ((1 << copybits) - 1);
+            result |= copy << (bits - resultbits - copybits);
+            self.next = if copybits == 8 { 0 } else { self.next >> copybits };
+            self.nextbits -= copybits;
+            resultbits += copybits;
+        }
+        Ok(result)
+    }
+
+    pub fn read_unaligned_bytes(&mut self, length: usize) -> Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(length);
+        for _ in 0..length {
+            out.push(try!(self.read_bits(8)) as u8);
+        }
+        Ok(out)
+    }
+
+    /// Reads `length` bits as a big-endian, right-aligned byte string
+    /// holding the same number `read_bits` would produce.  Bit arrays can
+    /// be far wider than 64 bits, so they are not returned as integers.
+    pub fn read_bitarray(&mut self, length: usize) -> Result<Vec<u8>> {
+        let nbytes = (length + 7) / 8;
+        let mut out = vec![0; nbytes];
+        let mut resultbits: usize = 0;
+        while resultbits != length {
+            if self.nextbits == 0 {
+                if self.done() {
+                    return Err(self.truncated());
+                }
+                self.next = self.buffer[self.used];
+                self.used += 1;
+                self.nextbits = 8;
+            }
+            let copybits = ::std::cmp::min(length - resultbits, self.nextbits as usize) as u8;
+            let copy = (self.next as u16) & ((1 << copybits) - 1);
+            let shift = length - resultbits - copybits as usize;
+            let wide = copy << (shift % 8);
+            let idx = nbytes - 1 - shift / 8;
+            out[idx] |= wide as u8;
+            if wide > 0xFF {
+                out[idx - 1] |= (wide >> 8) as u8;
+            }
+            self.next = if copybits == 8 { 0 } else { self.next >> copybits };
+            self.nextbits -= copybits;
+            resultbits += copybits as usize;
+        }
+        Ok(out)
+    }
+}
+
+pub struct Deserializer {
+    buffer: BitPackedBuffer,
+    typeinfos: &'static [TypeInfo],
+    typestack: Vec<&'static TypeInfo>,
+}
+
+impl Deserializer {
+    pub fn new(buf: &[u8], typeinfos: &'static [TypeInfo], root_typeinfo: usize) -> Deserializer {
+        Deserializer {
+            buffer: BitPackedBuffer::new(buf),
+            typeinfos: typeinfos,
+            typestack: vec![&typeinfos[root_typeinfo]],
+        }
+    }
+
+    fn top_typeinfo(&self) -> Result<&'static TypeInfo> {
+        match self.typestack.last() {
+            Some(ti) => Ok(*ti),
+            None => Err(Error::SyntaxError(ErrorCode::UnexpectedType, self.buffer.used, self.buffer.used)),
+        }
+    }
+
+    fn typeinfo(&self, typeid: u32) -> Result<&'static TypeInfo> {
+        match self.typeinfos.get(typeid as usize) {
+            Some(ti) => Ok(ti),
+            None => Err(Error::SyntaxError(ErrorCode::UnexpectedType, self.buffer.used, self.buffer.used)),
+        }
+    }
+
+    fn read_int(&mut self, bounds: IntBounds) -> Result<i64> {
+        let raw = try!(self.buffer.read_bits(bounds.bitlen));
+        Ok(bounds.min.wrapping_add(raw as i64))
+    }
+
+    /// Reals are stored as big-endian bytes, read unaligned.
+    fn read_real32(&mut self) -> Result<f32> {
+        let buf = try!(self.buffer.read_unaligned_bytes(4));
+        let raw = buf.iter().fold(0u32, |acc, &b| acc << 8 | b as u32);
+        Ok(unsafe { ::std::mem::transmute(raw) })
+    }
+
+    fn read_real64(&mut self) -> Result<f64> {
+        let buf = try!(self.buffer.read_unaligned_bytes(8));
+        let raw = buf.iter().fold(0u64, |acc, &b| acc << 8 | b as u64);
+        Ok(unsafe { ::std::mem::transmute(raw) })
+    }
+
+    fn visit_typeinfo<V>(&mut self, typeinfo: &'static TypeInfo, visitor: V) -> Result<V::Value>
+        where V: de::Visitor,
+    {
+        self.typestack.push(typeinfo);
+        let rv = de::Deserializer::visit(self, visitor);
+        self.typestack.pop().unwrap();
+        rv
+    }
+
+    /// Decodes the current type into a `Value`, keeping the choice, FourCC
+    /// and bit array distinctions that the serde data model loses.
+    pub fn read_value(&mut self) -> Result<Value> {
+        let typeinfo = try!(self.top_typeinfo());
+        self.read_typeinfo_value(typeinfo)
+    }
+
+    /// Decodes the next value as `typeid`, regardless of the root type.
+    /// Event streams are a sequence of differently typed instances.
+    pub fn read_instance(&mut self, typeid: u32) -> Result<Value> {
+        let typeinfo = try!(self.typeinfo(typeid));
+        self.read_typeinfo_value(typeinfo)
+    }
+
+    pub fn done(&self) -> bool {
+        self.buffer.done()
+    }
+
+    pub fn used_bits(&self) -> usize {
+        self.buffer.used_bits()
+    }
+
+    pub fn byte_align(&mut self) {
+        self.buffer.byte_align()
+    }
+
+    fn read_typeinfo_value(&mut self, typeinfo: &'static TypeInfo) -> Result<Value> {
+        match *typeinfo {
+            TypeInfo::Array { bounds, typeid } => {
+                let length = try!(self.read_int(bounds)) as usize;
+                let item_ti = try!(self.typeinfo(typeid));
+                let mut out = Vec::with_capacity(length);
+                for _ in 0..length {
+                    out.push(try!(self.read_typeinfo_value(item_ti)));
+                }
+                Ok(Value::Array(out))
+            },
+            TypeInfo::BitArray { len } => {
+                let length = try!(self.read_int(len)) as usize;
+                let bits = try!(self.buffer.read_bitarray(length));
+                Ok(Value::BitArray(length, bits))
+            },
+            TypeInfo::Blob { len } => {
+                let length = try!(self.read_int(len)) as usize;
+                let buf = try!(self.buffer.read_aligned_bytes(length)).to_vec();
+                Ok(match String::from_utf8(buf) {
+                    Ok(str_val) => Value::String(str_val),
+                    Err(err) => Value::Bytes(err.into_bytes()),
+                })
+            },
+            TypeInfo::Bool => {
+                Ok(Value::Boolean(try!(self.buffer.read_bits(1)) != 0))
+            },
+            TypeInfo::Choice { bounds, ref types } => {
+                let tag = try!(self.read_int(bounds));
+                let &(name, typeid) = match types.get(&(tag as u32)) {
+                    Some(field) => field,
+                    None => return Err(Error::SyntaxError(
+                        ErrorCode::InvalidTag(tag as i32), self.buffer.used, self.buffer.used)),
+                };
+                let field_ti = try!(self.typeinfo(typeid));
+                let val = try!(self.read_typeinfo_value(field_ti));
+                Ok(Value::Choice(name.to_string(), Box::new(val)))
+            },
+            TypeInfo::FourCC => {
+                let buf = try!(self.buffer.read_unaligned_bytes(4));
+                Ok(Value::FourCC([buf[0], buf[1], buf[2], buf[3]]))
+            },
+            TypeInfo::Int { bounds } => {
+                let val = try!(self.read_int(bounds));
+                Ok(if bounds.min < 0 { Value::I64(val) } else { Value::U64(val as u64) })
+            },
+            TypeInfo::Null => Ok(Value::Null),
+            TypeInfo::Optional { typeid } => {
+                if try!(self.buffer.read_bits(1)) == 0 {
+                    return Ok(Value::Null);
+                }
+                let inner_ti = try!(self.typeinfo(typeid));
+                let val = try!(self.read_typeinfo_value(inner_ti));
+                Ok(Value::Optional(Box::new(val)))
+            },
+            TypeInfo::Real32 => Ok(Value::F64(try!(self.read_real32()) as f64)),
+            TypeInfo::Real64 => Ok(Value::F64(try!(self.read_real64()))),
+            TypeInfo::Struct(st) => {
+                let mut out = BTreeMap::new();
+                for &(name, typeid, _) in st.fields.iter() {
+                    let field_ti = try!(self.typeinfo(typeid));
+                    let val = try!(self.read_typeinfo_value(field_ti));
+                    if name != "__parent" {
+                        out.insert(name.to_string(), val);
+                        continue;
+                    }
+                    match val {
+                        Value::Dict(parent) => out.extend(parent.into_iter()),
+                        val => {
+                            if st.fields.len() == 1 {
+                                return Ok(val);
+                            }
+                            out.insert(name.to_string(), val);
+                        }
+                    }
+                }
+                Ok(Value::Dict(out))
+            },
+        }
+    }
+}
+
+impl transcode::Source for Deserializer {
+    fn typeinfo(&self, typeid: TypeId) -> Result<&'static TypeInfo> {
+        Deserializer::typeinfo(self, typeid)
+    }
+
+    fn read_array_len(&mut self, bounds: IntBounds) -> Result<usize> {
+        Ok(try!(self.read_int(bounds)) as usize)
+    }
+
+    fn read_choice(&mut self, bounds: IntBounds, types: &'static ChoiceTypeMap)
+        -> Result<Option<(&'static str, TypeId)>>
+    {
+        let tag = try!(self.read_int(bounds));
+        match types.get(&(tag as u32)) {
+            Some(&variant) => Ok(Some(variant)),
+            None => Err(Error::SyntaxError(
+                ErrorCode::InvalidTag(tag as i32), self.buffer.used, self.buffer.used)),
+        }
+    }
+
+    fn read_optional(&mut self) -> Result<bool> {
+        Ok(try!(self.buffer.read_bits(1)) != 0)
+    }
+
+    fn read_struct_len(&mut self, def: Struct) -> Result<usize> {
+        Ok(def.fields.len())
+    }
+
+    fn read_struct_field(&mut self, def: Struct, idx: usize) -> Result<Option<&'static StructField>> {
+        Ok(Some(&def.fields[idx]))
+    }
+
+    fn read_scalar(&mut self, typeinfo: &'static TypeInfo) -> Result<Value> {
+        self.read_typeinfo_value(typeinfo)
+    }
+}
+
+impl serde::de::Deserializer for Deserializer {
+    type Error = Error;
+
+    #[inline]
+    fn visit<V>(&mut self, mut visitor: V) -> Result<V::Value>
+        where V: serde::de::Visitor,
+    {
+        let typeinfo = try!(self.top_typeinfo());
+        match *typeinfo {
+            TypeInfo::Array { bounds, typeid } => {
+                let length = try!(self.read_int(bounds)) as usize;
+                let item_ti = try!(self.typeinfo(typeid));
+                visitor.visit_seq(ArrayVisitor::new(self, length, item_ti))
+            },
+            TypeInfo::BitArray { len } => {
+                let length = try!(self.read_int(len)) as usize;
+                let bits = try!(self.buffer.read_bitarray(length));
+                visitor.visit_seq(BitArrayVisitor::new(length, bits))
+            },
+            TypeInfo::Blob { len } => {
+                let length = try!(self.read_int(len)) as usize;
+                let buf = try!(self.buffer.read_aligned_bytes(length));
+                match ::std::str::from_utf8(buf) {
+                    Ok(str_val) => visitor.visit_str(str_val),
+                    Err(_) => visitor.visit_bytes(buf),
+                }
+            },
+            TypeInfo::Bool => {
+                let val = try!(self.buffer.read_bits(1));
+                visitor.visit_bool(val != 0)
+            },
+            TypeInfo::Choice { bounds, ref types } => {
+                let tag = try!(self.read_int(bounds));
+                let &(name, typeid) = match types.get(&(tag as u32)) {
+                    Some(field) => field,
+                    None => return Err(Error::SyntaxError(
+                        ErrorCode::InvalidTag(tag as i32), self.buffer.used, self.buffer.used)),
+                };
+                let field_ti = try!(self.typeinfo(typeid));
+                visitor.visit_map(ChoiceVisitor::new(self, name, field_ti))
+            },
+            TypeInfo::FourCC => {
+                let buf = try!(self.buffer.read_unaligned_bytes(4));
+                match ::std::str::from_utf8(&buf) {
+                    Ok(str_val) => visitor.visit_str(str_val),
+                    Err(_) => visitor.visit_bytes(&buf),
+                }
+            },
+            TypeInfo::Int { bounds } => {
+                let val = try!(self.read_int(bounds));
+                if bounds.min < 0 {
+                    visitor.visit_i64(val)
+                } else {
+                    visitor.visit_u64(val as u64)
+                }
+            },
+            TypeInfo::Null => visitor.visit_unit(),
+            TypeInfo::Optional { typeid } => {
+                let is_some = try!(self.buffer.read_bits(1)) != 0;
+                if !is_some {
+                    return visitor.visit_none();
+                }
+                let typeinfo = try!(self.typeinfo(typeid));
+                self.typestack.push(typeinfo);
+                let result = visitor.visit_some(self);
+                self.typestack.pop().unwrap();
+                result
+            },
+            TypeInfo::Real32 => {
+                let val = try!(self.read_real32());
+                visitor.visit_f32(val)
+            },
+            TypeInfo::Real64 => {
+                let val = try!(self.read_real64());
+                visitor.visit_f64(val)
+            },
+            TypeInfo::Struct(st) => {
+                // A struct whose only field is its parent is the parent.
+                if st.fields.len() == 1 && st.fields[0].0 == "__parent" {
+                    let parent_ti = try!(self.typeinfo(st.fields[0].1));
+                    return self.visit_typeinfo(parent_ti, visitor);
+                }
+                visitor.visit_map(StructVisitor::new(self, st))
+            },
+        }
+    }
+}
+
+/// Bit-packed structs carry no tags or lengths; every field is present,
+/// in declaration order.
+struct StructVisitor<'a> {
+    de: &'a mut Deserializer,
+    def: Struct,
+    offset: usize,
+}
+
+impl<'a> StructVisitor<'a> {
+    fn new(de: &'a mut Deserializer, def: Struct) -> Self {
+        StructVisitor {
+            de: de,
+            def: def,
+            offset: 0,
+        }
+    }
+}
+
+impl<'a> de::MapVisitor for StructVisitor<'a> {
+    type Error = Error;
+
+    fn visit_key<K>(&mut self) -> Result<Option<K>>
+        where K: de::Deserialize,
+    {
+        if self.def.fields.len() == self.offset {
+            return Ok(None);
+        }
+        let (name, _, _) = self.def.fields[self.offset];
+        de::Deserialize::deserialize(&mut StrVisitor(name)).map(Some)
+    }
+
+    fn visit_value<V>(&mut self) -> Result<V>
+        where V: de::Deserialize,
+    {
+        let (_, typeid, _) = self.def.fields[self.offset];
+        let typeinfo = try!(self.de.typeinfo(typeid));
+        self.offset += 1;
+        self.de.typestack.push(typeinfo);
+        let rv = de::Deserialize::deserialize(self.de);
+        self.de.typestack.pop().unwrap();
+        rv
+    }
+
+    fn end(&mut self) -> Result<()> {
+        if self.def.fields.len() != self.offset {
+            panic!("internal error: bad number of values iterated");
+        }
+        Ok(())
+    }
+
+    fn missing_field<V>(&mut self, _field: &'static str) -> Result<V>
+        where V: de::Deserialize,
+    {
+        let mut de = de::value::ValueDeserializer::into_deserializer(());
+        Ok(try!(de::Deserialize::deserialize(&mut de)))
+    }
+}
+
+/// Presents a choice as a single-entry map of `{variant_name: value}`,
+/// which is how s2protocol represents it.
+struct ChoiceVisitor<'a> {
+    de: &'a mut Deserializer,
+    name: &'static str,
+    typeinfo: &'static TypeInfo,
+    state: u8,
+}
+
+impl<'a> ChoiceVisitor<'a> {
+    fn new(de: &'a mut Deserializer, name: &'static str, typeinfo: &'static TypeInfo) -> Self {
+        ChoiceVisitor {
+            de: de,
+            name: name,
+            typeinfo: typeinfo,
+            state: 0,
+        }
+    }
+}
+
+impl<'a> de::MapVisitor for ChoiceVisitor<'a> {
+    type Error = Error;
+
+    fn visit_key<K>(&mut self) -> Result<Option<K>>
+        where K: de::Deserialize,
+    {
+        if self.state != 0 {
+            return Ok(None);
+        }
+        self.state = 1;
+        de::Deserialize::deserialize(&mut StrVisitor(self.name)).map(Some)
+    }
+
+    fn visit_value<V>(&mut self) -> Result<V>
+        where V: de::Deserialize,
+    {
+        self.state = 2;
+        self.de.typestack.push(self.typeinfo);
+        let rv = de::Deserialize::deserialize(self.de);
+        self.de.typestack.pop().unwrap();
+        rv
+    }
+
+    fn end(&mut self) -> Result<()> {
+        if self.state != 2 {
+            panic!("internal error: choice value not visited");
+        }
+        Ok(())
+    }
+}
+
+struct ArrayVisitor<'a> {
+    de: &'a mut Deserializer,
+    length: usize,
+    offset: usize,
+    item_ti: &'static TypeInfo,
+}
+
+impl<'a> ArrayVisitor<'a> {
+    fn new(de: &'a mut Deserializer, length: usize, item_ti: &'static TypeInfo) -> Self {
+        ArrayVisitor {
+            de: de,
+            length: length,
+            offset: 0,
+            item_ti: item_ti,
+        }
+    }
+}
+
+impl<'a> de::SeqVisitor for ArrayVisitor<'a> {
+    type Error = Error;
+
+    fn visit<T>(&mut self) -> Result<Option<T>> where T: de::Deserialize {
+        if self.length == self.offset {
+            return Ok(None);
+        }
+        self.de.typestack.push(self.item_ti);
+        let rv = de::Deserialize::deserialize(self.de);
+        self.de.typestack.pop().unwrap();
+        self.offset += 1;
+        rv.map(Some)
+    }
+
+    fn end(&mut self) -> Result<()> {
+        if self.length != self.offset {
+            panic!("internal error: bad number of values iterated");
+        }
+        Ok(())
+    }
+}
+
+/// Presents a bit array as the `(length, bits)` pair s2protocol uses, with
+/// the bits as a big-endian byte string.
+struct BitArrayVisitor {
+    length: usize,
+    bits: Vec<u8>,
+    offset: usize,
+}
+
+impl BitArrayVisitor {
+    fn new(length: usize, bits: Vec<u8>) -> Self {
+        BitArrayVisitor {
+            length: length,
+            bits: bits,
+            offset: 0,
+        }
+    }
+}
+
+impl de::SeqVisitor for BitArrayVisitor {
+    type Error = Error;
+
+    fn visit<T>(&mut self) -> Result<Option<T>> where T: de::Deserialize {
+        self.offset += 1;
+        match self.offset {
+            1 => de::Deserialize::deserialize(&mut U64Visitor(self.length as u64)).map(Some),
+            2 => de::Deserialize::deserialize(&mut BytesVisitor(&self.bits)).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn end(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// just visits an integer
+struct U64Visitor(u64);
+
+impl de::Deserializer for U64Visitor {
+    type Error = Error;
+
+    #[inline]
+    fn visit<V>(&mut self, mut visitor: V) -> Result<V::Value> where V: de::Visitor {
+        visitor.visit_u64(self.0)
+    }
+}
+
+/// just visits a byte string
+struct BytesVisitor<'a>(&'a [u8]);
+
+impl<'a> de::Deserializer for BytesVisitor<'a> {
+    type Error = Error;
+
+    #[inline]
+    fn visit<V>(&mut self, mut visitor: V) -> Result<V::Value> where V: de::Visitor {
+        let res0: result::Result<V::Value, Error> = visitor.visit_bytes(self.0);
+        res0.or_else(|_| visitor.visit_string(format!("{:?}", self.0)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitPackedBuffer;
+
+    #[test]
+    fn test_read_bits() {
+        let mut buf = BitPackedBuffer::new(&[0xb5, 0x01, 0xff]);
+        assert_eq!(buf.read_bits(3).unwrap(), 0x5);
+        assert_eq!(buf.read_bits(5).unwrap(), 0x16);
+        assert_eq!(buf.used_bits(), 8);
+        // crosses into the third byte; the earlier byte is more significant
+        assert_eq!(buf.read_bits(12).unwrap(), 0x01f);
+        buf.byte_align();
+        assert!(buf.done());
+        assert!(buf.read_bits(1).is_err());
+    }
+
+    #[test]
+    fn test_read_bitarray() {
+        let mut buf = BitPackedBuffer::new(&[0x03, 0xaa, 0x55]);
+        assert_eq!(buf.read_bitarray(18).unwrap(), vec![0x00, 0x0e, 0xa9]);
+    }
+}
@@ -0,0 +1,177 @@
+//! Each player's camera over time, rebuilt from `SCameraUpdateEvent`s.
+
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+use std::time::Duration;
+
+use common::GameLoop;
+use typed::{self, GameEvent, TypedEvent};
+use format::protocol15405_events::SCameraUpdateEvent;
+
+/// Map cells the camera has to move for a new screen or a jump.
+pub const SCREEN_DISTANCE: f64 = 15.0;
+
+/// Where one player's camera was from `gameloop` until their next update.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CameraSample {
+    pub gameloop: GameLoop,
+    /// Map cells; the event stores 1/256ths of a cell.
+    pub x: f32,
+    pub y: f32,
+    /// Raw `m_distance`, `m_pitch` and `m_yaw`.  Updates that leave them
+    /// out keep the previous value, so these are `None` only until the
+    /// first update that sets them.
+    pub distance: Option<u16>,
+    pub pitch: Option<u16>,
+    pub yaw: Option<u16>,
+}
+
+impl CameraSample {
+    fn distance_to(&self, x: f32, y: f32) -> f64 {
+        let dx = self.x as f64 - x as f64;
+        let dy = self.y as f64 - y as f64;
+        (dx * dx + dy * dy).sqrt()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlayerCamera {
+    samples: Vec<CameraSample>,
+}
+
+impl PlayerCamera {
+    fn push(&mut self, gameloop: GameLoop, event: &SCameraUpdateEvent) {
+        let prev = self.samples.last().cloned();
+        self.samples.push(CameraSample {
+            gameloop: gameloop,
+            x: event.target.x as f32 / 256.0,
+            y: event.target.y as f32 / 256.0,
+            distance: event.distance.or(prev.and_then(|p| p.distance)),
+            pitch: event.pitch.or(prev.and_then(|p| p.pitch)),
+            yaw: event.yaw.or(prev.and_then(|p| p.yaw)),
+        });
+    }
+
+    pub fn samples(&self) -> &[CameraSample] {
+        &self.samples
+    }
+
+    /// The camera as of `gameloop`, or `None` before the first update.
+    pub fn at(&self, gameloop: GameLoop) -> Option<&CameraSample> {
+        // index of the first sample after gameloop
+        let mut lo = 0;
+        let mut hi = self.samples.len();
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.samples[mid].gameloop <= gameloop {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        if lo == 0 {
+            None
+        } else {
+            Some(&self.samples[lo - 1])
+        }
+    }
+
+    /// Updates that moved the camera more than `SCREEN_DISTANCE` at once,
+    /// as with hotkeys and minimap clicks rather than scrolling.
+    pub fn jumps(&self) -> usize {
+        self.samples.windows(2)
+            .filter(|w| w[1].distance_to(w[0].x, w[0].y) > SCREEN_DISTANCE)
+            .count()
+    }
+
+    /// Distinct screens looked at.  A new screen starts once the camera is
+    /// more than `SCREEN_DISTANCE` from where the current one started, so
+    /// a long scroll counts as several.
+    pub fn screens(&self) -> usize {
+        let mut samples = self.samples.iter();
+        let mut anchor = match samples.next() {
+            Some(sample) => sample,
+            None => return 0,
+        };
+        let mut screens = 1;
+        for sample in samples {
+            if sample.distance_to(anchor.x, anchor.y) > SCREEN_DISTANCE {
+                screens += 1;
+                anchor = sample;
+            }
+        }
+        screens
+    }
+
+    /// `screens()` per minute of `duration`, which is usually the game's
+    /// length in real or game time.
+    pub fn screens_per_minute(&self, duration: Duration) -> f64 {
+        let secs = duration.as_secs() as f64 + duration.subsec_nanos() as f64 / 1e9;
+        if secs == 0.0 {
+            return 0.0;
+        }
+        self.screens() as f64 * 60.0 / secs
+    }
+
+    /// Gameloops spent with the camera in each square of a grid of
+    /// `region_size` cells, keyed by column and row.  The last position is
+    /// held until `end`.
+    pub fn time_per_region(&self, region_size: f32, end: GameLoop) -> BTreeMap<(u32, u32), u32> {
+        let mut regions = BTreeMap::new();
+        for (idx, sample) in self.samples.iter().enumerate() {
+            let until = match self.samples.get(idx + 1) {
+                Some(next) => next.gameloop,
+                None => end,
+            };
+            if until <= sample.gameloop {
+                continue;
+            }
+            let region = ((sample.x / region_size) as u32, (sample.y / region_size) as u32);
+            *regions.entry(region).or_insert(0) += until.0 - sample.gameloop.0;
+        }
+        regions
+    }
+}
+
+/// Every player's camera, in user id order.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CameraTimeline {
+    players: BTreeMap<u32, PlayerCamera>,
+}
+
+impl CameraTimeline {
+    pub fn new() -> CameraTimeline {
+        CameraTimeline { players: BTreeMap::new() }
+    }
+
+    pub fn from_events<I>(events: I) -> typed::Result<CameraTimeline>
+        where I: Iterator<Item=typed::Result<TypedEvent<GameEvent>>>,
+    {
+        let mut timeline = CameraTimeline::new();
+        for event in events {
+            timeline.apply(&try!(event));
+        }
+        Ok(timeline)
+    }
+
+    /// The cameras from the contents of `replay.game.events`.
+    pub fn from_game_events(buf: &[u8]) -> typed::Result<CameraTimeline> {
+        CameraTimeline::from_events(typed::game_events(buf))
+    }
+
+    /// Adds a camera update; other events are ignored.  Events have to
+    /// arrive in gameloop order.
+    pub fn apply(&mut self, event: &TypedEvent<GameEvent>) {
+        if let (Some(player), &GameEvent::SCameraUpdateEvent(ref camera)) = (event.user_id, &event.event) {
+            self.players.entry(player).or_insert_with(PlayerCamera::default).push(event.gameloop, camera);
+        }
+    }
+
+    pub fn player(&self, player: u32) -> Option<&PlayerCamera> {
+        self.players.get(&player)
+    }
+
+    pub fn players(&self) -> btree_map::Iter<u32, PlayerCamera> {
+        self.players.iter()
+    }
+}
@@ -0,0 +1,90 @@
+//! Cheats used in a replay, from `SGameCheatEvent`, `SBroadcastCheatEvent`
+//! and the cheat options in `SUserOptionsEvent`.
+
+use common::GameLoop;
+use typed::{self, Blob, GameEvent, TypedEvent};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CheatKind {
+    /// `SGameCheatEvent`, a cheat applied to the issuing player's game.
+    Game,
+    /// `SBroadcastCheatEvent`, a cheat sent to every player.
+    Broadcast,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cheat {
+    pub gameloop: GameLoop,
+    pub player: Option<u32>,
+    pub kind: CheatKind,
+    pub verb: Blob,
+    pub arguments: Blob,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CheatReport {
+    pub cheats: Vec<Cheat>,
+    /// Players whose options allowed development or multiplayer cheats,
+    /// whether or not they used any.
+    pub cheats_enabled: Vec<u32>,
+}
+
+impl CheatReport {
+    pub fn new() -> CheatReport {
+        CheatReport::default()
+    }
+
+    pub fn from_events<I>(events: I) -> typed::Result<CheatReport>
+        where I: Iterator<Item=typed::Result<TypedEvent<GameEvent>>>,
+    {
+        let mut report = CheatReport::new();
+        for event in events {
+            report.apply(&try!(event));
+        }
+        Ok(report)
+    }
+
+    /// The report for the contents of `replay.game.events`.
+    pub fn from_game_events(buf: &[u8]) -> typed::Result<CheatReport> {
+        CheatReport::from_events(typed::game_events(buf))
+    }
+
+    pub fn apply(&mut self, event: &TypedEvent<GameEvent>) {
+        let (kind, verb, arguments) = match event.event {
+            GameEvent::SGameCheatEvent(ref cheat) => {
+                (CheatKind::Game, &cheat.data.verb, &cheat.data.arguments)
+            },
+            GameEvent::SBroadcastCheatEvent(ref cheat) => {
+                (CheatKind::Broadcast, &cheat.verb, &cheat.arguments)
+            },
+            GameEvent::SUserOptionsEvent(ref options) => {
+                let enabled = options.development_cheats_enabled || options.multiplayer_cheats_enabled;
+                if let (true, Some(player)) = (enabled, event.user_id) {
+                    if !self.cheats_enabled.contains(&player) {
+                        self.cheats_enabled.push(player);
+                    }
+                }
+                return;
+            },
+            _ => return,
+        };
+        self.cheats.push(Cheat {
+            gameloop: event.gameloop,
+            player: event.user_id,
+            kind: kind,
+            verb: verb.clone(),
+            arguments: arguments.clone(),
+        });
+    }
+
+    /// Cheats issued by `player`.
+    pub fn by_player(&self, player: u32) -> Vec<&Cheat> {
+        self.cheats.iter().filter(|cheat| cheat.player == Some(player)).collect()
+    }
+
+    /// Whether nothing suggests cheats: none were used and none were
+    /// enabled.
+    pub fn is_clean(&self) -> bool {
+        self.cheats.is_empty() && self.cheats_enabled.is_empty()
+    }
+}
@@ -1,4 +1,6 @@
 pub mod color;
 pub mod player;
+pub mod time;
 
-pub use self::color::Color;
\ No newline at end of file
+pub use self::color::Color;
+pub use self::time::{FileTime, UtcOffset, GameSpeed, GameLoop, MinSec};
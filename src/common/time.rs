@@ -0,0 +1,249 @@
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Game loops advance at 16 per game second, whatever the game speed.
+pub const GAME_LOOPS_PER_SECOND: u32 = 16;
+
+/// FILETIME ticks are 100 nanoseconds.
+const TICKS_PER_SECOND: i64 = 10_000_000;
+
+/// 1970-01-01T00:00:00Z expressed as a FILETIME.
+const UNIX_EPOCH_TICKS: i64 = 116_444_736_000_000_000;
+
+/// No real time zone is more than a day away from UTC.
+const MAX_OFFSET_TICKS: i64 = 24 * 3600 * TICKS_PER_SECOND;
+
+fn ticks_to_duration(ticks: u64) -> Duration {
+    let per_second = TICKS_PER_SECOND as u64;
+    Duration::new(ticks / per_second, ((ticks % per_second) * 100) as u32)
+}
+
+/// A Windows FILETIME (100ns ticks since 1601-01-01 UTC), as stored in
+/// `m_timeUTC`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileTime(pub i64);
+
+impl FileTime {
+    /// Seconds since the unix epoch, rounded towards negative infinity.
+    pub fn unix_timestamp(&self) -> i64 {
+        let ticks = self.0 - UNIX_EPOCH_TICKS;
+        let mut secs = ticks / TICKS_PER_SECOND;
+        if ticks % TICKS_PER_SECOND < 0 {
+            secs -= 1;
+        }
+        secs
+    }
+
+    pub fn to_system_time(&self) -> SystemTime {
+        let ticks = self.0 - UNIX_EPOCH_TICKS;
+        if ticks >= 0 {
+            UNIX_EPOCH + ticks_to_duration(ticks as u64)
+        } else {
+            UNIX_EPOCH - ticks_to_duration(ticks.wrapping_neg() as u64)
+        }
+    }
+
+    pub fn offset_by(&self, offset: UtcOffset) -> FileTime {
+        FileTime(self.0 + offset.0)
+    }
+}
+
+/// The difference between the recording machine's local time and UTC,
+/// in FILETIME ticks, as stored in `m_timeLocalOffset`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UtcOffset(pub i64);
+
+impl UtcOffset {
+    /// Builds the offset from the two `m_time*` fields of the replay details.
+    ///
+    /// Early builds (15405 among them) store the local FILETIME in
+    /// `m_timeLocalOffset` rather than an offset, so anything larger than
+    /// a day is taken to be a timestamp and differenced against `m_timeUTC`.
+    pub fn from_details(time_utc: i64, time_local_offset: i64) -> UtcOffset {
+        if time_local_offset.abs() > MAX_OFFSET_TICKS {
+            UtcOffset(time_local_offset - time_utc)
+        } else {
+            UtcOffset(time_local_offset)
+        }
+    }
+
+    /// The offset rounded to the nearest second.
+    pub fn seconds(&self) -> i64 {
+        let half = TICKS_PER_SECOND / 2;
+        if self.0 >= 0 {
+            (self.0 + half) / TICKS_PER_SECOND
+        } else {
+            (self.0 - half) / TICKS_PER_SECOND
+        }
+    }
+}
+
+impl fmt::Display for UtcOffset {
+    /// Formats as `+HH:MM`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let seconds = self.seconds();
+        let sign = if seconds < 0 { '-' } else { '+' };
+        let minutes = seconds.abs() / 60;
+        write!(f, "{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+    }
+}
+
+/// `m_gameSpeed`, in the order the game encodes it.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum GameSpeed {
+    Slower,
+    Slow,
+    Normal,
+    Fast,
+    Faster,
+}
+
+impl GameSpeed {
+    pub fn from_u8(val: u8) -> Option<GameSpeed> {
+        match val {
+            0 => Some(GameSpeed::Slower),
+            1 => Some(GameSpeed::Slow),
+            2 => Some(GameSpeed::Normal),
+            3 => Some(GameSpeed::Fast),
+            4 => Some(GameSpeed::Faster),
+            _ => None,
+        }
+    }
+
+    pub fn as_u8(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            GameSpeed::Slower => "Slower",
+            GameSpeed::Slow => "Slow",
+            GameSpeed::Normal => "Normal",
+            GameSpeed::Fast => "Fast",
+            GameSpeed::Faster => "Faster",
+        }
+    }
+
+    /// Game seconds elapsed per real second, in tenths.
+    fn rate_tenths(&self) -> u64 {
+        match *self {
+            GameSpeed::Slower => 6,
+            GameSpeed::Slow => 8,
+            GameSpeed::Normal => 10,
+            GameSpeed::Fast => 12,
+            GameSpeed::Faster => 14,
+        }
+    }
+}
+
+impl fmt::Display for GameSpeed {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// A point in the game, counted in game loops from the start.  This is what
+/// `m_elapsedGameLoops` and the per-event gameloop counters hold.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct GameLoop(pub u32);
+
+impl GameLoop {
+    pub fn from_game_time(time: Duration) -> GameLoop {
+        let micros = time.as_secs() * 1_000_000 + (time.subsec_nanos() / 1000) as u64;
+        GameLoop((micros * GAME_LOOPS_PER_SECOND as u64 / 1_000_000) as u32)
+    }
+
+    pub fn from_real_time(time: Duration, speed: GameSpeed) -> GameLoop {
+        let micros = time.as_secs() * 1_000_000 + (time.subsec_nanos() / 1000) as u64;
+        let loops = micros * GAME_LOOPS_PER_SECOND as u64 * speed.rate_tenths() / 10_000_000;
+        GameLoop(loops as u32)
+    }
+
+    /// Elapsed time on the in-game clock.
+    pub fn game_time(&self) -> Duration {
+        let per_second = GAME_LOOPS_PER_SECOND as u64;
+        let loops = self.0 as u64;
+        let nanos = (loops % per_second) * 1_000_000_000 / per_second;
+        Duration::new(loops / per_second, nanos as u32)
+    }
+
+    /// Elapsed wall-clock time when played at `speed`.
+    pub fn real_time(&self, speed: GameSpeed) -> Duration {
+        let divisor = GAME_LOOPS_PER_SECOND as u64 * speed.rate_tenths();
+        let micros = self.0 as u64 * 10_000_000 / divisor;
+        Duration::new(micros / 1_000_000, ((micros % 1_000_000) * 1000) as u32)
+    }
+}
+
+impl fmt::Display for GameLoop {
+    /// Formats the in-game clock as `mm:ss`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&MinSec(self.game_time()), f)
+    }
+}
+
+/// Formats a duration as `mm:ss`, truncating fractional seconds.  Minutes
+/// are not wrapped into hours, matching the in-game clock.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MinSec(pub Duration);
+
+impl fmt::Display for MinSec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let secs = self.0.as_secs();
+        write!(f, "{:02}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+    use super::{FileTime, UtcOffset, GameLoop, GameSpeed, MinSec};
+
+    // m_timeUTC and m_timeLocalOffset from testdata/base_build_15405/replay.details
+    const TIME_UTC: i64 = 129257541208634645;
+    const TIME_LOCAL_OFFSET: i64 = 129257649208634648;
+
+    #[test]
+    fn test_filetime() {
+        let time = FileTime(TIME_UTC);
+        assert_eq!(time.unix_timestamp(), 1281280520);
+
+        let since_epoch = time.to_system_time().duration_since(UNIX_EPOCH).unwrap();
+        assert_eq!(since_epoch, Duration::new(1281280520, 863464500));
+    }
+
+    #[test]
+    fn test_utc_offset() {
+        let offset = UtcOffset::from_details(TIME_UTC, TIME_LOCAL_OFFSET);
+        assert_eq!(offset.seconds(), 10800);
+        assert_eq!(format!("{}", offset), "+03:00");
+
+        let offset = UtcOffset::from_details(TIME_UTC, -5 * 3600 * 10_000_000);
+        assert_eq!(format!("{}", offset), "-05:00");
+    }
+
+    #[test]
+    fn test_gameloop() {
+        // m_elapsedGameLoops from testdata/header
+        let elapsed = GameLoop(25243);
+        assert_eq!(format!("{}", elapsed), "26:17");
+        assert_eq!(format!("{}", MinSec(elapsed.real_time(GameSpeed::Faster))), "18:46");
+        assert_eq!(format!("{}", MinSec(elapsed.real_time(GameSpeed::Normal))), "26:17");
+        assert_eq!(elapsed.game_time(), Duration::new(1577, 687500000));
+    }
+
+    #[test]
+    fn test_gameloop_roundtrip() {
+        let five_minutes = Duration::new(300, 0);
+        assert_eq!(GameLoop::from_game_time(five_minutes), GameLoop(4800));
+        assert_eq!(GameLoop::from_real_time(five_minutes, GameSpeed::Faster), GameLoop(6720));
+        assert_eq!(GameLoop(6720).real_time(GameSpeed::Faster), five_minutes);
+    }
+
+    #[test]
+    fn test_game_speed() {
+        assert_eq!(GameSpeed::from_u8(4), Some(GameSpeed::Faster));
+        assert_eq!(GameSpeed::from_u8(5), None);
+        assert_eq!(GameSpeed::Slower.as_u8(), 0);
+    }
+}
@@ -0,0 +1,242 @@
+use phf::Map as PhfMap;
+
+use common::GameLoop;
+use format::{TypeId, TypeInfo};
+use format::protocol15405::{
+    TYPEINFOS,
+    SVARUINT32_TYPEID,
+    REPLAY_USERID_TYPEID,
+    GAME_EVENTID_TYPEID,
+    GAME_EVENT_TYPES,
+    MESSAGE_EVENTID_TYPEID,
+    MESSAGE_EVENT_TYPES,
+};
+use versioned_serde::{self, Error, ErrorCode, Result};
+use bitpacked_serde;
+use value::Value;
+
+pub type EventTypes = PhfMap<u32, (u32, &'static str)>;
+
+/// How one event stream is framed: each event is a gameloop delta, an
+/// optional user id, an event id and then the event itself.
+#[derive(Copy, Clone)]
+pub struct EventStream {
+    pub svaruint32_typeid: TypeId,
+    /// `None` for streams without a per-event user, such as tracker events.
+    pub userid_typeid: Option<TypeId>,
+    pub eventid_typeid: TypeId,
+    pub event_types: &'static EventTypes,
+}
+
+impl EventStream {
+    /// `replay.game.events` for protocol 15405.
+    pub fn game_15405() -> EventStream {
+        EventStream {
+            svaruint32_typeid: SVARUINT32_TYPEID as TypeId,
+            userid_typeid: Some(REPLAY_USERID_TYPEID as TypeId),
+            eventid_typeid: GAME_EVENTID_TYPEID as TypeId,
+            event_types: &GAME_EVENT_TYPES,
+        }
+    }
+
+    /// `replay.message.events` for protocol 15405.
+    pub fn message_15405() -> EventStream {
+        EventStream {
+            svaruint32_typeid: SVARUINT32_TYPEID as TypeId,
+            userid_typeid: Some(REPLAY_USERID_TYPEID as TypeId),
+            eventid_typeid: MESSAGE_EVENTID_TYPEID,
+            event_types: &MESSAGE_EVENT_TYPES,
+        }
+    }
+
+    /// `replay.tracker.events`, which has no user id, for a protocol's
+    /// typeids and tracker event types.
+    pub fn tracker(svaruint32_typeid: TypeId, eventid_typeid: TypeId, event_types: &'static EventTypes) -> EventStream {
+        EventStream {
+            svaruint32_typeid: svaruint32_typeid,
+            userid_typeid: None,
+            eventid_typeid: eventid_typeid,
+            event_types: event_types,
+        }
+    }
+
+    /// Reads the framing ahead of an event's body, adding its delta to
+    /// `gameloop`.  The body is left for the caller to decode as
+    /// `head.typeid`.
+    pub fn read_head<D: EventDecoder>(&self, decoder: &mut D, gameloop: &mut u32) -> Result<EventHead> {
+        let delta = try!(decoder.read_instance(self.svaruint32_typeid));
+        let delta = match delta {
+            Value::Choice(_, ref val) => val.as_u64().unwrap_or(0),
+            _ => return Err(syntax_error(decoder, ErrorCode::UnexpectedType)),
+        };
+        *gameloop = gameloop.wrapping_add(delta as u32);
+
+        let user_id = match self.userid_typeid {
+            Some(typeid) => {
+                let user = try!(decoder.read_instance(typeid));
+                match user["m_playerId"].as_u64() {
+                    Ok(id) => Some(id as u32),
+                    Err(()) => return Err(syntax_error(decoder, ErrorCode::UnexpectedType)),
+                }
+            },
+            None => None,
+        };
+
+        let event_id = try!(decoder.read_instance(self.eventid_typeid));
+        let event_id = match event_id.as_u64() {
+            Ok(id) => id as u32,
+            Err(()) => return Err(syntax_error(decoder, ErrorCode::UnexpectedType)),
+        };
+        let &(typeid, name) = match self.event_types.get(&event_id) {
+            Some(event_type) => event_type,
+            None => return Err(syntax_error(decoder, ErrorCode::InvalidTag(event_id as i32))),
+        };
+
+        Ok(EventHead {
+            gameloop: GameLoop(*gameloop),
+            user_id: user_id,
+            event_id: event_id,
+            name: name,
+            typeid: typeid,
+        })
+    }
+}
+
+fn syntax_error<D: EventDecoder>(decoder: &D, code: ErrorCode) -> Error {
+    let offset = decoder.used_bits() / 8;
+    Error::SyntaxError(code, offset, offset)
+}
+
+/// The framing of one event, everything but its body.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EventHead {
+    pub gameloop: GameLoop,
+    pub user_id: Option<u32>,
+    pub event_id: u32,
+    pub name: &'static str,
+    pub typeid: TypeId,
+}
+
+/// The operations event framing needs from either encoding.
+pub trait EventDecoder {
+    fn done(&self) -> bool;
+    fn used_bits(&self) -> usize;
+    fn byte_align(&mut self);
+    fn read_instance(&mut self, typeid: TypeId) -> Result<Value>;
+}
+
+impl EventDecoder for bitpacked_serde::Deserializer {
+    fn done(&self) -> bool {
+        bitpacked_serde::Deserializer::done(self)
+    }
+
+    fn used_bits(&self) -> usize {
+        bitpacked_serde::Deserializer::used_bits(self)
+    }
+
+    fn byte_align(&mut self) {
+        bitpacked_serde::Deserializer::byte_align(self)
+    }
+
+    fn read_instance(&mut self, typeid: TypeId) -> Result<Value> {
+        bitpacked_serde::Deserializer::read_instance(self, typeid)
+    }
+}
+
+impl EventDecoder for versioned_serde::Deserializer {
+    fn done(&self) -> bool {
+        versioned_serde::Deserializer::done(self)
+    }
+
+    fn used_bits(&self) -> usize {
+        versioned_serde::Deserializer::used_bits(self)
+    }
+
+    // versioned instances always end on a byte boundary
+    fn byte_align(&mut self) {}
+
+    fn read_instance(&mut self, typeid: TypeId) -> Result<Value> {
+        versioned_serde::Deserializer::read_instance(self, typeid)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Event {
+    /// Absolute gameloop, the running sum of the stream's deltas.
+    pub gameloop: GameLoop,
+    pub user_id: Option<u32>,
+    pub event_id: u32,
+    pub name: &'static str,
+    pub value: Value,
+    /// Encoded size of the event, including its framing.
+    pub bits: usize,
+}
+
+/// Iterates over the events of one stream.  Decoding stops at the first
+/// error, which is yielded once.
+pub struct EventIter<D> {
+    decoder: D,
+    stream: EventStream,
+    gameloop: u32,
+    failed: bool,
+}
+
+impl<D: EventDecoder> EventIter<D> {
+    pub fn new(decoder: D, stream: EventStream) -> EventIter<D> {
+        EventIter {
+            decoder: decoder,
+            stream: stream,
+            gameloop: 0,
+            failed: false,
+        }
+    }
+
+    fn read_event(&mut self) -> Result<Event> {
+        let start = self.decoder.used_bits();
+        let head = try!(self.stream.read_head(&mut self.decoder, &mut self.gameloop));
+        let value = try!(self.decoder.read_instance(head.typeid));
+        self.decoder.byte_align();
+
+        Ok(Event {
+            gameloop: head.gameloop,
+            user_id: head.user_id,
+            event_id: head.event_id,
+            name: head.name,
+            value: value,
+            bits: self.decoder.used_bits() - start,
+        })
+    }
+}
+
+impl<D: EventDecoder> Iterator for EventIter<D> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Result<Event>> {
+        if self.failed || self.decoder.done() {
+            return None;
+        }
+        let rv = self.read_event();
+        self.failed = rv.is_err();
+        Some(rv)
+    }
+}
+
+/// Events from the contents of `replay.game.events`.
+pub fn game_events(buf: &[u8]) -> EventIter<bitpacked_serde::Deserializer> {
+    let decoder = bitpacked_serde::Deserializer::new(buf, TYPEINFOS, SVARUINT32_TYPEID);
+    EventIter::new(decoder, EventStream::game_15405())
+}
+
+/// Events from the contents of `replay.message.events`.
+pub fn message_events(buf: &[u8]) -> EventIter<bitpacked_serde::Deserializer> {
+    let decoder = bitpacked_serde::Deserializer::new(buf, TYPEINFOS, SVARUINT32_TYPEID);
+    EventIter::new(decoder, EventStream::message_15405())
+}
+
+/// Events from the contents of `replay.tracker.events`.  Protocol 15405
+/// predates tracker events, so the caller supplies the type infos and
+/// stream of a protocol that has them.
+pub fn tracker_events(buf: &[u8], typeinfos: &'static [TypeInfo], stream: EventStream) -> EventIter<versioned_serde::Deserializer> {
+    let decoder = versioned_serde::Deserializer::new(buf, typeinfos, stream.svaruint32_typeid as usize);
+    EventIter::new(decoder, stream)
+}
@@ -2,6 +2,8 @@ use std::io::{self};
 use phf::Map as PhfMap;
 
 pub mod protocol15405;
+pub mod protocol15405_events;
+pub mod protocol15405_messages;
 
 // mod protocol15405_def;
 // mod protocol16561_def;
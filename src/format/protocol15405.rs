@@ -8,6 +8,9 @@ use phf::Map as PhfMap;
 pub static REPLAY_HEADER_TYPEID: usize = 13;
 pub static GAME_EVENTID_TYPEID: usize = 0;
 pub static GAME_DETAILS_TYPEID: usize = 32;
+pub static REPLAY_INITDATA_TYPEID: usize = 55;
+pub static SVARUINT32_TYPEID: usize = 6;
+pub static REPLAY_USERID_TYPEID: usize = 8;
 
 pub static GAME_EVENT_TYPES: PhfMap<u32, (u32, &'static str)> = phf_map! {
     5_u32 => (62, "NNet.Game.SUserFinishedLoadingSyncEvent"),
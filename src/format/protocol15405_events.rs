@@ -0,0 +1,1399 @@
+// Generated by py2rs_events.py; do not edit.
+
+use events::Event;
+use typed::{FromValue, FromEvent, Error, Result, Blob, BitArray, field};
+use value::Value;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GameEvent {
+    SUserFinishedLoadingSyncEvent(SUserFinishedLoadingSyncEvent),
+    SBankFileEvent(SBankFileEvent),
+    SBankSectionEvent(SBankSectionEvent),
+    SBankKeyEvent(SBankKeyEvent),
+    SBankValueEvent(SBankValueEvent),
+    SUserOptionsEvent(SUserOptionsEvent),
+    SSaveGameEvent(SSaveGameEvent),
+    SSaveGameDoneEvent(SSaveGameDoneEvent),
+    SPlayerLeaveEvent(SPlayerLeaveEvent),
+    SGameCheatEvent(SGameCheatEvent),
+    SCmdEvent(SCmdEvent),
+    SSelectionDeltaEvent(SSelectionDeltaEvent),
+    SControlGroupUpdateEvent(SControlGroupUpdateEvent),
+    SSelectionSyncCheckEvent(SSelectionSyncCheckEvent),
+    SResourceTradeEvent(SResourceTradeEvent),
+    STriggerChatMessageEvent(STriggerChatMessageEvent),
+    SAICommunicateEvent(SAICommunicateEvent),
+    SSetAbsoluteGameSpeedEvent(SSetAbsoluteGameSpeedEvent),
+    SAddAbsoluteGameSpeedEvent(SAddAbsoluteGameSpeedEvent),
+    SBroadcastCheatEvent(SBroadcastCheatEvent),
+    SAllianceEvent(SAllianceEvent),
+    SUnitClickEvent(SUnitClickEvent),
+    SUnitHighlightEvent(SUnitHighlightEvent),
+    STriggerReplySelectedEvent(STriggerReplySelectedEvent),
+    STriggerSkippedEvent(STriggerSkippedEvent),
+    STriggerSoundLengthQueryEvent(STriggerSoundLengthQueryEvent),
+    STriggerSoundOffsetEvent(STriggerSoundOffsetEvent),
+    STriggerTransmissionOffsetEvent(STriggerTransmissionOffsetEvent),
+    STriggerTransmissionCompleteEvent(STriggerTransmissionCompleteEvent),
+    SCameraUpdateEvent(SCameraUpdateEvent),
+    STriggerAbortMissionEvent(STriggerAbortMissionEvent),
+    STriggerPurchaseMadeEvent(STriggerPurchaseMadeEvent),
+    STriggerPurchaseExitEvent(STriggerPurchaseExitEvent),
+    STriggerPlanetMissionLaunchedEvent(STriggerPlanetMissionLaunchedEvent),
+    STriggerPlanetPanelCanceledEvent(STriggerPlanetPanelCanceledEvent),
+    STriggerDialogControlEvent(STriggerDialogControlEvent),
+    STriggerSoundLengthSyncEvent(STriggerSoundLengthSyncEvent),
+    STriggerConversationSkippedEvent(STriggerConversationSkippedEvent),
+    STriggerMouseClickedEvent(STriggerMouseClickedEvent),
+    STriggerPlanetPanelReplayEvent(STriggerPlanetPanelReplayEvent),
+    STriggerSoundtrackDoneEvent(STriggerSoundtrackDoneEvent),
+    STriggerPlanetMissionSelectedEvent(STriggerPlanetMissionSelectedEvent),
+    STriggerKeyPressedEvent(STriggerKeyPressedEvent),
+    STriggerMovieFunctionEvent(STriggerMovieFunctionEvent),
+    STriggerPlanetPanelBirthCompleteEvent(STriggerPlanetPanelBirthCompleteEvent),
+    STriggerPlanetPanelDeathCompleteEvent(STriggerPlanetPanelDeathCompleteEvent),
+    SResourceRequestEvent(SResourceRequestEvent),
+    SResourceRequestFulfillEvent(SResourceRequestFulfillEvent),
+    SResourceRequestCancelEvent(SResourceRequestCancelEvent),
+    STriggerResearchPanelExitEvent(STriggerResearchPanelExitEvent),
+    STriggerResearchPanelPurchaseEvent(STriggerResearchPanelPurchaseEvent),
+    STriggerResearchPanelSelectionChangedEvent(STriggerResearchPanelSelectionChangedEvent),
+    SLagMessageEvent(SLagMessageEvent),
+    STriggerMercenaryPanelExitEvent(STriggerMercenaryPanelExitEvent),
+    STriggerMercenaryPanelPurchaseEvent(STriggerMercenaryPanelPurchaseEvent),
+    STriggerMercenaryPanelSelectionChangedEvent(STriggerMercenaryPanelSelectionChangedEvent),
+    STriggerVictoryPanelExitEvent(STriggerVictoryPanelExitEvent),
+    STriggerBattleReportPanelExitEvent(STriggerBattleReportPanelExitEvent),
+    STriggerBattleReportPanelPlayMissionEvent(STriggerBattleReportPanelPlayMissionEvent),
+    STriggerBattleReportPanelPlaySceneEvent(STriggerBattleReportPanelPlaySceneEvent),
+    STriggerBattleReportPanelSelectionChangedEvent(STriggerBattleReportPanelSelectionChangedEvent),
+    STriggerVictoryPanelPlayMissionAgainEvent(STriggerVictoryPanelPlayMissionAgainEvent),
+    STriggerMovieStartedEvent(STriggerMovieStartedEvent),
+    STriggerMovieFinishedEvent(STriggerMovieFinishedEvent),
+    SDecrementGameTimeRemainingEvent(SDecrementGameTimeRemainingEvent),
+    STriggerPortraitLoadedEvent(STriggerPortraitLoadedEvent),
+    STriggerCustomDialogDismissedEvent(STriggerCustomDialogDismissedEvent),
+    STriggerGameMenuItemSelectedEvent(STriggerGameMenuItemSelectedEvent),
+    STriggerCameraMoveEvent(STriggerCameraMoveEvent),
+    STriggerPurchasePanelSelectedPurchaseItemChangedEvent(STriggerPurchasePanelSelectedPurchaseItemChangedEvent),
+    STriggerPurchasePanelSelectedPurchaseCategoryChangedEvent(STriggerPurchasePanelSelectedPurchaseCategoryChangedEvent),
+    STriggerButtonPressedEvent(STriggerButtonPressedEvent),
+    STriggerGameCreditsFinishedEvent(STriggerGameCreditsFinishedEvent),
+}
+
+impl GameEvent {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            GameEvent::SUserFinishedLoadingSyncEvent(_) => "NNet.Game.SUserFinishedLoadingSyncEvent",
+            GameEvent::SBankFileEvent(_) => "NNet.Game.SBankFileEvent",
+            GameEvent::SBankSectionEvent(_) => "NNet.Game.SBankSectionEvent",
+            GameEvent::SBankKeyEvent(_) => "NNet.Game.SBankKeyEvent",
+            GameEvent::SBankValueEvent(_) => "NNet.Game.SBankValueEvent",
+            GameEvent::SUserOptionsEvent(_) => "NNet.Game.SUserOptionsEvent",
+            GameEvent::SSaveGameEvent(_) => "NNet.Game.SSaveGameEvent",
+            GameEvent::SSaveGameDoneEvent(_) => "NNet.Game.SSaveGameDoneEvent",
+            GameEvent::SPlayerLeaveEvent(_) => "NNet.Game.SPlayerLeaveEvent",
+            GameEvent::SGameCheatEvent(_) => "NNet.Game.SGameCheatEvent",
+            GameEvent::SCmdEvent(_) => "NNet.Game.SCmdEvent",
+            GameEvent::SSelectionDeltaEvent(_) => "NNet.Game.SSelectionDeltaEvent",
+            GameEvent::SControlGroupUpdateEvent(_) => "NNet.Game.SControlGroupUpdateEvent",
+            GameEvent::SSelectionSyncCheckEvent(_) => "NNet.Game.SSelectionSyncCheckEvent",
+            GameEvent::SResourceTradeEvent(_) => "NNet.Game.SResourceTradeEvent",
+            GameEvent::STriggerChatMessageEvent(_) => "NNet.Game.STriggerChatMessageEvent",
+            GameEvent::SAICommunicateEvent(_) => "NNet.Game.SAICommunicateEvent",
+            GameEvent::SSetAbsoluteGameSpeedEvent(_) => "NNet.Game.SSetAbsoluteGameSpeedEvent",
+            GameEvent::SAddAbsoluteGameSpeedEvent(_) => "NNet.Game.SAddAbsoluteGameSpeedEvent",
+            GameEvent::SBroadcastCheatEvent(_) => "NNet.Game.SBroadcastCheatEvent",
+            GameEvent::SAllianceEvent(_) => "NNet.Game.SAllianceEvent",
+            GameEvent::SUnitClickEvent(_) => "NNet.Game.SUnitClickEvent",
+            GameEvent::SUnitHighlightEvent(_) => "NNet.Game.SUnitHighlightEvent",
+            GameEvent::STriggerReplySelectedEvent(_) => "NNet.Game.STriggerReplySelectedEvent",
+            GameEvent::STriggerSkippedEvent(_) => "NNet.Game.STriggerSkippedEvent",
+            GameEvent::STriggerSoundLengthQueryEvent(_) => "NNet.Game.STriggerSoundLengthQueryEvent",
+            GameEvent::STriggerSoundOffsetEvent(_) => "NNet.Game.STriggerSoundOffsetEvent",
+            GameEvent::STriggerTransmissionOffsetEvent(_) => "NNet.Game.STriggerTransmissionOffsetEvent",
+            GameEvent::STriggerTransmissionCompleteEvent(_) => "NNet.Game.STriggerTransmissionCompleteEvent",
+            GameEvent::SCameraUpdateEvent(_) => "NNet.Game.SCameraUpdateEvent",
+            GameEvent::STriggerAbortMissionEvent(_) => "NNet.Game.STriggerAbortMissionEvent",
+            GameEvent::STriggerPurchaseMadeEvent(_) => "NNet.Game.STriggerPurchaseMadeEvent",
+            GameEvent::STriggerPurchaseExitEvent(_) => "NNet.Game.STriggerPurchaseExitEvent",
+            GameEvent::STriggerPlanetMissionLaunchedEvent(_) => "NNet.Game.STriggerPlanetMissionLaunchedEvent",
+            GameEvent::STriggerPlanetPanelCanceledEvent(_) => "NNet.Game.STriggerPlanetPanelCanceledEvent",
+            GameEvent::STriggerDialogControlEvent(_) => "NNet.Game.STriggerDialogControlEvent",
+            GameEvent::STriggerSoundLengthSyncEvent(_) => "NNet.Game.STriggerSoundLengthSyncEvent",
+            GameEvent::STriggerConversationSkippedEvent(_) => "NNet.Game.STriggerConversationSkippedEvent",
+            GameEvent::STriggerMouseClickedEvent(_) => "NNet.Game.STriggerMouseClickedEvent",
+            GameEvent::STriggerPlanetPanelReplayEvent(_) => "NNet.Game.STriggerPlanetPanelReplayEvent",
+            GameEvent::STriggerSoundtrackDoneEvent(_) => "NNet.Game.STriggerSoundtrackDoneEvent",
+            GameEvent::STriggerPlanetMissionSelectedEvent(_) => "NNet.Game.STriggerPlanetMissionSelectedEvent",
+            GameEvent::STriggerKeyPressedEvent(_) => "NNet.Game.STriggerKeyPressedEvent",
+            GameEvent::STriggerMovieFunctionEvent(_) => "NNet.Game.STriggerMovieFunctionEvent",
+            GameEvent::STriggerPlanetPanelBirthCompleteEvent(_) => "NNet.Game.STriggerPlanetPanelBirthCompleteEvent",
+            GameEvent::STriggerPlanetPanelDeathCompleteEvent(_) => "NNet.Game.STriggerPlanetPanelDeathCompleteEvent",
+            GameEvent::SResourceRequestEvent(_) => "NNet.Game.SResourceRequestEvent",
+            GameEvent::SResourceRequestFulfillEvent(_) => "NNet.Game.SResourceRequestFulfillEvent",
+            GameEvent::SResourceRequestCancelEvent(_) => "NNet.Game.SResourceRequestCancelEvent",
+            GameEvent::STriggerResearchPanelExitEvent(_) => "NNet.Game.STriggerResearchPanelExitEvent",
+            GameEvent::STriggerResearchPanelPurchaseEvent(_) => "NNet.Game.STriggerResearchPanelPurchaseEvent",
+            GameEvent::STriggerResearchPanelSelectionChangedEvent(_) => "NNet.Game.STriggerResearchPanelSelectionChangedEvent",
+            GameEvent::SLagMessageEvent(_) => "NNet.Game.SLagMessageEvent",
+            GameEvent::STriggerMercenaryPanelExitEvent(_) => "NNet.Game.STriggerMercenaryPanelExitEvent",
+            GameEvent::STriggerMercenaryPanelPurchaseEvent(_) => "NNet.Game.STriggerMercenaryPanelPurchaseEvent",
+            GameEvent::STriggerMercenaryPanelSelectionChangedEvent(_) => "NNet.Game.STriggerMercenaryPanelSelectionChangedEvent",
+            GameEvent::STriggerVictoryPanelExitEvent(_) => "NNet.Game.STriggerVictoryPanelExitEvent",
+            GameEvent::STriggerBattleReportPanelExitEvent(_) => "NNet.Game.STriggerBattleReportPanelExitEvent",
+            GameEvent::STriggerBattleReportPanelPlayMissionEvent(_) => "NNet.Game.STriggerBattleReportPanelPlayMissionEvent",
+            GameEvent::STriggerBattleReportPanelPlaySceneEvent(_) => "NNet.Game.STriggerBattleReportPanelPlaySceneEvent",
+            GameEvent::STriggerBattleReportPanelSelectionChangedEvent(_) => "NNet.Game.STriggerBattleReportPanelSelectionChangedEvent",
+            GameEvent::STriggerVictoryPanelPlayMissionAgainEvent(_) => "NNet.Game.STriggerVictoryPanelPlayMissionAgainEvent",
+            GameEvent::STriggerMovieStartedEvent(_) => "NNet.Game.STriggerMovieStartedEvent",
+            GameEvent::STriggerMovieFinishedEvent(_) => "NNet.Game.STriggerMovieFinishedEvent",
+            GameEvent::SDecrementGameTimeRemainingEvent(_) => "NNet.Game.SDecrementGameTimeRemainingEvent",
+            GameEvent::STriggerPortraitLoadedEvent(_) => "NNet.Game.STriggerPortraitLoadedEvent",
+            GameEvent::STriggerCustomDialogDismissedEvent(_) => "NNet.Game.STriggerCustomDialogDismissedEvent",
+            GameEvent::STriggerGameMenuItemSelectedEvent(_) => "NNet.Game.STriggerGameMenuItemSelectedEvent",
+            GameEvent::STriggerCameraMoveEvent(_) => "NNet.Game.STriggerCameraMoveEvent",
+            GameEvent::STriggerPurchasePanelSelectedPurchaseItemChangedEvent(_) => "NNet.Game.STriggerPurchasePanelSelectedPurchaseItemChangedEvent",
+            GameEvent::STriggerPurchasePanelSelectedPurchaseCategoryChangedEvent(_) => "NNet.Game.STriggerPurchasePanelSelectedPurchaseCategoryChangedEvent",
+            GameEvent::STriggerButtonPressedEvent(_) => "NNet.Game.STriggerButtonPressedEvent",
+            GameEvent::STriggerGameCreditsFinishedEvent(_) => "NNet.Game.STriggerGameCreditsFinishedEvent",
+        }
+    }
+}
+
+impl FromEvent for GameEvent {
+    fn from_event(event: &Event) -> Result<GameEvent> {
+        let value = &event.value;
+        match event.event_id {
+            5 => Ok(GameEvent::SUserFinishedLoadingSyncEvent(try!(FromValue::from_value(value)))),
+            7 => Ok(GameEvent::SBankFileEvent(try!(FromValue::from_value(value)))),
+            8 => Ok(GameEvent::SBankSectionEvent(try!(FromValue::from_value(value)))),
+            9 => Ok(GameEvent::SBankKeyEvent(try!(FromValue::from_value(value)))),
+            10 => Ok(GameEvent::SBankValueEvent(try!(FromValue::from_value(value)))),
+            11 => Ok(GameEvent::SUserOptionsEvent(try!(FromValue::from_value(value)))),
+            22 => Ok(GameEvent::SSaveGameEvent(try!(FromValue::from_value(value)))),
+            23 => Ok(GameEvent::SSaveGameDoneEvent(try!(FromValue::from_value(value)))),
+            25 => Ok(GameEvent::SPlayerLeaveEvent(try!(FromValue::from_value(value)))),
+            26 => Ok(GameEvent::SGameCheatEvent(try!(FromValue::from_value(value)))),
+            27 => Ok(GameEvent::SCmdEvent(try!(FromValue::from_value(value)))),
+            28 => Ok(GameEvent::SSelectionDeltaEvent(try!(FromValue::from_value(value)))),
+            29 => Ok(GameEvent::SControlGroupUpdateEvent(try!(FromValue::from_value(value)))),
+            30 => Ok(GameEvent::SSelectionSyncCheckEvent(try!(FromValue::from_value(value)))),
+            31 => Ok(GameEvent::SResourceTradeEvent(try!(FromValue::from_value(value)))),
+            32 => Ok(GameEvent::STriggerChatMessageEvent(try!(FromValue::from_value(value)))),
+            33 => Ok(GameEvent::SAICommunicateEvent(try!(FromValue::from_value(value)))),
+            34 => Ok(GameEvent::SSetAbsoluteGameSpeedEvent(try!(FromValue::from_value(value)))),
+            35 => Ok(GameEvent::SAddAbsoluteGameSpeedEvent(try!(FromValue::from_value(value)))),
+            37 => Ok(GameEvent::SBroadcastCheatEvent(try!(FromValue::from_value(value)))),
+            38 => Ok(GameEvent::SAllianceEvent(try!(FromValue::from_value(value)))),
+            39 => Ok(GameEvent::SUnitClickEvent(try!(FromValue::from_value(value)))),
+            40 => Ok(GameEvent::SUnitHighlightEvent(try!(FromValue::from_value(value)))),
+            41 => Ok(GameEvent::STriggerReplySelectedEvent(try!(FromValue::from_value(value)))),
+            44 => Ok(GameEvent::STriggerSkippedEvent(try!(FromValue::from_value(value)))),
+            45 => Ok(GameEvent::STriggerSoundLengthQueryEvent(try!(FromValue::from_value(value)))),
+            46 => Ok(GameEvent::STriggerSoundOffsetEvent(try!(FromValue::from_value(value)))),
+            47 => Ok(GameEvent::STriggerTransmissionOffsetEvent(try!(FromValue::from_value(value)))),
+            48 => Ok(GameEvent::STriggerTransmissionCompleteEvent(try!(FromValue::from_value(value)))),
+            49 => Ok(GameEvent::SCameraUpdateEvent(try!(FromValue::from_value(value)))),
+            50 => Ok(GameEvent::STriggerAbortMissionEvent(try!(FromValue::from_value(value)))),
+            51 => Ok(GameEvent::STriggerPurchaseMadeEvent(try!(FromValue::from_value(value)))),
+            52 => Ok(GameEvent::STriggerPurchaseExitEvent(try!(FromValue::from_value(value)))),
+            53 => Ok(GameEvent::STriggerPlanetMissionLaunchedEvent(try!(FromValue::from_value(value)))),
+            54 => Ok(GameEvent::STriggerPlanetPanelCanceledEvent(try!(FromValue::from_value(value)))),
+            55 => Ok(GameEvent::STriggerDialogControlEvent(try!(FromValue::from_value(value)))),
+            56 => Ok(GameEvent::STriggerSoundLengthSyncEvent(try!(FromValue::from_value(value)))),
+            57 => Ok(GameEvent::STriggerConversationSkippedEvent(try!(FromValue::from_value(value)))),
+            58 => Ok(GameEvent::STriggerMouseClickedEvent(try!(FromValue::from_value(value)))),
+            63 => Ok(GameEvent::STriggerPlanetPanelReplayEvent(try!(FromValue::from_value(value)))),
+            64 => Ok(GameEvent::STriggerSoundtrackDoneEvent(try!(FromValue::from_value(value)))),
+            65 => Ok(GameEvent::STriggerPlanetMissionSelectedEvent(try!(FromValue::from_value(value)))),
+            66 => Ok(GameEvent::STriggerKeyPressedEvent(try!(FromValue::from_value(value)))),
+            67 => Ok(GameEvent::STriggerMovieFunctionEvent(try!(FromValue::from_value(value)))),
+            68 => Ok(GameEvent::STriggerPlanetPanelBirthCompleteEvent(try!(FromValue::from_value(value)))),
+            69 => Ok(GameEvent::STriggerPlanetPanelDeathCompleteEvent(try!(FromValue::from_value(value)))),
+            70 => Ok(GameEvent::SResourceRequestEvent(try!(FromValue::from_value(value)))),
+            71 => Ok(GameEvent::SResourceRequestFulfillEvent(try!(FromValue::from_value(value)))),
+            72 => Ok(GameEvent::SResourceRequestCancelEvent(try!(FromValue::from_value(value)))),
+            73 => Ok(GameEvent::STriggerResearchPanelExitEvent(try!(FromValue::from_value(value)))),
+            74 => Ok(GameEvent::STriggerResearchPanelPurchaseEvent(try!(FromValue::from_value(value)))),
+            75 => Ok(GameEvent::STriggerResearchPanelSelectionChangedEvent(try!(FromValue::from_value(value)))),
+            76 => Ok(GameEvent::SLagMessageEvent(try!(FromValue::from_value(value)))),
+            77 => Ok(GameEvent::STriggerMercenaryPanelExitEvent(try!(FromValue::from_value(value)))),
+            78 => Ok(GameEvent::STriggerMercenaryPanelPurchaseEvent(try!(FromValue::from_value(value)))),
+            79 => Ok(GameEvent::STriggerMercenaryPanelSelectionChangedEvent(try!(FromValue::from_value(value)))),
+            80 => Ok(GameEvent::STriggerVictoryPanelExitEvent(try!(FromValue::from_value(value)))),
+            81 => Ok(GameEvent::STriggerBattleReportPanelExitEvent(try!(FromValue::from_value(value)))),
+            82 => Ok(GameEvent::STriggerBattleReportPanelPlayMissionEvent(try!(FromValue::from_value(value)))),
+            83 => Ok(GameEvent::STriggerBattleReportPanelPlaySceneEvent(try!(FromValue::from_value(value)))),
+            84 => Ok(GameEvent::STriggerBattleReportPanelSelectionChangedEvent(try!(FromValue::from_value(value)))),
+            85 => Ok(GameEvent::STriggerVictoryPanelPlayMissionAgainEvent(try!(FromValue::from_value(value)))),
+            86 => Ok(GameEvent::STriggerMovieStartedEvent(try!(FromValue::from_value(value)))),
+            87 => Ok(GameEvent::STriggerMovieFinishedEvent(try!(FromValue::from_value(value)))),
+            88 => Ok(GameEvent::SDecrementGameTimeRemainingEvent(try!(FromValue::from_value(value)))),
+            89 => Ok(GameEvent::STriggerPortraitLoadedEvent(try!(FromValue::from_value(value)))),
+            90 => Ok(GameEvent::STriggerCustomDialogDismissedEvent(try!(FromValue::from_value(value)))),
+            91 => Ok(GameEvent::STriggerGameMenuItemSelectedEvent(try!(FromValue::from_value(value)))),
+            92 => Ok(GameEvent::STriggerCameraMoveEvent(try!(FromValue::from_value(value)))),
+            93 => Ok(GameEvent::STriggerPurchasePanelSelectedPurchaseItemChangedEvent(try!(FromValue::from_value(value)))),
+            94 => Ok(GameEvent::STriggerPurchasePanelSelectedPurchaseCategoryChangedEvent(try!(FromValue::from_value(value)))),
+            95 => Ok(GameEvent::STriggerButtonPressedEvent(try!(FromValue::from_value(value)))),
+            96 => Ok(GameEvent::STriggerGameCreditsFinishedEvent(try!(FromValue::from_value(value)))),
+            other => Err(Error::UnknownEvent(other)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SUserFinishedLoadingSyncEvent;
+
+impl FromValue for SUserFinishedLoadingSyncEvent {
+    fn from_value(value: &Value) -> Result<SUserFinishedLoadingSyncEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(SUserFinishedLoadingSyncEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SBankFileEvent {
+    pub name: Blob,
+}
+
+impl FromValue for SBankFileEvent {
+    fn from_value(value: &Value) -> Result<SBankFileEvent> {
+        Ok(SBankFileEvent {
+            name: try!(field(value, "m_name")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SBankSectionEvent {
+    pub name: Blob,
+}
+
+impl FromValue for SBankSectionEvent {
+    fn from_value(value: &Value) -> Result<SBankSectionEvent> {
+        Ok(SBankSectionEvent {
+            name: try!(field(value, "m_name")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SBankKeyEvent {
+    pub name: Blob,
+    pub type_: u32,
+    pub data: Blob,
+}
+
+impl FromValue for SBankKeyEvent {
+    fn from_value(value: &Value) -> Result<SBankKeyEvent> {
+        Ok(SBankKeyEvent {
+            name: try!(field(value, "m_name")),
+            type_: try!(field(value, "m_type")),
+            data: try!(field(value, "m_data")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SBankValueEvent {
+    pub type_: u32,
+    pub name: Blob,
+    pub data: Blob,
+}
+
+impl FromValue for SBankValueEvent {
+    fn from_value(value: &Value) -> Result<SBankValueEvent> {
+        Ok(SBankValueEvent {
+            type_: try!(field(value, "m_type")),
+            name: try!(field(value, "m_name")),
+            data: try!(field(value, "m_data")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SUserOptionsEvent {
+    pub development_cheats_enabled: bool,
+    pub multiplayer_cheats_enabled: bool,
+    pub sync_checksumming_enabled: bool,
+    pub is_map_to_map_transition: bool,
+}
+
+impl FromValue for SUserOptionsEvent {
+    fn from_value(value: &Value) -> Result<SUserOptionsEvent> {
+        Ok(SUserOptionsEvent {
+            development_cheats_enabled: try!(field(value, "m_developmentCheatsEnabled")),
+            multiplayer_cheats_enabled: try!(field(value, "m_multiplayerCheatsEnabled")),
+            sync_checksumming_enabled: try!(field(value, "m_syncChecksummingEnabled")),
+            is_map_to_map_transition: try!(field(value, "m_isMapToMapTransition")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SSaveGameEvent {
+    pub file_name: Blob,
+    pub automatic: bool,
+    pub overwrite: bool,
+    pub name: Blob,
+    pub description: Blob,
+}
+
+impl FromValue for SSaveGameEvent {
+    fn from_value(value: &Value) -> Result<SSaveGameEvent> {
+        Ok(SSaveGameEvent {
+            file_name: try!(field(value, "m_fileName")),
+            automatic: try!(field(value, "m_automatic")),
+            overwrite: try!(field(value, "m_overwrite")),
+            name: try!(field(value, "m_name")),
+            description: try!(field(value, "m_description")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SSaveGameDoneEvent;
+
+impl FromValue for SSaveGameDoneEvent {
+    fn from_value(value: &Value) -> Result<SSaveGameDoneEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(SSaveGameDoneEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SPlayerLeaveEvent;
+
+impl FromValue for SPlayerLeaveEvent {
+    fn from_value(value: &Value) -> Result<SPlayerLeaveEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(SPlayerLeaveEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SGameCheatEvent {
+    pub data: Data,
+}
+
+impl FromValue for SGameCheatEvent {
+    fn from_value(value: &Value) -> Result<SGameCheatEvent> {
+        Ok(SGameCheatEvent {
+            data: try!(field(value, "m_data")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SCmdEvent {
+    pub cmd_flags: u32,
+    pub abil_link: u16,
+    pub abil_cmd_index: u8,
+    pub abil_cmd_data: u8,
+    pub target_unit_flags: u8,
+    pub target_unit_timer: u8,
+    pub other_unit: u32,
+    pub target_unit_tag: u32,
+    pub target_unit_snapshot_unit_link: u16,
+    pub target_unit_snapshot_player_id: Option<u8>,
+    pub target_point: TargetPoint,
+}
+
+impl FromValue for SCmdEvent {
+    fn from_value(value: &Value) -> Result<SCmdEvent> {
+        Ok(SCmdEvent {
+            cmd_flags: try!(field(value, "m_cmdFlags")),
+            abil_link: try!(field(value, "m_abilLink")),
+            abil_cmd_index: try!(field(value, "m_abilCmdIndex")),
+            abil_cmd_data: try!(field(value, "m_abilCmdData")),
+            target_unit_flags: try!(field(value, "m_targetUnitFlags")),
+            target_unit_timer: try!(field(value, "m_targetUnitTimer")),
+            other_unit: try!(field(value, "m_otherUnit")),
+            target_unit_tag: try!(field(value, "m_targetUnitTag")),
+            target_unit_snapshot_unit_link: try!(field(value, "m_targetUnitSnapshotUnitLink")),
+            target_unit_snapshot_player_id: try!(field(value, "m_targetUnitSnapshotPlayerId")),
+            target_point: try!(field(value, "m_targetPoint")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SSelectionDeltaEvent {
+    pub control_group_id: u8,
+    pub delta: Delta,
+}
+
+impl FromValue for SSelectionDeltaEvent {
+    fn from_value(value: &Value) -> Result<SSelectionDeltaEvent> {
+        Ok(SSelectionDeltaEvent {
+            control_group_id: try!(field(value, "m_controlGroupId")),
+            delta: try!(field(value, "m_delta")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SControlGroupUpdateEvent {
+    pub control_group_index: u8,
+    pub control_group_update: u8,
+    pub mask: Option<BitArray>,
+}
+
+impl FromValue for SControlGroupUpdateEvent {
+    fn from_value(value: &Value) -> Result<SControlGroupUpdateEvent> {
+        Ok(SControlGroupUpdateEvent {
+            control_group_index: try!(field(value, "m_controlGroupIndex")),
+            control_group_update: try!(field(value, "m_controlGroupUpdate")),
+            mask: try!(field(value, "m_mask")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SSelectionSyncCheckEvent {
+    pub control_group_id: u8,
+    pub selection_sync_data: SelectionSyncData,
+}
+
+impl FromValue for SSelectionSyncCheckEvent {
+    fn from_value(value: &Value) -> Result<SSelectionSyncCheckEvent> {
+        Ok(SSelectionSyncCheckEvent {
+            control_group_id: try!(field(value, "m_controlGroupId")),
+            selection_sync_data: try!(field(value, "m_selectionSyncData")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SResourceTradeEvent {
+    pub recipient_id: u8,
+    pub resources: Vec<i32>,
+}
+
+impl FromValue for SResourceTradeEvent {
+    fn from_value(value: &Value) -> Result<SResourceTradeEvent> {
+        Ok(SResourceTradeEvent {
+            recipient_id: try!(field(value, "m_recipientId")),
+            resources: try!(field(value, "m_resources")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerChatMessageEvent {
+    pub chat_message: Blob,
+}
+
+impl FromValue for STriggerChatMessageEvent {
+    fn from_value(value: &Value) -> Result<STriggerChatMessageEvent> {
+        Ok(STriggerChatMessageEvent {
+            chat_message: try!(field(value, "m_chatMessage")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SAICommunicateEvent {
+    pub beacon: i8,
+    pub ally: i8,
+    pub autocast: i8,
+    pub target_unit_tag: u32,
+    pub target_unit_snapshot_unit_link: u16,
+    pub target_unit_snapshot_player_id: Option<u8>,
+    pub target_point: TargetPoint,
+}
+
+impl FromValue for SAICommunicateEvent {
+    fn from_value(value: &Value) -> Result<SAICommunicateEvent> {
+        Ok(SAICommunicateEvent {
+            beacon: try!(field(value, "m_beacon")),
+            ally: try!(field(value, "m_ally")),
+            autocast: try!(field(value, "m_autocast")),
+            target_unit_tag: try!(field(value, "m_targetUnitTag")),
+            target_unit_snapshot_unit_link: try!(field(value, "m_targetUnitSnapshotUnitLink")),
+            target_unit_snapshot_player_id: try!(field(value, "m_targetUnitSnapshotPlayerId")),
+            target_point: try!(field(value, "m_targetPoint")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SSetAbsoluteGameSpeedEvent {
+    pub speed: u8,
+}
+
+impl FromValue for SSetAbsoluteGameSpeedEvent {
+    fn from_value(value: &Value) -> Result<SSetAbsoluteGameSpeedEvent> {
+        Ok(SSetAbsoluteGameSpeedEvent {
+            speed: try!(field(value, "m_speed")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SAddAbsoluteGameSpeedEvent {
+    pub delta: i8,
+}
+
+impl FromValue for SAddAbsoluteGameSpeedEvent {
+    fn from_value(value: &Value) -> Result<SAddAbsoluteGameSpeedEvent> {
+        Ok(SAddAbsoluteGameSpeedEvent {
+            delta: try!(field(value, "m_delta")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SBroadcastCheatEvent {
+    pub verb: Blob,
+    pub arguments: Blob,
+}
+
+impl FromValue for SBroadcastCheatEvent {
+    fn from_value(value: &Value) -> Result<SBroadcastCheatEvent> {
+        Ok(SBroadcastCheatEvent {
+            verb: try!(field(value, "m_verb")),
+            arguments: try!(field(value, "m_arguments")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SAllianceEvent {
+    pub alliance: u32,
+    pub control: u32,
+}
+
+impl FromValue for SAllianceEvent {
+    fn from_value(value: &Value) -> Result<SAllianceEvent> {
+        Ok(SAllianceEvent {
+            alliance: try!(field(value, "m_alliance")),
+            control: try!(field(value, "m_control")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SUnitClickEvent {
+    pub unit_tag: u32,
+}
+
+impl FromValue for SUnitClickEvent {
+    fn from_value(value: &Value) -> Result<SUnitClickEvent> {
+        Ok(SUnitClickEvent {
+            unit_tag: try!(field(value, "m_unitTag")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SUnitHighlightEvent {
+    pub unit_tag: u32,
+    pub flags: u8,
+}
+
+impl FromValue for SUnitHighlightEvent {
+    fn from_value(value: &Value) -> Result<SUnitHighlightEvent> {
+        Ok(SUnitHighlightEvent {
+            unit_tag: try!(field(value, "m_unitTag")),
+            flags: try!(field(value, "m_flags")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerReplySelectedEvent {
+    pub conversation_id: i32,
+    pub reply_id: i32,
+}
+
+impl FromValue for STriggerReplySelectedEvent {
+    fn from_value(value: &Value) -> Result<STriggerReplySelectedEvent> {
+        Ok(STriggerReplySelectedEvent {
+            conversation_id: try!(field(value, "m_conversationId")),
+            reply_id: try!(field(value, "m_replyId")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerSkippedEvent;
+
+impl FromValue for STriggerSkippedEvent {
+    fn from_value(value: &Value) -> Result<STriggerSkippedEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerSkippedEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerSoundLengthQueryEvent {
+    pub sound_hash: u32,
+    pub length: u32,
+}
+
+impl FromValue for STriggerSoundLengthQueryEvent {
+    fn from_value(value: &Value) -> Result<STriggerSoundLengthQueryEvent> {
+        Ok(STriggerSoundLengthQueryEvent {
+            sound_hash: try!(field(value, "m_soundHash")),
+            length: try!(field(value, "m_length")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerSoundOffsetEvent {
+    pub sound: u32,
+}
+
+impl FromValue for STriggerSoundOffsetEvent {
+    fn from_value(value: &Value) -> Result<STriggerSoundOffsetEvent> {
+        Ok(STriggerSoundOffsetEvent {
+            sound: try!(field(value, "m_sound")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerTransmissionOffsetEvent {
+    pub transmission_id: i32,
+}
+
+impl FromValue for STriggerTransmissionOffsetEvent {
+    fn from_value(value: &Value) -> Result<STriggerTransmissionOffsetEvent> {
+        Ok(STriggerTransmissionOffsetEvent {
+            transmission_id: try!(field(value, "m_transmissionId")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerTransmissionCompleteEvent {
+    pub transmission_id: i32,
+}
+
+impl FromValue for STriggerTransmissionCompleteEvent {
+    fn from_value(value: &Value) -> Result<STriggerTransmissionCompleteEvent> {
+        Ok(STriggerTransmissionCompleteEvent {
+            transmission_id: try!(field(value, "m_transmissionId")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SCameraUpdateEvent {
+    pub target: Target,
+    pub distance: Option<u16>,
+    pub pitch: Option<u16>,
+    pub yaw: Option<u16>,
+}
+
+impl FromValue for SCameraUpdateEvent {
+    fn from_value(value: &Value) -> Result<SCameraUpdateEvent> {
+        Ok(SCameraUpdateEvent {
+            target: try!(field(value, "m_target")),
+            distance: try!(field(value, "m_distance")),
+            pitch: try!(field(value, "m_pitch")),
+            yaw: try!(field(value, "m_yaw")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerAbortMissionEvent;
+
+impl FromValue for STriggerAbortMissionEvent {
+    fn from_value(value: &Value) -> Result<STriggerAbortMissionEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerAbortMissionEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerPurchaseMadeEvent {
+    pub purchase_item_id: i32,
+}
+
+impl FromValue for STriggerPurchaseMadeEvent {
+    fn from_value(value: &Value) -> Result<STriggerPurchaseMadeEvent> {
+        Ok(STriggerPurchaseMadeEvent {
+            purchase_item_id: try!(field(value, "m_purchaseItemId")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerPurchaseExitEvent;
+
+impl FromValue for STriggerPurchaseExitEvent {
+    fn from_value(value: &Value) -> Result<STriggerPurchaseExitEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerPurchaseExitEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerPlanetMissionLaunchedEvent {
+    pub difficulty_level: i32,
+}
+
+impl FromValue for STriggerPlanetMissionLaunchedEvent {
+    fn from_value(value: &Value) -> Result<STriggerPlanetMissionLaunchedEvent> {
+        Ok(STriggerPlanetMissionLaunchedEvent {
+            difficulty_level: try!(field(value, "m_difficultyLevel")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerPlanetPanelCanceledEvent;
+
+impl FromValue for STriggerPlanetPanelCanceledEvent {
+    fn from_value(value: &Value) -> Result<STriggerPlanetPanelCanceledEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerPlanetPanelCanceledEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerDialogControlEvent {
+    pub control_id: i32,
+    pub event_type: i32,
+    pub event_data: EventData,
+}
+
+impl FromValue for STriggerDialogControlEvent {
+    fn from_value(value: &Value) -> Result<STriggerDialogControlEvent> {
+        Ok(STriggerDialogControlEvent {
+            control_id: try!(field(value, "m_controlId")),
+            event_type: try!(field(value, "m_eventType")),
+            event_data: try!(field(value, "m_eventData")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerSoundLengthSyncEvent {
+    pub sync_info: SyncInfo,
+}
+
+impl FromValue for STriggerSoundLengthSyncEvent {
+    fn from_value(value: &Value) -> Result<STriggerSoundLengthSyncEvent> {
+        Ok(STriggerSoundLengthSyncEvent {
+            sync_info: try!(field(value, "m_syncInfo")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerConversationSkippedEvent {
+    pub skip_type: u8,
+}
+
+impl FromValue for STriggerConversationSkippedEvent {
+    fn from_value(value: &Value) -> Result<STriggerConversationSkippedEvent> {
+        Ok(STriggerConversationSkippedEvent {
+            skip_type: try!(field(value, "m_skipType")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerMouseClickedEvent {
+    pub button: u32,
+    pub down: bool,
+    pub pos_xui: u32,
+    pub pos_yui: u32,
+    pub pos_x_world: i32,
+    pub pos_y_world: i32,
+    pub pos_z_world: i32,
+}
+
+impl FromValue for STriggerMouseClickedEvent {
+    fn from_value(value: &Value) -> Result<STriggerMouseClickedEvent> {
+        Ok(STriggerMouseClickedEvent {
+            button: try!(field(value, "m_button")),
+            down: try!(field(value, "m_down")),
+            pos_xui: try!(field(value, "m_posXUI")),
+            pos_yui: try!(field(value, "m_posYUI")),
+            pos_x_world: try!(field(value, "m_posXWorld")),
+            pos_y_world: try!(field(value, "m_posYWorld")),
+            pos_z_world: try!(field(value, "m_posZWorld")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerPlanetPanelReplayEvent;
+
+impl FromValue for STriggerPlanetPanelReplayEvent {
+    fn from_value(value: &Value) -> Result<STriggerPlanetPanelReplayEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerPlanetPanelReplayEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerSoundtrackDoneEvent {
+    pub soundtrack: u32,
+}
+
+impl FromValue for STriggerSoundtrackDoneEvent {
+    fn from_value(value: &Value) -> Result<STriggerSoundtrackDoneEvent> {
+        Ok(STriggerSoundtrackDoneEvent {
+            soundtrack: try!(field(value, "m_soundtrack")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerPlanetMissionSelectedEvent {
+    pub planet_id: i32,
+}
+
+impl FromValue for STriggerPlanetMissionSelectedEvent {
+    fn from_value(value: &Value) -> Result<STriggerPlanetMissionSelectedEvent> {
+        Ok(STriggerPlanetMissionSelectedEvent {
+            planet_id: try!(field(value, "m_planetId")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerKeyPressedEvent {
+    pub key: i8,
+    pub flags: i8,
+}
+
+impl FromValue for STriggerKeyPressedEvent {
+    fn from_value(value: &Value) -> Result<STriggerKeyPressedEvent> {
+        Ok(STriggerKeyPressedEvent {
+            key: try!(field(value, "m_key")),
+            flags: try!(field(value, "m_flags")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerMovieFunctionEvent {
+    pub function_name: Blob,
+}
+
+impl FromValue for STriggerMovieFunctionEvent {
+    fn from_value(value: &Value) -> Result<STriggerMovieFunctionEvent> {
+        Ok(STriggerMovieFunctionEvent {
+            function_name: try!(field(value, "m_functionName")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerPlanetPanelBirthCompleteEvent;
+
+impl FromValue for STriggerPlanetPanelBirthCompleteEvent {
+    fn from_value(value: &Value) -> Result<STriggerPlanetPanelBirthCompleteEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerPlanetPanelBirthCompleteEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerPlanetPanelDeathCompleteEvent;
+
+impl FromValue for STriggerPlanetPanelDeathCompleteEvent {
+    fn from_value(value: &Value) -> Result<STriggerPlanetPanelDeathCompleteEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerPlanetPanelDeathCompleteEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SResourceRequestEvent {
+    pub resources: Vec<i32>,
+}
+
+impl FromValue for SResourceRequestEvent {
+    fn from_value(value: &Value) -> Result<SResourceRequestEvent> {
+        Ok(SResourceRequestEvent {
+            resources: try!(field(value, "m_resources")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SResourceRequestFulfillEvent {
+    pub fulfill_request_id: i32,
+}
+
+impl FromValue for SResourceRequestFulfillEvent {
+    fn from_value(value: &Value) -> Result<SResourceRequestFulfillEvent> {
+        Ok(SResourceRequestFulfillEvent {
+            fulfill_request_id: try!(field(value, "m_fulfillRequestId")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SResourceRequestCancelEvent {
+    pub cancel_request_id: i32,
+}
+
+impl FromValue for SResourceRequestCancelEvent {
+    fn from_value(value: &Value) -> Result<SResourceRequestCancelEvent> {
+        Ok(SResourceRequestCancelEvent {
+            cancel_request_id: try!(field(value, "m_cancelRequestId")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerResearchPanelExitEvent;
+
+impl FromValue for STriggerResearchPanelExitEvent {
+    fn from_value(value: &Value) -> Result<STriggerResearchPanelExitEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerResearchPanelExitEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerResearchPanelPurchaseEvent;
+
+impl FromValue for STriggerResearchPanelPurchaseEvent {
+    fn from_value(value: &Value) -> Result<STriggerResearchPanelPurchaseEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerResearchPanelPurchaseEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerResearchPanelSelectionChangedEvent {
+    pub research_item_id: i32,
+}
+
+impl FromValue for STriggerResearchPanelSelectionChangedEvent {
+    fn from_value(value: &Value) -> Result<STriggerResearchPanelSelectionChangedEvent> {
+        Ok(STriggerResearchPanelSelectionChangedEvent {
+            research_item_id: try!(field(value, "m_researchItemId")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SLagMessageEvent {
+    pub lagging_player_id: u8,
+}
+
+impl FromValue for SLagMessageEvent {
+    fn from_value(value: &Value) -> Result<SLagMessageEvent> {
+        Ok(SLagMessageEvent {
+            lagging_player_id: try!(field(value, "m_laggingPlayerId")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerMercenaryPanelExitEvent;
+
+impl FromValue for STriggerMercenaryPanelExitEvent {
+    fn from_value(value: &Value) -> Result<STriggerMercenaryPanelExitEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerMercenaryPanelExitEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerMercenaryPanelPurchaseEvent;
+
+impl FromValue for STriggerMercenaryPanelPurchaseEvent {
+    fn from_value(value: &Value) -> Result<STriggerMercenaryPanelPurchaseEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerMercenaryPanelPurchaseEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerMercenaryPanelSelectionChangedEvent {
+    pub mercenary_id: i32,
+}
+
+impl FromValue for STriggerMercenaryPanelSelectionChangedEvent {
+    fn from_value(value: &Value) -> Result<STriggerMercenaryPanelSelectionChangedEvent> {
+        Ok(STriggerMercenaryPanelSelectionChangedEvent {
+            mercenary_id: try!(field(value, "m_mercenaryId")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerVictoryPanelExitEvent;
+
+impl FromValue for STriggerVictoryPanelExitEvent {
+    fn from_value(value: &Value) -> Result<STriggerVictoryPanelExitEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerVictoryPanelExitEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerBattleReportPanelExitEvent;
+
+impl FromValue for STriggerBattleReportPanelExitEvent {
+    fn from_value(value: &Value) -> Result<STriggerBattleReportPanelExitEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerBattleReportPanelExitEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerBattleReportPanelPlayMissionEvent {
+    pub battle_report_id: i32,
+    pub difficulty_level: i32,
+}
+
+impl FromValue for STriggerBattleReportPanelPlayMissionEvent {
+    fn from_value(value: &Value) -> Result<STriggerBattleReportPanelPlayMissionEvent> {
+        Ok(STriggerBattleReportPanelPlayMissionEvent {
+            battle_report_id: try!(field(value, "m_battleReportId")),
+            difficulty_level: try!(field(value, "m_difficultyLevel")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerBattleReportPanelPlaySceneEvent {
+    pub battle_report_id: i32,
+}
+
+impl FromValue for STriggerBattleReportPanelPlaySceneEvent {
+    fn from_value(value: &Value) -> Result<STriggerBattleReportPanelPlaySceneEvent> {
+        Ok(STriggerBattleReportPanelPlaySceneEvent {
+            battle_report_id: try!(field(value, "m_battleReportId")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerBattleReportPanelSelectionChangedEvent {
+    pub battle_report_id: i32,
+}
+
+impl FromValue for STriggerBattleReportPanelSelectionChangedEvent {
+    fn from_value(value: &Value) -> Result<STriggerBattleReportPanelSelectionChangedEvent> {
+        Ok(STriggerBattleReportPanelSelectionChangedEvent {
+            battle_report_id: try!(field(value, "m_battleReportId")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerVictoryPanelPlayMissionAgainEvent {
+    pub difficulty_level: i32,
+}
+
+impl FromValue for STriggerVictoryPanelPlayMissionAgainEvent {
+    fn from_value(value: &Value) -> Result<STriggerVictoryPanelPlayMissionAgainEvent> {
+        Ok(STriggerVictoryPanelPlayMissionAgainEvent {
+            difficulty_level: try!(field(value, "m_difficultyLevel")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerMovieStartedEvent;
+
+impl FromValue for STriggerMovieStartedEvent {
+    fn from_value(value: &Value) -> Result<STriggerMovieStartedEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerMovieStartedEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerMovieFinishedEvent;
+
+impl FromValue for STriggerMovieFinishedEvent {
+    fn from_value(value: &Value) -> Result<STriggerMovieFinishedEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerMovieFinishedEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SDecrementGameTimeRemainingEvent {
+    pub decrement_ms: u32,
+}
+
+impl FromValue for SDecrementGameTimeRemainingEvent {
+    fn from_value(value: &Value) -> Result<SDecrementGameTimeRemainingEvent> {
+        Ok(SDecrementGameTimeRemainingEvent {
+            decrement_ms: try!(field(value, "m_decrementMs")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerPortraitLoadedEvent {
+    pub portrait_id: i32,
+}
+
+impl FromValue for STriggerPortraitLoadedEvent {
+    fn from_value(value: &Value) -> Result<STriggerPortraitLoadedEvent> {
+        Ok(STriggerPortraitLoadedEvent {
+            portrait_id: try!(field(value, "m_portraitId")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerCustomDialogDismissedEvent {
+    pub result: i32,
+}
+
+impl FromValue for STriggerCustomDialogDismissedEvent {
+    fn from_value(value: &Value) -> Result<STriggerCustomDialogDismissedEvent> {
+        Ok(STriggerCustomDialogDismissedEvent {
+            result: try!(field(value, "m_result")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerGameMenuItemSelectedEvent {
+    pub game_menu_item_index: i32,
+}
+
+impl FromValue for STriggerGameMenuItemSelectedEvent {
+    fn from_value(value: &Value) -> Result<STriggerGameMenuItemSelectedEvent> {
+        Ok(STriggerGameMenuItemSelectedEvent {
+            game_menu_item_index: try!(field(value, "m_gameMenuItemIndex")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerCameraMoveEvent {
+    pub reason: i8,
+}
+
+impl FromValue for STriggerCameraMoveEvent {
+    fn from_value(value: &Value) -> Result<STriggerCameraMoveEvent> {
+        Ok(STriggerCameraMoveEvent {
+            reason: try!(field(value, "m_reason")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerPurchasePanelSelectedPurchaseItemChangedEvent {
+    pub purchase_item_id: i32,
+}
+
+impl FromValue for STriggerPurchasePanelSelectedPurchaseItemChangedEvent {
+    fn from_value(value: &Value) -> Result<STriggerPurchasePanelSelectedPurchaseItemChangedEvent> {
+        Ok(STriggerPurchasePanelSelectedPurchaseItemChangedEvent {
+            purchase_item_id: try!(field(value, "m_purchaseItemId")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerPurchasePanelSelectedPurchaseCategoryChangedEvent {
+    pub purchase_category_id: i32,
+}
+
+impl FromValue for STriggerPurchasePanelSelectedPurchaseCategoryChangedEvent {
+    fn from_value(value: &Value) -> Result<STriggerPurchasePanelSelectedPurchaseCategoryChangedEvent> {
+        Ok(STriggerPurchasePanelSelectedPurchaseCategoryChangedEvent {
+            purchase_category_id: try!(field(value, "m_purchaseCategoryId")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerButtonPressedEvent {
+    pub button: u16,
+}
+
+impl FromValue for STriggerButtonPressedEvent {
+    fn from_value(value: &Value) -> Result<STriggerButtonPressedEvent> {
+        Ok(STriggerButtonPressedEvent {
+            button: try!(field(value, "m_button")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct STriggerGameCreditsFinishedEvent;
+
+impl FromValue for STriggerGameCreditsFinishedEvent {
+    fn from_value(value: &Value) -> Result<STriggerGameCreditsFinishedEvent> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(STriggerGameCreditsFinishedEvent)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Data {
+    pub point: Point,
+    pub time: i32,
+    pub verb: Blob,
+    pub arguments: Blob,
+}
+
+impl FromValue for Data {
+    fn from_value(value: &Value) -> Result<Data> {
+        Ok(Data {
+            point: try!(field(value, "m_point")),
+            time: try!(field(value, "m_time")),
+            verb: try!(field(value, "m_verb")),
+            arguments: try!(field(value, "m_arguments")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TargetPoint {
+    pub x: i32,
+    pub y: i32,
+    pub z: i32,
+}
+
+impl FromValue for TargetPoint {
+    fn from_value(value: &Value) -> Result<TargetPoint> {
+        Ok(TargetPoint {
+            x: try!(field(value, "x")),
+            y: try!(field(value, "y")),
+            z: try!(field(value, "z")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Delta {
+    pub subgroup_index: u8,
+    pub remove_mask: BitArray,
+    pub add_subgroups: Vec<AddSubgroup>,
+    pub add_unit_tags: Vec<u32>,
+}
+
+impl FromValue for Delta {
+    fn from_value(value: &Value) -> Result<Delta> {
+        Ok(Delta {
+            subgroup_index: try!(field(value, "m_subgroupIndex")),
+            remove_mask: try!(field(value, "m_removeMask")),
+            add_subgroups: try!(field(value, "m_addSubgroups")),
+            add_unit_tags: try!(field(value, "m_addUnitTags")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SelectionSyncData {
+    pub count: u8,
+    pub subgroup_count: u8,
+    pub active_subgroup_index: u8,
+    pub unit_tags_checksum: u32,
+    pub subgroup_indices_checksum: u32,
+    pub subgroups_checksum: u32,
+}
+
+impl FromValue for SelectionSyncData {
+    fn from_value(value: &Value) -> Result<SelectionSyncData> {
+        Ok(SelectionSyncData {
+            count: try!(field(value, "m_count")),
+            subgroup_count: try!(field(value, "m_subgroupCount")),
+            active_subgroup_index: try!(field(value, "m_activeSubgroupIndex")),
+            unit_tags_checksum: try!(field(value, "m_unitTagsChecksum")),
+            subgroup_indices_checksum: try!(field(value, "m_subgroupIndicesChecksum")),
+            subgroups_checksum: try!(field(value, "m_subgroupsChecksum")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Target {
+    pub x: u16,
+    pub y: u16,
+}
+
+impl FromValue for Target {
+    fn from_value(value: &Value) -> Result<Target> {
+        Ok(Target {
+            x: try!(field(value, "x")),
+            y: try!(field(value, "y")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EventData {
+    None,
+    Checked(bool),
+    ValueChanged(u32),
+    SelectionChanged(i32),
+    TextChanged(Blob),
+}
+
+impl FromValue for EventData {
+    fn from_value(value: &Value) -> Result<EventData> {
+        let (variant, inner) = try!(value.as_choice().map_err(|_| Error::InvalidType));
+        match variant {
+            "None" => Ok(EventData::None),
+            "Checked" => Ok(EventData::Checked(try!(FromValue::from_value(inner)))),
+            "ValueChanged" => Ok(EventData::ValueChanged(try!(FromValue::from_value(inner)))),
+            "SelectionChanged" => Ok(EventData::SelectionChanged(try!(FromValue::from_value(inner)))),
+            "TextChanged" => Ok(EventData::TextChanged(try!(FromValue::from_value(inner)))),
+            _ => Err(Error::InvalidType),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SyncInfo {
+    pub sound_hash: Vec<u32>,
+    pub length: Vec<u32>,
+}
+
+impl FromValue for SyncInfo {
+    fn from_value(value: &Value) -> Result<SyncInfo> {
+        Ok(SyncInfo {
+            sound_hash: try!(field(value, "m_soundHash")),
+            length: try!(field(value, "m_length")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl FromValue for Point {
+    fn from_value(value: &Value) -> Result<Point> {
+        Ok(Point {
+            x: try!(field(value, "x")),
+            y: try!(field(value, "y")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AddSubgroup {
+    pub unit_link: u16,
+    pub intra_subgroup_priority: u8,
+    pub count: u8,
+}
+
+impl FromValue for AddSubgroup {
+    fn from_value(value: &Value) -> Result<AddSubgroup> {
+        Ok(AddSubgroup {
+            unit_link: try!(field(value, "m_unitLink")),
+            intra_subgroup_priority: try!(field(value, "m_intraSubgroupPriority")),
+            count: try!(field(value, "m_count")),
+        })
+    }
+}
@@ -0,0 +1,105 @@
+// Generated by py2rs_events.py; do not edit.
+
+use events::Event;
+use typed::{FromValue, FromEvent, Error, Result, Blob, Recipient, field};
+use value::Value;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MessageEvent {
+    SChatMessage(SChatMessage),
+    SPingMessage(SPingMessage),
+    SLoadingProgressMessage(SLoadingProgressMessage),
+    SServerPingMessage(SServerPingMessage),
+}
+
+impl MessageEvent {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            MessageEvent::SChatMessage(_) => "NNet.Game.SChatMessage",
+            MessageEvent::SPingMessage(_) => "NNet.Game.SPingMessage",
+            MessageEvent::SLoadingProgressMessage(_) => "NNet.Game.SLoadingProgressMessage",
+            MessageEvent::SServerPingMessage(_) => "NNet.Game.SServerPingMessage",
+        }
+    }
+}
+
+impl FromEvent for MessageEvent {
+    fn from_event(event: &Event) -> Result<MessageEvent> {
+        let value = &event.value;
+        match event.event_id {
+            0 => Ok(MessageEvent::SChatMessage(try!(FromValue::from_value(value)))),
+            1 => Ok(MessageEvent::SPingMessage(try!(FromValue::from_value(value)))),
+            2 => Ok(MessageEvent::SLoadingProgressMessage(try!(FromValue::from_value(value)))),
+            3 => Ok(MessageEvent::SServerPingMessage(try!(FromValue::from_value(value)))),
+            other => Err(Error::UnknownEvent(other)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SChatMessage {
+    pub recipient: Recipient,
+    pub string: Blob,
+}
+
+impl FromValue for SChatMessage {
+    fn from_value(value: &Value) -> Result<SChatMessage> {
+        Ok(SChatMessage {
+            recipient: try!(field(value, "m_recipient")),
+            string: try!(field(value, "m_string")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SPingMessage {
+    pub recipient: Recipient,
+    pub point: Point,
+}
+
+impl FromValue for SPingMessage {
+    fn from_value(value: &Value) -> Result<SPingMessage> {
+        Ok(SPingMessage {
+            recipient: try!(field(value, "m_recipient")),
+            point: try!(field(value, "m_point")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SLoadingProgressMessage {
+    pub progress: i32,
+}
+
+impl FromValue for SLoadingProgressMessage {
+    fn from_value(value: &Value) -> Result<SLoadingProgressMessage> {
+        Ok(SLoadingProgressMessage {
+            progress: try!(field(value, "m_progress")),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SServerPingMessage;
+
+impl FromValue for SServerPingMessage {
+    fn from_value(value: &Value) -> Result<SServerPingMessage> {
+        try!(value.as_dict().map_err(|_| Error::InvalidType));
+        Ok(SServerPingMessage)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl FromValue for Point {
+    fn from_value(value: &Value) -> Result<Point> {
+        Ok(Point {
+            x: try!(field(value, "x")),
+            y: try!(field(value, "y")),
+        })
+    }
+}
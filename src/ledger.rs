@@ -0,0 +1,207 @@
+//! Resources sent between players, resource requests and alliance changes,
+//! for working out who fed whom in team games.
+//!
+//! Outstanding requests aren't tracked yet.  Fulfils and cancels name a
+//! request by an id that `SResourceRequestEvent` doesn't carry, and none of
+//! the test replays make a request, so there is nothing to establish how the
+//! game numbers them.  Until a replay that does shows the scheme, requests
+//! and their responses are kept side by side, unmatched.
+
+use std::ops::Add;
+
+use common::GameLoop;
+use typed::{self, GameEvent, TypedEvent};
+
+/// `m_resources`, in the game's order.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Resources {
+    pub minerals: i32,
+    pub vespene: i32,
+    pub terrazine: i32,
+    pub custom: i32,
+}
+
+impl Resources {
+    pub fn from_slice(resources: &[i32]) -> Resources {
+        let get = |idx: usize| resources.get(idx).cloned().unwrap_or(0);
+        Resources {
+            minerals: get(0),
+            vespene: get(1),
+            terrazine: get(2),
+            custom: get(3),
+        }
+    }
+}
+
+impl Add for Resources {
+    type Output = Resources;
+
+    fn add(self, other: Resources) -> Resources {
+        Resources {
+            minerals: self.minerals + other.minerals,
+            vespene: self.vespene + other.vespene,
+            terrazine: self.terrazine + other.terrazine,
+            custom: self.custom + other.custom,
+        }
+    }
+}
+
+/// Resources sent by `SResourceTradeEvent`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Transfer {
+    pub gameloop: GameLoop,
+    pub from: u32,
+    pub to: u32,
+    pub resources: Resources,
+}
+
+/// A resource request, not matched up with its fulfil or cancel; see the
+/// module documentation.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Request {
+    pub gameloop: GameLoop,
+    pub player: u32,
+    pub resources: Resources,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum RequestAction {
+    Fulfill,
+    Cancel,
+}
+
+/// A fulfil or cancel of the request the game knows as `request_id`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RequestResponse {
+    pub gameloop: GameLoop,
+    pub player: u32,
+    pub request_id: i32,
+    pub action: RequestAction,
+}
+
+/// A player's alliance flags from `gameloop` on.  Bit `n` of each mask is
+/// player `n`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct AllianceChange {
+    pub gameloop: GameLoop,
+    pub player: u32,
+    pub alliance: u32,
+    pub control: u32,
+}
+
+impl AllianceChange {
+    pub fn allied_with(&self, player: u32) -> bool {
+        player < 32 && self.alliance & (1 << player) != 0
+    }
+
+    pub fn shares_control_with(&self, player: u32) -> bool {
+        player < 32 && self.control & (1 << player) != 0
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Ledger {
+    pub transfers: Vec<Transfer>,
+    pub requests: Vec<Request>,
+    pub responses: Vec<RequestResponse>,
+    pub alliances: Vec<AllianceChange>,
+}
+
+impl Ledger {
+    pub fn new() -> Ledger {
+        Ledger::default()
+    }
+
+    pub fn from_events<I>(events: I) -> typed::Result<Ledger>
+        where I: Iterator<Item=typed::Result<TypedEvent<GameEvent>>>,
+    {
+        let mut ledger = Ledger::new();
+        for event in events {
+            ledger.apply(&try!(event));
+        }
+        Ok(ledger)
+    }
+
+    /// The ledger for the contents of `replay.game.events`.
+    pub fn from_game_events(buf: &[u8]) -> typed::Result<Ledger> {
+        Ledger::from_events(typed::game_events(buf))
+    }
+
+    /// Applies one event; anything but trades, requests and alliance
+    /// changes is ignored.  Events have to arrive in gameloop order.
+    pub fn apply(&mut self, event: &TypedEvent<GameEvent>) {
+        let (gameloop, player) = match event.user_id {
+            Some(player) => (event.gameloop, player),
+            None => return,
+        };
+        match event.event {
+            GameEvent::SResourceTradeEvent(ref trade) => {
+                self.transfers.push(Transfer {
+                    gameloop: gameloop,
+                    from: player,
+                    to: trade.recipient_id as u32,
+                    resources: Resources::from_slice(&trade.resources),
+                });
+            },
+            GameEvent::SResourceRequestEvent(ref request) => {
+                self.requests.push(Request {
+                    gameloop: gameloop,
+                    player: player,
+                    resources: Resources::from_slice(&request.resources),
+                });
+            },
+            GameEvent::SResourceRequestFulfillEvent(ref fulfill) => {
+                self.responses.push(RequestResponse {
+                    gameloop: gameloop,
+                    player: player,
+                    request_id: fulfill.fulfill_request_id,
+                    action: RequestAction::Fulfill,
+                });
+            },
+            GameEvent::SResourceRequestCancelEvent(ref cancel) => {
+                self.responses.push(RequestResponse {
+                    gameloop: gameloop,
+                    player: player,
+                    request_id: cancel.cancel_request_id,
+                    action: RequestAction::Cancel,
+                });
+            },
+            GameEvent::SAllianceEvent(ref alliance) => {
+                self.alliances.push(AllianceChange {
+                    gameloop: gameloop,
+                    player: player,
+                    alliance: alliance.alliance,
+                    control: alliance.control,
+                });
+            },
+            _ => {},
+        }
+    }
+
+    /// Everything `from` gave `to`.
+    pub fn sent(&self, from: u32, to: u32) -> Resources {
+        self.transfers.iter()
+            .filter(|t| t.from == from && t.to == to)
+            .fold(Resources::default(), |acc, t| acc + t.resources)
+    }
+
+    pub fn sent_by(&self, player: u32) -> Resources {
+        self.transfers.iter()
+            .filter(|t| t.from == player)
+            .fold(Resources::default(), |acc, t| acc + t.resources)
+    }
+
+    pub fn received_by(&self, player: u32) -> Resources {
+        self.transfers.iter()
+            .filter(|t| t.to == player)
+            .fold(Resources::default(), |acc, t| acc + t.resources)
+    }
+
+    /// `player`'s alliance flags as of `gameloop`, or `None` if they have
+    /// not changed them by then.
+    pub fn alliance_at(&self, player: u32, gameloop: GameLoop) -> Option<&AllianceChange> {
+        self.alliances.iter()
+            .filter(|change| change.player == player && change.gameloop <= gameloop)
+            .last()
+    }
+}
@@ -9,11 +9,32 @@ extern crate serde_json;
 
 #[cfg(test)]
 mod tests;
+pub mod apm;
+pub mod bank;
+pub mod camera;
+pub mod cheats;
 pub mod common;
+pub mod events;
 pub mod format;
+pub mod ledger;
+pub mod network;
+pub mod outcome;
+pub mod query;
+pub mod s2json;
+pub mod selection;
+pub mod summary;
+pub mod timeline;
+pub mod transcode;
+pub mod typed;
+pub mod value;
 mod versioned_serde;
+mod bitpacked_serde;
 
 pub use versioned_serde::Deserializer as VersionedDeserializer;
+pub use versioned_serde::{Error, ErrorCode};
+pub use bitpacked_serde::Deserializer as BitPackedDeserializer;
+pub use summary::ReplaySummary;
+pub use value::Value;
 
 #[no_mangle]
 fn quux() {}
\ No newline at end of file
@@ -0,0 +1,149 @@
+//! Connection problems and game speed changes, from `SLagMessageEvent`,
+//! `SSetAbsoluteGameSpeedEvent` and `SAddAbsoluteGameSpeedEvent`.
+
+use std::cmp;
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+
+use common::{GameLoop, GameSpeed};
+use typed::{self, GameEvent, TypedEvent};
+
+/// Lag messages for one player at most this many gameloops apart belong to
+/// the same episode.
+pub const LAG_GAP: u32 = 16;
+
+/// A run of lag messages naming the same player.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LagEpisode {
+    pub start: GameLoop,
+    /// The gameloop of the last message, inclusive.
+    pub end: GameLoop,
+    pub messages: u32,
+}
+
+impl LagEpisode {
+    pub fn loops(&self) -> u32 {
+        self.end.0 - self.start.0 + 1
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlayerLag {
+    pub episodes: Vec<LagEpisode>,
+}
+
+impl PlayerLag {
+    pub fn messages(&self) -> u32 {
+        self.episodes.iter().fold(0, |acc, episode| acc + episode.messages)
+    }
+
+    /// Gameloops covered by the player's lag episodes.
+    pub fn lagged_loops(&self) -> u32 {
+        self.episodes.iter().fold(0, |acc, episode| acc + episode.loops())
+    }
+
+    fn add(&mut self, gameloop: GameLoop) {
+        if let Some(episode) = self.episodes.last_mut() {
+            if gameloop.0.wrapping_sub(episode.end.0) <= LAG_GAP {
+                episode.end = gameloop;
+                episode.messages += 1;
+                return;
+            }
+        }
+        self.episodes.push(LagEpisode {
+            start: gameloop,
+            end: gameloop,
+            messages: 1,
+        });
+    }
+}
+
+/// The game speed from `gameloop` on.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SpeedChange {
+    pub gameloop: GameLoop,
+    /// Who changed it.
+    pub user_id: Option<u32>,
+    pub speed: GameSpeed,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkReport {
+    /// `m_gameSpeed` from the details.
+    pub initial_speed: GameSpeed,
+    pub speed_changes: Vec<SpeedChange>,
+    lag: BTreeMap<u32, PlayerLag>,
+}
+
+impl NetworkReport {
+    pub fn new(initial_speed: GameSpeed) -> NetworkReport {
+        NetworkReport {
+            initial_speed: initial_speed,
+            speed_changes: Vec::new(),
+            lag: BTreeMap::new(),
+        }
+    }
+
+    pub fn from_events<I>(events: I, initial_speed: GameSpeed) -> typed::Result<NetworkReport>
+        where I: Iterator<Item=typed::Result<TypedEvent<GameEvent>>>,
+    {
+        let mut report = NetworkReport::new(initial_speed);
+        for event in events {
+            report.apply(&try!(event));
+        }
+        Ok(report)
+    }
+
+    /// The report for the contents of `replay.game.events`.
+    pub fn from_game_events(buf: &[u8], initial_speed: GameSpeed) -> typed::Result<NetworkReport> {
+        NetworkReport::from_events(typed::game_events(buf), initial_speed)
+    }
+
+    /// Applies one event; other than lag and speed events are ignored.
+    /// Events have to arrive in gameloop order.
+    pub fn apply(&mut self, event: &TypedEvent<GameEvent>) {
+        let speed = match event.event {
+            GameEvent::SLagMessageEvent(ref lag) => {
+                self.lag.entry(lag.lagging_player_id as u32)
+                    .or_insert_with(PlayerLag::default)
+                    .add(event.gameloop);
+                return;
+            },
+            GameEvent::SSetAbsoluteGameSpeedEvent(ref set) => set.speed as i32,
+            GameEvent::SAddAbsoluteGameSpeedEvent(ref add) => {
+                self.speed_at(event.gameloop).as_u8() as i32 + add.delta as i32
+            },
+            _ => return,
+        };
+        // clamped to the speeds there are
+        let speed = cmp::max(0, cmp::min(speed, GameSpeed::Faster.as_u8() as i32));
+        self.speed_changes.push(SpeedChange {
+            gameloop: event.gameloop,
+            user_id: event.user_id,
+            speed: GameSpeed::from_u8(speed as u8).unwrap(),
+        });
+    }
+
+    /// Lag by the player that was lagging, keyed by `m_laggingPlayerId`.
+    pub fn lag(&self) -> btree_map::Iter<u32, PlayerLag> {
+        self.lag.iter()
+    }
+
+    pub fn player_lag(&self, player: u32) -> Option<&PlayerLag> {
+        self.lag.get(&player)
+    }
+
+    /// Lag episodes across every player.
+    pub fn lag_episodes(&self) -> usize {
+        self.lag.values().fold(0, |acc, lag| acc + lag.episodes.len())
+    }
+
+    /// The game speed in effect at `gameloop`, after any change made on it.
+    pub fn speed_at(&self, gameloop: GameLoop) -> GameSpeed {
+        self.speed_changes.iter()
+            .take_while(|change| change.gameloop <= gameloop)
+            .last()
+            .map(|change| change.speed)
+            .unwrap_or(self.initial_speed)
+    }
+}
@@ -0,0 +1,154 @@
+//! Who left when, how the game ended and who won, from `SPlayerLeaveEvent`s
+//! and the replay summary.
+
+use std::collections::BTreeSet;
+
+use common::GameLoop;
+use summary::{PlayerResult, ReplaySummary};
+use typed::{self, GameEvent, TypedEvent};
+
+/// Gameloops between the last leave and the end of the replay for the
+/// leave to have ended the game.
+pub const END_SLACK: u32 = 64;
+
+/// A player in the game, named as game events name them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Participant {
+    pub player_id: u32,
+    pub team: u8,
+    pub result: PlayerResult,
+}
+
+impl Participant {
+    /// The summary's players, leaving out those without a lobby slot.
+    pub fn from_summary(summary: &ReplaySummary) -> Vec<Participant> {
+        summary.players.iter().filter_map(|player| {
+            player.user_id.map(|user_id| Participant {
+                player_id: user_id + 1,
+                team: player.lobby_team_id.unwrap_or(player.team_id),
+                result: player.result,
+            })
+        }).collect()
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Leave {
+    pub gameloop: GameLoop,
+    pub player_id: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum EndReason {
+    /// The replay ended on a leave that left a single team in the game.
+    Surrender,
+    /// The replay ended on a leave with several teams still in the game,
+    /// so it stopped for the recording player rather than being decided.
+    Disconnect,
+    /// Nobody left before the replay ended, as when a time limit runs out.
+    Timeout,
+    /// Players left, but not close enough to the end of the replay for
+    /// their leaving to explain it.
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameOutcome {
+    /// In the order players left.
+    pub leaves: Vec<Leave>,
+    pub end: GameLoop,
+    pub end_reason: EndReason,
+    pub winning_team: Option<u8>,
+    pub winners: Vec<u32>,
+    /// Whether the winners were worked out from the leaves because every
+    /// `m_result` was unknown.
+    pub inferred: bool,
+}
+
+impl GameOutcome {
+    /// `end` is the header's `m_elapsedGameLoops`.
+    pub fn new(participants: &[Participant], mut leaves: Vec<Leave>, end: GameLoop) -> GameOutcome {
+        // stable, so leaves on the same gameloop keep their event order
+        leaves.sort_by(|a, b| a.gameloop.cmp(&b.gameloop));
+
+        let mut remaining: BTreeSet<u8> = BTreeSet::new();
+        for participant in participants.iter() {
+            if !leaves.iter().any(|leave| leave.player_id == participant.player_id) {
+                remaining.insert(participant.team);
+            }
+        }
+
+        let last_leave = leaves.iter()
+            .filter(|leave| participants.iter().any(|p| p.player_id == leave.player_id))
+            .last();
+        let end_reason = match last_leave {
+            None => EndReason::Timeout,
+            Some(leave) if end.0.saturating_sub(leave.gameloop.0) > END_SLACK => EndReason::Unknown,
+            Some(_) if remaining.len() <= 1 => EndReason::Surrender,
+            Some(_) => EndReason::Disconnect,
+        };
+
+        let known = participants.iter().any(|p| p.result != PlayerResult::Unknown);
+        let (winners, winning_team, inferred) = if known {
+            let winners: Vec<&Participant> = participants.iter()
+                .filter(|p| p.result == PlayerResult::Victory)
+                .collect();
+            let team = match winners.first() {
+                Some(first) if winners.iter().all(|p| p.team == first.team) => Some(first.team),
+                _ => None,
+            };
+            (winners.iter().map(|p| p.player_id).collect(), team, false)
+        } else if end_reason == EndReason::Surrender && remaining.len() == 1 {
+            let team = *remaining.iter().next().unwrap();
+            let winners = participants.iter()
+                .filter(|p| p.team == team)
+                .map(|p| p.player_id)
+                .collect();
+            (winners, Some(team), true)
+        } else {
+            (Vec::new(), None, false)
+        };
+
+        GameOutcome {
+            leaves: leaves,
+            end: end,
+            end_reason: end_reason,
+            winning_team: winning_team,
+            winners: winners,
+            inferred: inferred,
+        }
+    }
+
+    /// The outcome of a replay given its summary and game events.
+    pub fn from_events<I>(summary: &ReplaySummary, events: I) -> typed::Result<GameOutcome>
+        where I: Iterator<Item=typed::Result<TypedEvent<GameEvent>>>,
+    {
+        let mut leaves = Vec::new();
+        for event in events {
+            let event = try!(event);
+            if let (Some(player_id), &GameEvent::SPlayerLeaveEvent(_)) = (event.user_id, &event.event) {
+                leaves.push(Leave {
+                    gameloop: event.gameloop,
+                    player_id: player_id,
+                });
+            }
+        }
+        Ok(GameOutcome::new(&Participant::from_summary(summary), leaves, summary.elapsed))
+    }
+
+    /// The outcome for the raw contents of `replay.game.events`.
+    pub fn from_game_events(summary: &ReplaySummary, buf: &[u8]) -> typed::Result<GameOutcome> {
+        GameOutcome::from_events(summary, typed::game_events(buf))
+    }
+
+    pub fn first_leave(&self) -> Option<&Leave> {
+        self.leaves.first()
+    }
+
+    /// When `player_id` left, or `None` if they stayed to the end.
+    pub fn left_at(&self, player_id: u32) -> Option<GameLoop> {
+        self.leaves.iter()
+            .find(|leave| leave.player_id == player_id)
+            .map(|leave| leave.gameloop)
+    }
+}
@@ -0,0 +1,419 @@
+//! A small path language over decoded `Value`s.
+//!
+//! ```text
+//! m_title                              dictionary key
+//! m_playerList[0].m_name               array index (negative counts from the end)
+//! m_playerList[*].m_toon.m_id          every element (or every dictionary value)
+//! m_playerList[?m_result==1].m_name    elements matching a filter
+//! m_playerList[?m_userId].m_name       elements where the path is present
+//! ..m_programId                        recursive descent
+//! ['key with spaces']                  quoted key
+//! ```
+//!
+//! Filters compare a relative path against an integer, float, quoted
+//! string, `true`, `false` or `null` using `==`, `!=`, `<`, `<=`, `>` or
+//! `>=`.  Optionals are looked through, and choices can be stepped into by
+//! variant name.  Compile a `Query` once and reuse it across values.
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str;
+
+use value::Value;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Error {
+    /// Byte offset into the query text.
+    pub position: usize,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at offset {}", self.reason, self.position)
+    }
+}
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+/// A compiled query.
+#[derive(Debug, Clone)]
+pub struct Query {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    Key(String),
+    Wildcard,
+    Index(i64),
+    Filter(Box<Filter>),
+    Descend(Box<Step>),
+}
+
+#[derive(Debug, Clone)]
+struct Filter {
+    path: Query,
+    test: Option<(CmpOp, Literal)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Literal {
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Bool(bool),
+    Null,
+}
+
+impl Query {
+    pub fn compile(query: &str) -> Result<Query> {
+        let mut parser = Parser { src: query.as_bytes(), pos: 0 };
+        let compiled = try!(parser.parse_query(false));
+        if parser.pos != parser.src.len() {
+            return Err(parser.error("unexpected character"));
+        }
+        Ok(compiled)
+    }
+
+    /// Every value the query selects, in document order.
+    pub fn select<'a>(&self, root: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![root];
+        for step in self.steps.iter() {
+            let mut next = Vec::new();
+            for node in current.into_iter() {
+                step.apply(node, &mut next);
+            }
+            current = next;
+        }
+        current
+    }
+
+    pub fn first<'a>(&self, root: &'a Value) -> Option<&'a Value> {
+        self.select(root).into_iter().next()
+    }
+
+    pub fn matches(&self, root: &Value) -> bool {
+        !self.select(root).is_empty()
+    }
+}
+
+impl Value {
+    /// Compiles and runs `query` once.  Use `Query::compile` for queries
+    /// that run against many values.
+    pub fn query(&self, query: &str) -> Result<Vec<&Value>> {
+        let compiled = try!(Query::compile(query));
+        Ok(compiled.select(self))
+    }
+}
+
+impl Step {
+    fn apply<'a>(&self, node: &'a Value, out: &mut Vec<&'a Value>) {
+        let node = node.unwrap_optional();
+        match *self {
+            Step::Key(ref key) => {
+                if let Some(val) = node.get(key) {
+                    out.push(val);
+                }
+            },
+            Step::Wildcard => children(node, out),
+            Step::Index(index) => {
+                if let Value::Array(ref vec) = *node {
+                    let index = if index < 0 { vec.len() as i64 + index } else { index };
+                    if 0 <= index && index < vec.len() as i64 {
+                        out.push(&vec[index as usize]);
+                    }
+                }
+            },
+            Step::Filter(ref filter) => {
+                let mut candidates = Vec::new();
+                children(node, &mut candidates);
+                for candidate in candidates.into_iter() {
+                    if filter.test(candidate) {
+                        out.push(candidate);
+                    }
+                }
+            },
+            Step::Descend(ref step) => descend(node, step, out),
+        }
+    }
+}
+
+fn children<'a>(node: &'a Value, out: &mut Vec<&'a Value>) {
+    match *node.unwrap_optional() {
+        Value::Array(ref vec) => out.extend(vec.iter()),
+        Value::Dict(ref map) => out.extend(map.values()),
+        Value::Choice(_, ref val) => out.push(&**val),
+        _ => (),
+    }
+}
+
+fn descend<'a>(node: &'a Value, step: &Step, out: &mut Vec<&'a Value>) {
+    step.apply(node, out);
+    let mut kids = Vec::new();
+    children(node, &mut kids);
+    for kid in kids.into_iter() {
+        descend(kid, step, out);
+    }
+}
+
+impl Filter {
+    fn test(&self, node: &Value) -> bool {
+        let found = self.path.select(node);
+        match self.test {
+            None => found.iter().any(|val| !val.unwrap_optional().is_null()),
+            Some((op, ref literal)) => found.iter().any(|val| op.eval(compare(val, literal))),
+        }
+    }
+}
+
+impl CmpOp {
+    fn eval(&self, ordering: Option<Ordering>) -> bool {
+        match (*self, ordering) {
+            (CmpOp::Ne, ord) => ord != Some(Ordering::Equal),
+            (_, None) => false,
+            (CmpOp::Eq, Some(ord)) => ord == Ordering::Equal,
+            (CmpOp::Lt, Some(ord)) => ord == Ordering::Less,
+            (CmpOp::Le, Some(ord)) => ord != Ordering::Greater,
+            (CmpOp::Gt, Some(ord)) => ord == Ordering::Greater,
+            (CmpOp::Ge, Some(ord)) => ord != Ordering::Less,
+        }
+    }
+}
+
+/// Orders a value against a literal, or `None` if they are not comparable.
+fn compare(val: &Value, literal: &Literal) -> Option<Ordering> {
+    match *literal {
+        Literal::Int(lit) => match val.as_i64() {
+            Ok(num) => Some(num.cmp(&lit)),
+            Err(()) => val.as_f64().ok().and_then(|num| num.partial_cmp(&(lit as f64))),
+        },
+        Literal::Float(lit) => val.as_f64().ok().and_then(|num| num.partial_cmp(&lit)),
+        Literal::Str(ref lit) => val.as_bytes().ok().map(|buf| buf.cmp(lit.as_bytes())),
+        Literal::Bool(lit) => val.as_bool().ok().map(|b| b.cmp(&lit)),
+        Literal::Null => {
+            if val.unwrap_optional().is_null() { Some(Ordering::Equal) } else { None }
+        },
+    }
+}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, reason: &'static str) -> Error {
+        Error { position: self.pos, reason: reason }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.src.get(self.pos).cloned()
+    }
+
+    fn eat(&mut self, ch: u8) -> bool {
+        if self.peek() == Some(ch) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, ch: u8, reason: &'static str) -> Result<()> {
+        if self.eat(ch) { Ok(()) } else { Err(self.error(reason)) }
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.peek() == Some(b' ') {
+            self.pos += 1;
+        }
+    }
+
+    /// Inside a filter, the path ends at the comparison operator or the
+    /// closing bracket rather than at the end of input.
+    fn parse_query(&mut self, in_filter: bool) -> Result<Query> {
+        let mut steps = Vec::new();
+        if self.eat(b'$') || (in_filter && self.eat(b'@')) {
+            // explicit root
+        } else if self.peek().map_or(false, is_key_char) {
+            steps.push(Step::Key(try!(self.parse_key())));
+        }
+        loop {
+            match self.peek() {
+                None => break,
+                Some(b'.') => {
+                    self.pos += 1;
+                    if self.eat(b'.') {
+                        let step = try!(self.parse_descend_target());
+                        steps.push(Step::Descend(Box::new(step)));
+                    } else if self.eat(b'*') {
+                        steps.push(Step::Wildcard);
+                    } else {
+                        steps.push(Step::Key(try!(self.parse_key())));
+                    }
+                },
+                Some(b'[') => steps.push(try!(self.parse_bracket())),
+                Some(_) if in_filter => break,
+                Some(_) => return Err(self.error("unexpected character")),
+            }
+        }
+        Ok(Query { steps: steps })
+    }
+
+    fn parse_descend_target(&mut self) -> Result<Step> {
+        match self.peek() {
+            Some(b'*') => {
+                self.pos += 1;
+                Ok(Step::Wildcard)
+            },
+            Some(b'[') => self.parse_bracket(),
+            _ => Ok(Step::Key(try!(self.parse_key()))),
+        }
+    }
+
+    fn parse_key(&mut self) -> Result<String> {
+        let start = self.pos;
+        while self.peek().map_or(false, is_key_char) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error("expected a key"));
+        }
+        Ok(str::from_utf8(&self.src[start..self.pos]).unwrap().to_string())
+    }
+
+    fn parse_bracket(&mut self) -> Result<Step> {
+        try!(self.expect(b'[', "expected '['"));
+        self.skip_spaces();
+        let step = match self.peek() {
+            Some(b'*') => {
+                self.pos += 1;
+                Step::Wildcard
+            },
+            Some(b'?') => {
+                self.pos += 1;
+                self.skip_spaces();
+                Step::Filter(Box::new(try!(self.parse_filter())))
+            },
+            Some(b'\'') | Some(b'"') => Step::Key(try!(self.parse_quoted())),
+            Some(b'-') | Some(b'0'...b'9') => {
+                match try!(self.parse_number()) {
+                    Literal::Int(index) => Step::Index(index),
+                    _ => return Err(self.error("expected an integer index")),
+                }
+            },
+            _ => return Err(self.error("expected an index, '*', '?' or a quoted key")),
+        };
+        self.skip_spaces();
+        try!(self.expect(b']', "expected ']'"));
+        Ok(step)
+    }
+
+    fn parse_filter(&mut self) -> Result<Filter> {
+        let path = try!(self.parse_query(true));
+        self.skip_spaces();
+        if self.peek() == Some(b']') {
+            return Ok(Filter { path: path, test: None });
+        }
+        let op = try!(self.parse_op());
+        self.skip_spaces();
+        let literal = try!(self.parse_literal());
+        Ok(Filter { path: path, test: Some((op, literal)) })
+    }
+
+    fn parse_op(&mut self) -> Result<CmpOp> {
+        let rest = &self.src[self.pos..];
+        let (op, len) = if rest.starts_with(b"==") {
+            (CmpOp::Eq, 2)
+        } else if rest.starts_with(b"!=") {
+            (CmpOp::Ne, 2)
+        } else if rest.starts_with(b"<=") {
+            (CmpOp::Le, 2)
+        } else if rest.starts_with(b">=") {
+            (CmpOp::Ge, 2)
+        } else if rest.starts_with(b"<") {
+            (CmpOp::Lt, 1)
+        } else if rest.starts_with(b">") {
+            (CmpOp::Gt, 1)
+        } else {
+            return Err(self.error("expected a comparison operator"));
+        };
+        self.pos += len;
+        Ok(op)
+    }
+
+    fn parse_literal(&mut self) -> Result<Literal> {
+        match self.peek() {
+            Some(b'\'') | Some(b'"') => Ok(Literal::Str(try!(self.parse_quoted()))),
+            Some(b'-') | Some(b'0'...b'9') => self.parse_number(),
+            _ => {
+                let word = try!(self.parse_key());
+                match &word[..] {
+                    "true" => Ok(Literal::Bool(true)),
+                    "false" => Ok(Literal::Bool(false)),
+                    "null" => Ok(Literal::Null),
+                    _ => Err(self.error("expected a literal")),
+                }
+            },
+        }
+    }
+
+    fn parse_number(&mut self) -> Result<Literal> {
+        let start = self.pos;
+        self.eat(b'-');
+        while self.peek().map_or(false, |ch| (b'0' <= ch && ch <= b'9') || ch == b'.') {
+            self.pos += 1;
+        }
+        let text = str::from_utf8(&self.src[start..self.pos]).unwrap();
+        if let Ok(num) = text.parse::<i64>() {
+            return Ok(Literal::Int(num));
+        }
+        match text.parse::<f64>() {
+            Ok(num) => Ok(Literal::Float(num)),
+            Err(_) => Err(Error { position: start, reason: "invalid number" }),
+        }
+    }
+
+    /// Single or double quoted, with backslash escaping the next character.
+    fn parse_quoted(&mut self) -> Result<String> {
+        let quote = self.src[self.pos];
+        self.pos += 1;
+        let mut out = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated string")),
+                Some(b'\\') => {
+                    self.pos += 1;
+                    match self.peek() {
+                        Some(ch) => out.push(ch),
+                        None => return Err(self.error("unterminated string")),
+                    }
+                },
+                Some(ch) if ch == quote => {
+                    self.pos += 1;
+                    break;
+                },
+                Some(ch) => out.push(ch),
+            }
+            self.pos += 1;
+        }
+        // the source was a str and escapes only drop ASCII backslashes
+        Ok(String::from_utf8(out).unwrap())
+    }
+}
+
+fn is_key_char(ch: u8) -> bool {
+    (b'a' <= ch && ch <= b'z') || (b'A' <= ch && ch <= b'Z') ||
+        (b'0' <= ch && ch <= b'9') || ch == b'_'
+}
@@ -0,0 +1,252 @@
+//! JSON output matching what s2protocol's `json.dumps` produces, so the two
+//! can be diffed byte for byte.
+//!
+//! The conventions followed are s2protocol's, not serde_json's:
+//!
+//! * keys are sorted and non-ASCII is escaped as `\u00XX`;
+//! * blobs, strings and FourCCs are written byte-for-byte as Latin-1;
+//! * choices are single-entry objects;
+//! * bit arrays are `[length, integer]`, the integer in decimal however wide;
+//! * reals are one-element arrays, since `struct.unpack` returns a tuple;
+//! * events carry `_event`, `_eventid`, `_gameloop`, `_userid` and `_bits`.
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use events::{self, Event, EventIter, EventDecoder};
+use format::protocol15405::{
+    TYPEINFOS,
+    REPLAY_HEADER_TYPEID,
+    GAME_DETAILS_TYPEID,
+    REPLAY_INITDATA_TYPEID,
+};
+use versioned_serde::{self, Result};
+use bitpacked_serde;
+use value::Value;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Style {
+    /// `json.dumps(value, sort_keys=True)`: one line, `", "` between items.
+    Line,
+    /// `json.dumps(value, sort_keys=True, indent=n, separators=(',', ': '))`.
+    Indent(usize),
+}
+
+pub fn to_writer<W: Write>(writer: &mut W, value: &Value, style: Style) -> io::Result<()> {
+    write_value(writer, value, style, 0)
+}
+
+pub fn to_string(value: &Value, style: Style) -> String {
+    let mut out = Vec::new();
+    to_writer(&mut out, value, style).unwrap();
+    // everything we write is escaped down to ASCII
+    String::from_utf8(out).unwrap()
+}
+
+/// The event as s2protocol yields it: its fields plus the framing keys.
+pub fn event_value(event: &Event) -> Value {
+    let mut dict = match event.value {
+        Value::Dict(ref dict) => dict.clone(),
+        _ => BTreeMap::new(),
+    };
+    dict.insert("_event".to_string(), Value::String(event.name.to_string()));
+    dict.insert("_eventid".to_string(), Value::U64(event.event_id as u64));
+    dict.insert("_gameloop".to_string(), Value::U64(event.gameloop.0 as u64));
+    if let Some(user_id) = event.user_id {
+        let mut user = BTreeMap::new();
+        user.insert("m_playerId".to_string(), Value::U64(user_id as u64));
+        dict.insert("_userid".to_string(), Value::Dict(user));
+    }
+    dict.insert("_bits".to_string(), Value::U64(event.bits as u64));
+    Value::Dict(dict)
+}
+
+/// Writes the decoded replay header, i.e. the MPQ user data contents.
+pub fn write_header<W: Write>(writer: &mut W, buf: &[u8], style: Style) -> Result<()> {
+    let value = try!(versioned_serde::Deserializer::new(buf, TYPEINFOS, REPLAY_HEADER_TYPEID).read_value());
+    try!(to_writer(writer, &value, style));
+    Ok(try!(writer.write_all(b"\n")))
+}
+
+/// Writes the decoded contents of `replay.details`.
+pub fn write_details<W: Write>(writer: &mut W, buf: &[u8], style: Style) -> Result<()> {
+    let value = try!(versioned_serde::Deserializer::new(buf, TYPEINFOS, GAME_DETAILS_TYPEID).read_value());
+    try!(to_writer(writer, &value, style));
+    Ok(try!(writer.write_all(b"\n")))
+}
+
+/// Writes the decoded contents of `replay.initData`.
+pub fn write_init_data<W: Write>(writer: &mut W, buf: &[u8], style: Style) -> Result<()> {
+    let value = try!(bitpacked_serde::Deserializer::new(buf, TYPEINFOS, REPLAY_INITDATA_TYPEID).read_value());
+    try!(to_writer(writer, &value, style));
+    Ok(try!(writer.write_all(b"\n")))
+}
+
+/// Writes each event followed by a newline.
+pub fn write_events<W, D>(writer: &mut W, events: EventIter<D>, style: Style) -> Result<()>
+    where W: Write, D: EventDecoder,
+{
+    for event in events {
+        let event = try!(event);
+        try!(to_writer(writer, &event_value(&event), style));
+        try!(writer.write_all(b"\n"));
+    }
+    Ok(())
+}
+
+/// Writes the events of `replay.game.events`.
+pub fn write_game_events<W: Write>(writer: &mut W, buf: &[u8], style: Style) -> Result<()> {
+    write_events(writer, events::game_events(buf), style)
+}
+
+/// Writes the events of `replay.message.events`.
+pub fn write_message_events<W: Write>(writer: &mut W, buf: &[u8], style: Style) -> Result<()> {
+    write_events(writer, events::message_events(buf), style)
+}
+
+fn write_value<W: Write>(w: &mut W, value: &Value, style: Style, depth: usize) -> io::Result<()> {
+    match *value {
+        Value::I64(val) => write!(w, "{}", val),
+        Value::U64(val) => write!(w, "{}", val),
+        Value::F64(val) => write!(w, "[{}]", float_repr(val)),
+        Value::Bytes(ref val) => write_latin1(w, val),
+        Value::String(ref val) => write_latin1(w, val.as_bytes()),
+        Value::FourCC(ref val) => write_latin1(w, val),
+        Value::Boolean(val) => w.write_all(if val { b"true" } else { b"false" }),
+        Value::Null => w.write_all(b"null"),
+        Value::Optional(ref val) => write_value(w, val, style, depth),
+        Value::BitArray(len, ref bits) => {
+            write!(w, "[{}, {}]", len, bytes_to_decimal(bits))
+        },
+        Value::AlignedBitArray(len, ref bits) => {
+            try!(write!(w, "[{}, ", len));
+            try!(write_latin1(w, bits));
+            w.write_all(b"]")
+        },
+        Value::Array(ref items) => {
+            if items.is_empty() {
+                return w.write_all(b"[]");
+            }
+            try!(w.write_all(b"["));
+            for (idx, item) in items.iter().enumerate() {
+                try!(write_separator(w, style, depth + 1, idx == 0));
+                try!(write_value(w, item, style, depth + 1));
+            }
+            try!(write_close(w, style, depth));
+            w.write_all(b"]")
+        },
+        Value::Dict(ref dict) => {
+            if dict.is_empty() {
+                return w.write_all(b"{}");
+            }
+            try!(w.write_all(b"{"));
+            for (idx, (key, val)) in dict.iter().enumerate() {
+                try!(write_separator(w, style, depth + 1, idx == 0));
+                try!(write_latin1(w, key.as_bytes()));
+                try!(w.write_all(b": "));
+                try!(write_value(w, val, style, depth + 1));
+            }
+            try!(write_close(w, style, depth));
+            w.write_all(b"}")
+        },
+        Value::Choice(ref name, ref val) => {
+            try!(w.write_all(b"{"));
+            try!(write_separator(w, style, depth + 1, true));
+            try!(write_latin1(w, name.as_bytes()));
+            try!(w.write_all(b": "));
+            try!(write_value(w, val, style, depth + 1));
+            try!(write_close(w, style, depth));
+            w.write_all(b"}")
+        },
+    }
+}
+
+fn write_separator<W: Write>(w: &mut W, style: Style, depth: usize, first: bool) -> io::Result<()> {
+    match style {
+        Style::Line => if first { Ok(()) } else { w.write_all(b", ") },
+        Style::Indent(width) => {
+            if !first {
+                try!(w.write_all(b","));
+            }
+            write_newline(w, width * depth)
+        },
+    }
+}
+
+fn write_close<W: Write>(w: &mut W, style: Style, depth: usize) -> io::Result<()> {
+    match style {
+        Style::Line => Ok(()),
+        Style::Indent(width) => write_newline(w, width * depth),
+    }
+}
+
+fn write_newline<W: Write>(w: &mut W, indent: usize) -> io::Result<()> {
+    try!(w.write_all(b"\n"));
+    for _ in 0..indent {
+        try!(w.write_all(b" "));
+    }
+    Ok(())
+}
+
+/// Each byte is one code point, as with Python's `encoding="ISO-8859-1"`,
+/// escaped the way `ensure_ascii` does.
+fn write_latin1<W: Write>(w: &mut W, buf: &[u8]) -> io::Result<()> {
+    try!(w.write_all(b"\""));
+    for &byte in buf.iter() {
+        try!(match byte {
+            b'"' => w.write_all(b"\\\""),
+            b'\\' => w.write_all(b"\\\\"),
+            b'\n' => w.write_all(b"\\n"),
+            b'\r' => w.write_all(b"\\r"),
+            b'\t' => w.write_all(b"\\t"),
+            0x08 => w.write_all(b"\\b"),
+            0x0c => w.write_all(b"\\f"),
+            b' '...b'~' => w.write_all(&[byte]),
+            _ => write!(w, "\\u{:04x}", byte),
+        });
+    }
+    w.write_all(b"\"")
+}
+
+/// Renders a right-aligned big-endian byte string as a decimal integer.
+fn bytes_to_decimal(buf: &[u8]) -> String {
+    let mut num: Vec<u8> = buf.iter().cloned().skip_while(|&b| b == 0).collect();
+    if num.is_empty() {
+        return "0".to_string();
+    }
+    let mut digits = Vec::new();
+    while !num.is_empty() {
+        let mut rem: u32 = 0;
+        for byte in num.iter_mut() {
+            let cur = rem << 8 | *byte as u32;
+            *byte = (cur / 10) as u8;
+            rem = cur % 10;
+        }
+        digits.push(b'0' + rem as u8);
+        let zeros = num.iter().take_while(|&&b| b == 0).count();
+        num.drain(..zeros);
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap()
+}
+
+/// Python's `repr(float)`: shortest round-trip digits, with an exponent
+/// outside of `1e-4 <= |val| < 1e16`.
+fn float_repr(val: f64) -> String {
+    if val.is_nan() {
+        return "NaN".to_string();
+    }
+    if val.is_infinite() {
+        return if val > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+    }
+    let abs = val.abs();
+    if abs == 0.0 || (1e-4 <= abs && abs < 1e16) {
+        let out = format!("{}", val);
+        return if out.contains('.') { out } else { out + ".0" };
+    }
+    let out = format!("{:e}", val);
+    let (mantissa, exp) = out.split_at(out.find('e').unwrap());
+    let exp: i32 = exp[1..].parse().unwrap();
+    let sign = if exp < 0 { '-' } else { '+' };
+    format!("{}e{}{:02}", mantissa, sign, exp.abs())
+}
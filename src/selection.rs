@@ -0,0 +1,321 @@
+//! Rebuilds each player's selection and control groups from game events.
+//!
+//! The game orders a group's subgroups by each unit type's subgroup
+//! priority, which comes from game data this crate doesn't have.  Unit link
+//! order stands in for it, so for some mixes of unit types removal masks
+//! drop the wrong units.  Sync checks only compare counts and don't notice.
+
+use std::collections::BTreeMap;
+use std::collections::btree_map;
+
+use common::GameLoop;
+use typed::{self, BitArray, GameEvent, TypedEvent};
+use format::protocol15405_events::{
+    SSelectionDeltaEvent,
+    SControlGroupUpdateEvent,
+    SSelectionSyncCheckEvent,
+};
+
+/// Number of hotkeyed control groups.
+pub const CONTROL_GROUPS: usize = 10;
+
+/// The control group id selection events use for the active selection.
+pub const ACTIVE_SELECTION: usize = 10;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Unit {
+    pub tag: u32,
+    pub unit_link: u16,
+}
+
+/// `m_controlGroupUpdate` of `SControlGroupUpdateEvent`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ControlGroupUpdate {
+    /// Replaces the group with the active selection.
+    Set,
+    /// Adds the active selection to the group.
+    Add,
+    /// Selects the group.
+    Get,
+    Clear,
+}
+
+impl ControlGroupUpdate {
+    pub fn from_u8(val: u8) -> Option<ControlGroupUpdate> {
+        match val {
+            0 => Some(ControlGroupUpdate::Set),
+            1 => Some(ControlGroupUpdate::Add),
+            2 => Some(ControlGroupUpdate::Get),
+            3 => Some(ControlGroupUpdate::Clear),
+            _ => None,
+        }
+    }
+}
+
+/// How often one control group's hotkey was used.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct HotkeyCounts {
+    pub set: u32,
+    pub add: u32,
+    pub get: u32,
+    pub clear: u32,
+}
+
+impl HotkeyCounts {
+    pub fn total(&self) -> u32 {
+        self.set + self.add + self.get + self.clear
+    }
+}
+
+/// A sync check whose counts disagree with the rebuilt selection.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Desync {
+    pub gameloop: GameLoop,
+    pub player: u32,
+    pub control_group: usize,
+    pub expected_count: usize,
+    pub actual_count: usize,
+    pub expected_subgroups: usize,
+    pub actual_subgroups: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlayerSelection {
+    // control groups 0-9, then the active selection
+    groups: Vec<Vec<Unit>>,
+    hotkeys: Vec<HotkeyCounts>,
+}
+
+impl PlayerSelection {
+    fn new() -> PlayerSelection {
+        PlayerSelection {
+            groups: vec![Vec::new(); CONTROL_GROUPS + 1],
+            hotkeys: vec![HotkeyCounts::default(); CONTROL_GROUPS],
+        }
+    }
+
+    pub fn selection(&self) -> &[Unit] {
+        &self.groups[ACTIVE_SELECTION]
+    }
+
+    /// Units in control group `idx`, or `None` if there is no such group.
+    pub fn control_group(&self, idx: usize) -> Option<&[Unit]> {
+        if idx < CONTROL_GROUPS {
+            Some(&self.groups[idx])
+        } else {
+            None
+        }
+    }
+
+    pub fn hotkeys(&self, idx: usize) -> Option<&HotkeyCounts> {
+        self.hotkeys.get(idx)
+    }
+
+    /// Hotkey uses summed over every control group.
+    pub fn hotkey_total(&self) -> HotkeyCounts {
+        self.hotkeys.iter().fold(HotkeyCounts::default(), |acc, counts| HotkeyCounts {
+            set: acc.set + counts.set,
+            add: acc.add + counts.add,
+            get: acc.get + counts.get,
+            clear: acc.clear + counts.clear,
+        })
+    }
+
+    fn apply_delta(&mut self, event: &SSelectionDeltaEvent) {
+        let group = match self.groups.get_mut(event.control_group_id as usize) {
+            Some(group) => group,
+            None => return,
+        };
+        let delta = &event.delta;
+        remove_masked(group, &delta.remove_mask);
+
+        // subgroups list unit types in order, each claiming the next
+        // `count` tags
+        let mut tags = delta.add_unit_tags.iter();
+        for subgroup in delta.add_subgroups.iter() {
+            for _ in 0..subgroup.count {
+                match tags.next() {
+                    Some(&tag) => insert_sorted(group, Unit {
+                        tag: tag,
+                        unit_link: subgroup.unit_link,
+                    }),
+                    None => return,
+                }
+            }
+        }
+    }
+
+    fn apply_update(&mut self, event: &SControlGroupUpdateEvent) {
+        let idx = event.control_group_index as usize;
+        let update = match ControlGroupUpdate::from_u8(event.control_group_update) {
+            Some(update) => update,
+            None => return,
+        };
+        if CONTROL_GROUPS <= idx {
+            return;
+        }
+
+        let counts = &mut self.hotkeys[idx];
+        let groups = &mut self.groups;
+        match update {
+            ControlGroupUpdate::Set => {
+                counts.set += 1;
+                groups[idx] = groups[ACTIVE_SELECTION].clone();
+            },
+            ControlGroupUpdate::Add => {
+                counts.add += 1;
+                let added: Vec<Unit> = groups[ACTIVE_SELECTION].iter()
+                    .filter(|unit| !groups[idx].contains(unit))
+                    .cloned()
+                    .collect();
+                for unit in added {
+                    insert_sorted(&mut groups[idx], unit);
+                }
+            },
+            ControlGroupUpdate::Get => {
+                counts.get += 1;
+                // the mask drops units that have died since the group was
+                // last used, from the group as well as the selection
+                if let Some(ref mask) = event.mask {
+                    remove_masked(&mut groups[idx], mask);
+                }
+                groups[ACTIVE_SELECTION] = groups[idx].clone();
+            },
+            ControlGroupUpdate::Clear => {
+                counts.clear += 1;
+                groups[idx].clear();
+            },
+        }
+    }
+
+    fn check_sync(&self, gameloop: GameLoop, player: u32, event: &SSelectionSyncCheckEvent) -> Option<Desync> {
+        let group = match self.groups.get(event.control_group_id as usize) {
+            Some(group) => group,
+            None => return None,
+        };
+        let data = &event.selection_sync_data;
+        let desync = Desync {
+            gameloop: gameloop,
+            player: player,
+            control_group: event.control_group_id as usize,
+            expected_count: data.count as usize,
+            actual_count: group.len(),
+            expected_subgroups: data.subgroup_count as usize,
+            actual_subgroups: subgroup_count(group),
+        };
+        if desync.expected_count == desync.actual_count && desync.expected_subgroups == desync.actual_subgroups {
+            return None;
+        }
+        Some(desync)
+    }
+}
+
+/// Drops unit `i` wherever bit `i` of the mask is set.  Masks are trimmed
+/// after their last set bit, so they may be shorter than the group.
+fn remove_masked(group: &mut Vec<Unit>, mask: &BitArray) {
+    let mut idx = 0;
+    group.retain(|_| {
+        let keep = !mask.bit(idx);
+        idx += 1;
+        keep
+    });
+}
+
+/// Groups keep their units by unit link and then tag, approximating the
+/// game's subgroup order.  Removal masks index that order, so added units
+/// can't just be appended.
+fn insert_sorted(group: &mut Vec<Unit>, unit: Unit) {
+    let key = (unit.unit_link, unit.tag);
+    let pos = group.iter()
+        .position(|other| key < (other.unit_link, other.tag))
+        .unwrap_or(group.len());
+    group.insert(pos, unit);
+}
+
+/// A subgroup holds the units of one type.
+fn subgroup_count(group: &[Unit]) -> usize {
+    let mut links: Vec<u16> = group.iter().map(|unit| unit.unit_link).collect();
+    links.sort();
+    links.dedup();
+    links.len()
+}
+
+/// Every player's selection state, built up one game event at a time.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SelectionTracker {
+    players: BTreeMap<u32, PlayerSelection>,
+    desyncs: Vec<Desync>,
+}
+
+impl SelectionTracker {
+    pub fn new() -> SelectionTracker {
+        SelectionTracker {
+            players: BTreeMap::new(),
+            desyncs: Vec::new(),
+        }
+    }
+
+    /// State after applying every event up to and including `gameloop`.
+    pub fn at<I>(events: I, gameloop: GameLoop) -> typed::Result<SelectionTracker>
+        where I: Iterator<Item=typed::Result<TypedEvent<GameEvent>>>,
+    {
+        let mut tracker = SelectionTracker::new();
+        for event in events {
+            let event = try!(event);
+            if gameloop < event.gameloop {
+                break;
+            }
+            tracker.apply(&event);
+        }
+        Ok(tracker)
+    }
+
+    /// State after the whole of `replay.game.events`.
+    pub fn from_game_events(buf: &[u8]) -> typed::Result<SelectionTracker> {
+        SelectionTracker::at(typed::game_events(buf), GameLoop(!0))
+    }
+
+    /// Applies one event.  Events other than selection deltas, control
+    /// group updates and sync checks are ignored.
+    pub fn apply(&mut self, event: &TypedEvent<GameEvent>) {
+        let player = match event.user_id {
+            Some(player) => player,
+            None => return,
+        };
+        match event.event {
+            GameEvent::SSelectionDeltaEvent(ref delta) => {
+                self.player_mut(player).apply_delta(delta);
+            },
+            GameEvent::SControlGroupUpdateEvent(ref update) => {
+                self.player_mut(player).apply_update(update);
+            },
+            GameEvent::SSelectionSyncCheckEvent(ref check) => {
+                let desync = self.player_mut(player).check_sync(event.gameloop, player, check);
+                if let Some(desync) = desync {
+                    self.desyncs.push(desync);
+                }
+            },
+            _ => {},
+        }
+    }
+
+    fn player_mut(&mut self, player: u32) -> &mut PlayerSelection {
+        self.players.entry(player).or_insert_with(PlayerSelection::new)
+    }
+
+    pub fn player(&self, player: u32) -> Option<&PlayerSelection> {
+        self.players.get(&player)
+    }
+
+    /// Players in user id order.
+    pub fn players(&self) -> btree_map::Iter<u32, PlayerSelection> {
+        self.players.iter()
+    }
+
+    /// Sync checks that disagreed with the rebuilt state.  Each one means
+    /// an event was misread or a rule here is wrong, so everything after it
+    /// for that player is suspect.
+    pub fn desyncs(&self) -> &[Desync] {
+        &self.desyncs
+    }
+}
@@ -0,0 +1,305 @@
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use serde::de;
+use serde_json::value::Value;
+
+use common::{Color, FileTime, UtcOffset, GameLoop, GameSpeed};
+use format::protocol15405::{
+    TYPEINFOS,
+    REPLAY_HEADER_TYPEID,
+    GAME_DETAILS_TYPEID,
+    REPLAY_INITDATA_TYPEID,
+};
+use cheats::CheatReport;
+use network::NetworkReport;
+use typed;
+use versioned_serde::{self, Deserializer as VersionedDeserializer};
+use bitpacked_serde::Deserializer as BitPackedDeserializer;
+
+#[derive(Debug)]
+pub enum Error {
+    Decode(versioned_serde::Error),
+    MissingField(&'static str),
+    InvalidField(&'static str),
+}
+
+impl From<versioned_serde::Error> for Error {
+    fn from(e: versioned_serde::Error) -> Error {
+        Error::Decode(e)
+    }
+}
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+/// `m_control` of a lobby slot: 0 is open and 1 closed, 2 and up are taken
+/// by a human or a computer.
+const SLOT_CONTROL_HUMAN: u64 = 2;
+
+/// `m_version` from the replay header.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub revision: u32,
+    pub build: u32,
+    pub base_build: u32,
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}.{}", self.major, self.minor, self.revision, self.build)
+    }
+}
+
+/// `m_result` from the details player list.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PlayerResult {
+    Unknown,
+    Victory,
+    Defeat,
+    Tie,
+}
+
+impl PlayerResult {
+    pub fn from_u8(val: u8) -> Option<PlayerResult> {
+        match val {
+            0 => Some(PlayerResult::Unknown),
+            1 => Some(PlayerResult::Victory),
+            2 => Some(PlayerResult::Defeat),
+            3 => Some(PlayerResult::Tie),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PlayerSummary {
+    pub name: String,
+    pub region: u8,
+    pub realm: u32,
+    pub toon_id: u64,
+    pub team_id: u8,
+    pub race: String,
+    pub result: PlayerResult,
+    pub color: Color,
+    pub handicap: u8,
+    pub control: u8,
+    /// The user in the player's lobby slot.  Game events name players by
+    /// `user_id + 1`.
+    pub user_id: Option<u32>,
+    /// `m_teamId` of the lobby slot.  Early builds' details can disagree
+    /// with it; the slot's team is the one that agrees with `m_result`.
+    pub lobby_team_id: Option<u8>,
+}
+
+#[derive(Debug)]
+pub struct ObserverSummary {
+    pub user_id: Option<u32>,
+    pub name: String,
+}
+
+/// The facts most consumers want from a replay, gathered from the header,
+/// details and init data streams.
+#[derive(Debug)]
+pub struct ReplaySummary {
+    pub title: String,
+    pub version: Version,
+    pub elapsed: GameLoop,
+    pub game_speed: GameSpeed,
+    pub start_time: FileTime,
+    pub utc_offset: UtcOffset,
+    pub players: Vec<PlayerSummary>,
+    pub observers: Vec<ObserverSummary>,
+    pub is_blizzard_map: bool,
+    /// Lag and game speed changes, once `attach_network` has been called.
+    pub network: Option<NetworkReport>,
+    /// Cheats used, once `attach_cheats` has been called.
+    pub cheats: Option<CheatReport>,
+}
+
+impl ReplaySummary {
+    /// Decodes the raw `header`, `replay.details` and `replay.initData`
+    /// contents and summarizes them.
+    pub fn decode(header: &[u8], details: &[u8], init_data: &[u8]) -> Result<ReplaySummary> {
+        let header: Value = try!(de::Deserialize::deserialize(
+            &mut VersionedDeserializer::new(header, TYPEINFOS, REPLAY_HEADER_TYPEID)));
+        let details: Value = try!(de::Deserialize::deserialize(
+            &mut VersionedDeserializer::new(details, TYPEINFOS, GAME_DETAILS_TYPEID)));
+        let init_data: Value = try!(de::Deserialize::deserialize(
+            &mut BitPackedDeserializer::new(init_data, TYPEINFOS, REPLAY_INITDATA_TYPEID)));
+        ReplaySummary::from_values(&header, &details, &init_data)
+    }
+
+    /// Summarizes already-decoded header, details and init data.
+    pub fn from_values(header: &Value, details: &Value, init_data: &Value) -> Result<ReplaySummary> {
+        let version = Version {
+            major: try!(u64_field(header, &["m_version", "m_major"])) as u32,
+            minor: try!(u64_field(header, &["m_version", "m_minor"])) as u32,
+            revision: try!(u64_field(header, &["m_version", "m_revision"])) as u32,
+            build: try!(u64_field(header, &["m_version", "m_build"])) as u32,
+            base_build: try!(u64_field(header, &["m_version", "m_baseBuild"])) as u32,
+        };
+        let elapsed = try!(u64_field(header, &["m_elapsedGameLoops"])) as u32;
+
+        let game_speed = try!(u64_field(details, &["m_gameSpeed"]));
+        let game_speed = try!(GameSpeed::from_u8(game_speed as u8)
+            .ok_or(Error::InvalidField("m_gameSpeed")));
+        let time_utc = try!(i64_field(details, &["m_timeUTC"]));
+        let time_local_offset = try!(i64_field(details, &["m_timeLocalOffset"]));
+
+        let mut players = Vec::new();
+        let mut observers = Vec::new();
+        let player_list = match try!(field(details, &["m_playerList"])) {
+            &Value::Array(ref list) => &list[..],
+            _ => return Err(Error::InvalidField("m_playerList")),
+        };
+        for player in player_list.iter() {
+            if try!(u64_field(player, &["m_observe"])) != 0 {
+                observers.push(ObserverSummary {
+                    user_id: None,
+                    name: try!(str_field(player, &["m_name"])),
+                });
+                continue;
+            }
+            players.push(try!(player_summary(player)));
+        }
+
+        let user_data = match init_data.find_path(&["m_syncLobbyState", "m_userInitialData"]) {
+            Some(&Value::Array(ref list)) => &list[..],
+            _ => &[],
+        };
+        let slots = match init_data.find_path(&["m_syncLobbyState", "m_lobbyState", "m_slots"]) {
+            Some(&Value::Array(ref list)) => &list[..],
+            _ => &[],
+        };
+        // details lists players in lobby slot order, so the n-th occupied
+        // player slot is the n-th player.  Names can repeat and can't be the
+        // key, but a slot whose user has a different name is left unmatched.
+        {
+            let mut slot_players = players.iter_mut();
+            for slot in slots.iter() {
+                let observe = try!(u64_field(slot, &["m_observe"]));
+                if observe == 0 && try!(u64_field(slot, &["m_control"])) < SLOT_CONTROL_HUMAN {
+                    continue;
+                }
+                let player = if observe == 0 { slot_players.next() } else { None };
+                let user_id = match slot.find("m_userId").and_then(|x| x.as_u64()) {
+                    Some(user_id) => user_id as u32,
+                    None => continue,
+                };
+                let name = match user_data.get(user_id as usize) {
+                    Some(user) => try!(str_field(user, &["m_name"])),
+                    None => continue,
+                };
+                if observe == 0 {
+                    if let Some(player) = player {
+                        if player.name == name {
+                            player.user_id = Some(user_id);
+                            player.lobby_team_id = Some(try!(u64_field(slot, &["m_teamId"])) as u8);
+                        }
+                    }
+                    continue;
+                }
+                if observers.iter().any(|o| o.name == name) {
+                    continue;
+                }
+                observers.push(ObserverSummary {
+                    user_id: Some(user_id),
+                    name: name,
+                });
+            }
+        }
+
+        Ok(ReplaySummary {
+            title: try!(str_field(details, &["m_title"])),
+            version: version,
+            elapsed: GameLoop(elapsed),
+            game_speed: game_speed,
+            start_time: FileTime(time_utc),
+            utc_offset: UtcOffset::from_details(time_utc, time_local_offset),
+            players: players,
+            observers: observers,
+            is_blizzard_map: try!(bool_field(details, &["m_isBlizzardMap"])),
+            network: None,
+            cheats: None,
+        })
+    }
+
+    /// Adds the lag and game speed report for the raw contents of
+    /// `replay.game.events`.
+    pub fn attach_network(&mut self, game_events: &[u8]) -> typed::Result<()> {
+        let report = try!(NetworkReport::from_game_events(game_events, self.game_speed));
+        self.network = Some(report);
+        Ok(())
+    }
+
+    /// Adds the cheat report for the raw contents of `replay.game.events`.
+    pub fn attach_cheats(&mut self, game_events: &[u8]) -> typed::Result<()> {
+        let report = try!(CheatReport::from_game_events(game_events));
+        self.cheats = Some(report);
+        Ok(())
+    }
+
+    /// Whether the game can count as competitive: `false` once cheats have
+    /// been found, `None` until `attach_cheats` has been called.
+    pub fn is_competitive(&self) -> Option<bool> {
+        self.cheats.as_ref().map(|cheats| cheats.is_clean())
+    }
+
+    /// Real time taken by the game at its game speed.
+    pub fn duration(&self) -> Duration {
+        self.elapsed.real_time(self.game_speed)
+    }
+
+    pub fn start_system_time(&self) -> SystemTime {
+        self.start_time.to_system_time()
+    }
+}
+
+fn player_summary(player: &Value) -> Result<PlayerSummary> {
+    let result = try!(u64_field(player, &["m_result"]));
+    Ok(PlayerSummary {
+        name: try!(str_field(player, &["m_name"])),
+        region: try!(u64_field(player, &["m_toon", "m_region"])) as u8,
+        realm: try!(u64_field(player, &["m_toon", "m_realm"])) as u32,
+        toon_id: try!(u64_field(player, &["m_toon", "m_id"])),
+        team_id: try!(u64_field(player, &["m_teamId"])) as u8,
+        race: try!(str_field(player, &["m_race"])),
+        result: try!(PlayerResult::from_u8(result as u8).ok_or(Error::InvalidField("m_result"))),
+        color: Color {
+            a: try!(u64_field(player, &["m_color", "m_a"])) as u8,
+            r: try!(u64_field(player, &["m_color", "m_r"])) as u8,
+            g: try!(u64_field(player, &["m_color", "m_g"])) as u8,
+            b: try!(u64_field(player, &["m_color", "m_b"])) as u8,
+        },
+        handicap: try!(u64_field(player, &["m_handicap"])) as u8,
+        control: try!(u64_field(player, &["m_control"])) as u8,
+        user_id: None,
+        lobby_team_id: None,
+    })
+}
+
+fn field<'a>(value: &'a Value, path: &[&'static str]) -> Result<&'a Value> {
+    value.find_path(path).ok_or(Error::MissingField(path[path.len() - 1]))
+}
+
+fn u64_field(value: &Value, path: &[&'static str]) -> Result<u64> {
+    let val = try!(field(value, path));
+    val.as_u64().ok_or(Error::InvalidField(path[path.len() - 1]))
+}
+
+fn i64_field(value: &Value, path: &[&'static str]) -> Result<i64> {
+    let val = try!(field(value, path));
+    val.as_i64().ok_or(Error::InvalidField(path[path.len() - 1]))
+}
+
+fn bool_field(value: &Value, path: &[&'static str]) -> Result<bool> {
+    let val = try!(field(value, path));
+    val.as_boolean().ok_or(Error::InvalidField(path[path.len() - 1]))
+}
+
+fn str_field(value: &Value, path: &[&'static str]) -> Result<String> {
+    let val = try!(field(value, path));
+    val.as_string().map(|s| s.to_string()).ok_or(Error::InvalidField(path[path.len() - 1]))
+}
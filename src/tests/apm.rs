@@ -0,0 +1,67 @@
+use ::apm::{ApmReport, ApmOptions, ActionClass, MinuteApm};
+use ::common::{GameLoop, GameSpeed};
+use ::format::protocol15405_events::{GameEvent, SSaveGameDoneEvent};
+use super::GAME_EVENTS;
+
+// m_elapsedGameLoops from the header
+const ELAPSED: GameLoop = GameLoop(25243);
+
+#[test]
+fn apm_15405() {
+    let report = ApmReport::from_game_events(GAME_EVENTS, ELAPSED, &ApmOptions::default()).unwrap();
+    assert_eq!(report.players().count(), 8);
+
+    let counts: Vec<(u32, u32)> = report.players().map(|(_, p)| (p.actions, p.effective)).collect();
+    assert_eq!(counts, vec![
+        (1714, 717), (967, 573), (714, 596), (1501, 855),
+        (948, 498), (1016, 505), (1392, 609), (1093, 563),
+    ]);
+
+    let player = report.player(1).unwrap();
+    assert_eq!(player.per_minute.len(), 27);
+    assert_eq!(player.per_minute[..3].to_vec(), vec![
+        MinuteApm { actions: 24, effective: 17 },
+        MinuteApm { actions: 69, effective: 25 },
+        MinuteApm { actions: 111, effective: 18 },
+    ]);
+    let total = player.per_minute.iter().fold(0, |acc, m| acc + m.actions);
+    assert_eq!(total, player.actions);
+    assert!((player.apm() - 65.184).abs() < 0.001);
+    assert!((player.epm() - 27.268).abs() < 0.001);
+}
+
+#[test]
+fn apm_game_speed() {
+    let options = ApmOptions {
+        game_speed: Some(GameSpeed::Faster),
+        ..ApmOptions::default()
+    };
+    let report = ApmReport::from_game_events(GAME_EVENTS, ELAPSED, &options).unwrap();
+    let player = report.player(4).unwrap();
+    assert_eq!(player.per_minute.len(), 19);
+    assert_eq!(player.per_minute[0], MinuteApm { actions: 45, effective: 27 });
+    assert!((player.apm() - 79.917).abs() < 0.001);
+    assert!((player.epm() - 45.522).abs() < 0.001);
+}
+
+#[test]
+fn apm_classes() {
+    let options = ApmOptions {
+        camera: true,
+        ..ApmOptions::default()
+    };
+    let report = ApmReport::from_game_events(GAME_EVENTS, ELAPSED, &options).unwrap();
+    assert_eq!(report.player(1).unwrap().actions, 4853);
+
+    let options = ApmOptions {
+        selections: false,
+        control_groups: false,
+        ..ApmOptions::default()
+    };
+    assert!(!options.includes(ActionClass::Selection));
+    let report = ApmReport::from_game_events(GAME_EVENTS, ELAPSED, &options).unwrap();
+    let commands: u32 = report.players().fold(0, |acc, (_, p)| acc + p.actions);
+    assert_eq!(commands, 6866);
+
+    assert_eq!(ActionClass::of(&GameEvent::SSaveGameDoneEvent(SSaveGameDoneEvent)), None);
+}
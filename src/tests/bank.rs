@@ -0,0 +1,126 @@
+use ::bank::{Banks, BankValueType};
+use ::format::protocol15405_events::*;
+use super::{event, blob, GAME_EVENTS};
+
+fn file(name: &str) -> GameEvent {
+    GameEvent::SBankFileEvent(SBankFileEvent { name: blob(name) })
+}
+
+fn section(name: &str) -> GameEvent {
+    GameEvent::SBankSectionEvent(SBankSectionEvent { name: blob(name) })
+}
+
+fn key(name: &str, type_: u32, data: &str) -> GameEvent {
+    GameEvent::SBankKeyEvent(SBankKeyEvent { name: blob(name), type_: type_, data: blob(data) })
+}
+
+fn value(name: &str, type_: u32, data: &str) -> GameEvent {
+    GameEvent::SBankValueEvent(SBankValueEvent { type_: type_, name: blob(name), data: blob(data) })
+}
+
+#[test]
+fn bank_15405() {
+    // a melee game writes no banks
+    let banks = Banks::from_game_events(GAME_EVENTS).unwrap();
+    assert!(banks.is_empty());
+}
+
+#[test]
+fn bank_events() {
+    let events = vec![
+        event(0, 1, file("Stats")),
+        event(0, 1, section("Totals")),
+        event(0, 1, key("Wins", 2, "3")),
+        event(0, 1, key("Rating", 0, "1520.5")),
+        event(0, 2, file("Stats")),
+        event(0, 2, section("Totals")),
+        event(0, 2, key("Wins", 2, "7")),
+        event(0, 1, section("Hero")),
+        event(0, 1, key("Unit", 4, "")),
+        event(0, 1, value("type", 3, "Marine")),
+        event(0, 1, value("level", 2, "4")),
+        event(0, 1, section("Totals")),
+        event(0, 1, key("Wins", 2, "4")),
+    ];
+    let mut banks = Banks::new();
+    for event in events.iter() {
+        banks.apply(event);
+    }
+    assert_eq!(banks.players().count(), 2);
+
+    let stats = banks.player(1).unwrap().file(b"Stats").unwrap();
+    assert_eq!(stats.sections.len(), 2);
+    let totals = stats.section(b"Totals").unwrap();
+    assert_eq!(totals.keys.len(), 2);
+    let wins = totals.key(b"Wins").unwrap();
+    assert_eq!(wins.value_type, BankValueType::Int);
+    assert_eq!(wins.data, blob("4"));
+
+    let unit = stats.section(b"Hero").unwrap().key(b"Unit").unwrap();
+    assert_eq!(unit.value_type, BankValueType::Unit);
+    assert_eq!(unit.values.len(), 2);
+    assert_eq!(unit.values[0].name, blob("type"));
+    assert_eq!(unit.values[0].value_type, BankValueType::String);
+
+    let other = banks.player(2).unwrap().file(b"Stats").unwrap();
+    assert_eq!(other.section(b"Totals").unwrap().key(b"Wins").unwrap().data, blob("7"));
+}
+
+#[test]
+fn bank_rewritten() {
+    let events = vec![
+        event(0, 1, key("Orphan", 2, "1")),
+        event(0, 1, file("Save")),
+        event(0, 1, key("NoSection", 2, "1")),
+        event(0, 1, section("A")),
+        event(0, 1, key("Old", 2, "1")),
+        event(0, 1, file("Save")),
+        event(0, 1, section("B")),
+        event(0, 1, key("New", 2, "2")),
+    ];
+    let mut banks = Banks::new();
+    for event in events.iter() {
+        banks.apply(event);
+    }
+    let files = banks.player(1).unwrap().files();
+    assert_eq!(files.len(), 1);
+    assert!(files[0].section(b"A").is_none());
+    assert_eq!(files[0].section(b"B").unwrap().keys.len(), 1);
+}
+
+#[test]
+fn bank_xml() {
+    let events = vec![
+        event(0, 1, file("Stats")),
+        event(0, 1, section("Totals")),
+        event(0, 1, key("Wins", 2, "3")),
+        event(0, 1, key("Motto", 6, "Fast & \"loud\" <3")),
+        event(0, 1, section("Hero")),
+        event(0, 1, key("Unit", 4, "")),
+        event(0, 1, value("type", 3, "Marine")),
+    ];
+    let mut banks = Banks::new();
+    for event in events.iter() {
+        banks.apply(event);
+    }
+    let xml = banks.player(1).unwrap().files()[0].to_xml();
+    assert_eq!(xml, concat!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n",
+        "<Bank version=\"1\">\n",
+        "    <Section name=\"Totals\">\n",
+        "        <Key name=\"Wins\">\n",
+        "            <Value int=\"3\"/>\n",
+        "        </Key>\n",
+        "        <Key name=\"Motto\">\n",
+        "            <Value text=\"Fast &amp; &quot;loud&quot; &lt;3\"/>\n",
+        "        </Key>\n",
+        "    </Section>\n",
+        "    <Section name=\"Hero\">\n",
+        "        <Key name=\"Unit\">\n",
+        "            <Value unit=\"\"/>\n",
+        "            <Value name=\"type\" string=\"Marine\"/>\n",
+        "        </Key>\n",
+        "    </Section>\n",
+        "</Bank>\n",
+    ));
+}
@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use ::camera::{CameraTimeline, CameraSample};
+use ::common::GameLoop;
+use super::GAME_EVENTS;
+
+#[test]
+fn camera_15405() {
+    let timeline = CameraTimeline::from_game_events(GAME_EVENTS).unwrap();
+    assert_eq!(timeline.players().count(), 8);
+
+    let samples: Vec<usize> = timeline.players().map(|(_, camera)| camera.samples().len()).collect();
+    assert_eq!(samples, vec![3139, 3822, 3407, 3810, 3048, 3265, 3585, 3621]);
+    assert_eq!(samples.iter().fold(0, |acc, n| acc + n), 27697);
+
+    let camera = timeline.player(1).unwrap();
+    assert_eq!(camera.samples()[0], CameraSample {
+        gameloop: GameLoop(2),
+        x: 102.0,
+        y: 155.7578125,
+        distance: Some(8704),
+        pitch: Some(318),
+        yaw: Some(1024),
+    });
+    assert_eq!(camera.at(GameLoop(1)), None);
+    assert_eq!(camera.at(GameLoop(2)), camera.samples().first());
+    let sample = camera.at(GameLoop(5000)).unwrap();
+    assert_eq!(sample.gameloop, GameLoop(4911));
+    assert_eq!((sample.x, sample.y), (101.9453125, 153.57421875));
+    assert_eq!(camera.at(GameLoop(30000)).unwrap().gameloop, GameLoop(25187));
+}
+
+#[test]
+fn camera_metrics() {
+    let timeline = CameraTimeline::from_game_events(GAME_EVENTS).unwrap();
+
+    let jumps: Vec<usize> = timeline.players().map(|(_, camera)| camera.jumps()).collect();
+    assert_eq!(jumps, vec![86, 67, 89, 115, 66, 143, 76, 23]);
+    let screens: Vec<usize> = timeline.players().map(|(_, camera)| camera.screens()).collect();
+    assert_eq!(screens, vec![183, 189, 167, 298, 180, 337, 183, 143]);
+
+    let camera = timeline.player(1).unwrap();
+    assert_eq!(camera.screens_per_minute(Duration::from_secs(183)), 60.0);
+    assert_eq!(camera.screens_per_minute(Duration::from_secs(0)), 0.0);
+
+    let regions = camera.time_per_region(32.0, GameLoop(25221));
+    assert_eq!(regions.values().fold(0, |acc, n| acc + n), 25221 - 2);
+    assert_eq!(regions.get(&(3, 4)), Some(&9051));
+    assert_eq!(regions.get(&(2, 2)), Some(&1950));
+    let (busiest, _) = regions.iter().max_by_key(|&(_, loops)| *loops).unwrap();
+    assert_eq!(*busiest, (3, 4));
+}
@@ -0,0 +1,74 @@
+use ::cheats::{CheatReport, CheatKind};
+use ::common::GameLoop;
+use ::format::protocol15405_events::*;
+use ::summary::ReplaySummary;
+use super::{event, blob, HEADER, DETAILS, INIT_DATA, GAME_EVENTS};
+
+fn options(cheats: bool) -> GameEvent {
+    GameEvent::SUserOptionsEvent(SUserOptionsEvent {
+        development_cheats_enabled: false,
+        multiplayer_cheats_enabled: cheats,
+        sync_checksumming_enabled: false,
+        is_map_to_map_transition: false,
+    })
+}
+
+#[test]
+fn cheats_summary_15405() {
+    let mut summary = ReplaySummary::decode(HEADER, DETAILS, INIT_DATA).unwrap();
+    assert_eq!(summary.is_competitive(), None);
+    summary.attach_cheats(GAME_EVENTS).unwrap();
+    assert_eq!(summary.is_competitive(), Some(true));
+
+    let cheats = summary.cheats.unwrap();
+    assert!(cheats.cheats.is_empty());
+    assert!(cheats.cheats_enabled.is_empty());
+}
+
+#[test]
+fn cheats_used() {
+    let events = vec![
+        event(0, 1, options(false)),
+        event(0, 2, options(true)),
+        event(0, 2, options(true)),
+        event(120, 2, GameEvent::SGameCheatEvent(SGameCheatEvent {
+            data: Data {
+                point: Point { x: 0, y: 0 },
+                time: 0,
+                verb: blob("TerribleTerribleDamage"),
+                arguments: blob(""),
+            },
+        })),
+        event(300, 3, GameEvent::SBroadcastCheatEvent(SBroadcastCheatEvent {
+            verb: blob("ShowMeTheMoney"),
+            arguments: blob("5000"),
+        })),
+    ];
+    let mut report = CheatReport::new();
+    for event in events.iter() {
+        report.apply(event);
+    }
+
+    assert!(!report.is_clean());
+    assert_eq!(report.cheats_enabled, vec![2]);
+    assert_eq!(report.cheats.len(), 2);
+
+    let by_two = report.by_player(2);
+    assert_eq!(by_two.len(), 1);
+    assert_eq!(by_two[0].gameloop, GameLoop(120));
+    assert_eq!(by_two[0].kind, CheatKind::Game);
+    assert_eq!(by_two[0].verb.as_str(), Some("TerribleTerribleDamage"));
+
+    let by_three = report.by_player(3);
+    assert_eq!(by_three[0].kind, CheatKind::Broadcast);
+    assert_eq!(by_three[0].arguments.as_str(), Some("5000"));
+    assert!(report.by_player(1).is_empty());
+}
+
+#[test]
+fn cheats_enabled_only() {
+    let mut report = CheatReport::new();
+    report.apply(&event(0, 4, options(true)));
+    assert!(report.cheats.is_empty());
+    assert!(!report.is_clean());
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+
+use ::common::GameLoop;
+use ::events::{game_events, message_events, tracker_events, EventStream, EventTypes};
+use ::format::{TypeInfo, IntBounds, Struct};
+use ::s2json;
+use super::{GAME_EVENTS, MESSAGE_EVENTS};
+
+// a tracker protocol cut down to one event
+static TRACKER_TYPEINFOS: &'static [TypeInfo] = &[
+    TypeInfo::Int { bounds: IntBounds { min: 0, bitlen: 7 } },
+    TypeInfo::Int { bounds: IntBounds { min: 0, bitlen: 6 } },
+    TypeInfo::Int { bounds: IntBounds { min: 0, bitlen: 14 } },
+    TypeInfo::Int { bounds: IntBounds { min: 0, bitlen: 22 } },
+    TypeInfo::Int { bounds: IntBounds { min: 0, bitlen: 32 } },
+    TypeInfo::Choice {
+        bounds: IntBounds { min: 0, bitlen: 2 },
+        types: phf_map! {
+            0_u32 => ("m_uint6", 1),
+            1_u32 => ("m_uint14", 2),
+            2_u32 => ("m_uint22", 3),
+            3_u32 => ("m_uint32", 4),
+        },
+    },
+    TypeInfo::Struct(Struct {
+        fields: &[
+            ("m_playerId", 0, 0),
+            ("m_type", 0, 1),
+        ],
+    }),
+];
+
+static TRACKER_EVENT_TYPES: EventTypes = phf_map! {
+    9_u32 => (6, "NNet.Replay.Tracker.SPlayerSetupEvent"),
+};
+
+#[test]
+fn message_events_15405() {
+    let events: Vec<_> = message_events(MESSAGE_EVENTS).map(|e| e.unwrap()).collect();
+    assert_eq!(events.len(), 45);
+
+    let first = &events[0];
+    assert_eq!(first.name, "NNet.Game.SLoadingProgressMessage");
+    assert_eq!(first.gameloop, GameLoop(0));
+    assert_eq!(first.user_id, Some(8));
+    assert_eq!(first.bits, 56);
+    assert_eq!(first.value["m_progress"].as_i64(), Ok(50));
+
+    let chat = events.iter().find(|e| e.name == "NNet.Game.SChatMessage").unwrap();
+    assert_eq!(chat.gameloop, GameLoop(332));
+    assert_eq!(chat.value["m_string"].as_str(), Ok("yo"));
+}
+
+#[test]
+fn game_events_15405() {
+    let mut counts: HashMap<&'static str, usize> = HashMap::new();
+    let mut last_gameloop = GameLoop(0);
+    for event in game_events(GAME_EVENTS) {
+        let event = event.unwrap();
+        assert!(last_gameloop <= event.gameloop);
+        last_gameloop = event.gameloop;
+        *counts.entry(event.name).or_insert(0) += 1;
+    }
+    assert_eq!(counts.values().fold(0, |acc, &n| acc + n), 37058);
+    assert_eq!(counts["NNet.Game.SCameraUpdateEvent"], 27697);
+    assert_eq!(counts["NNet.Game.SCmdEvent"], 6866);
+    assert_eq!(counts["NNet.Game.SPlayerLeaveEvent"], 4);
+}
+
+#[test]
+fn truncated_game_events() {
+    let mut events = game_events(&GAME_EVENTS[..100]);
+    assert!(events.any(|e| e.is_err()));
+    assert!(events.next().is_none());
+}
+
+#[test]
+fn tracker_events_versioned() {
+    let buf = [
+        // delta m_uint6 0, event 9, {m_playerId: 1, m_type: 1}
+        0x03, 0x00, 0x09, 0x00,
+        0x09, 0x12,
+        0x05, 0x04, 0x00, 0x09, 0x02, 0x02, 0x09, 0x02,
+        // delta m_uint14 100, event 9, {m_playerId: 2, m_type: 2}
+        0x03, 0x02, 0x09, 0xc8, 0x01,
+        0x09, 0x12,
+        0x05, 0x04, 0x00, 0x09, 0x04, 0x02, 0x09, 0x04,
+    ];
+    let stream = EventStream::tracker(5, 0, &TRACKER_EVENT_TYPES);
+    let events: Vec<_> = tracker_events(&buf, TRACKER_TYPEINFOS, stream).map(|e| e.unwrap()).collect();
+    assert_eq!(events.len(), 2);
+
+    let second = &events[1];
+    assert_eq!(second.name, "NNet.Replay.Tracker.SPlayerSetupEvent");
+    assert_eq!(second.event_id, 9);
+    assert_eq!(second.gameloop, GameLoop(100));
+    assert_eq!(second.user_id, None);
+    assert_eq!(second.bits, 120);
+    assert_eq!(second.value["m_playerId"].as_u64(), Ok(2));
+
+    let value = s2json::event_value(second);
+    let keys: Vec<&str> = value.as_dict().unwrap().keys().map(|k| &k[..]).collect();
+    assert_eq!(keys, vec!["_bits", "_event", "_eventid", "_gameloop", "m_playerId", "m_type"]);
+}
@@ -0,0 +1,98 @@
+use ::common::GameLoop;
+use ::format::protocol15405_events::*;
+use ::ledger::{Ledger, Resources, Transfer, Request, RequestResponse, RequestAction};
+use super::{event, GAME_EVENTS};
+
+fn minerals(minerals: i32) -> Resources {
+    Resources { minerals: minerals, ..Resources::default() }
+}
+
+#[test]
+fn ledger_15405() {
+    let ledger = Ledger::from_game_events(GAME_EVENTS).unwrap();
+    assert_eq!(ledger.transfers, vec![
+        Transfer {
+            gameloop: GameLoop(19474),
+            from: 5,
+            to: 6,
+            resources: Resources { minerals: 1594, vespene: 1155, terrazine: 0, custom: 0 },
+        },
+        Transfer {
+            gameloop: GameLoop(24608),
+            from: 3,
+            to: 1,
+            resources: minerals(1050),
+        },
+    ]);
+    assert_eq!(ledger.received_by(1), minerals(1050));
+    assert_eq!(ledger.sent_by(5).vespene, 1155);
+    assert_eq!(ledger.sent(6, 5), Resources::default());
+    assert!(ledger.requests.is_empty());
+    assert!(ledger.responses.is_empty());
+    assert!(ledger.alliances.is_empty());
+}
+
+#[test]
+fn ledger_requests() {
+    let request = |amount| GameEvent::SResourceRequestEvent(SResourceRequestEvent {
+        resources: vec![amount, 0, 0, 0],
+    });
+    let events = vec![
+        event(10, 1, request(100)),
+        event(20, 2, request(200)),
+        event(40, 3, GameEvent::SResourceRequestFulfillEvent(SResourceRequestFulfillEvent {
+            fulfill_request_id: 7,
+        })),
+        event(50, 2, GameEvent::SResourceRequestCancelEvent(SResourceRequestCancelEvent {
+            cancel_request_id: 8,
+        })),
+        event(70, 1, GameEvent::SResourceTradeEvent(SResourceTradeEvent {
+            recipient_id: 3,
+            resources: vec![50, 25],
+        })),
+    ];
+    let mut ledger = Ledger::new();
+    for event in events.iter() {
+        ledger.apply(event);
+    }
+
+    assert_eq!(ledger.requests, vec![
+        Request { gameloop: GameLoop(10), player: 1, resources: minerals(100) },
+        Request { gameloop: GameLoop(20), player: 2, resources: minerals(200) },
+    ]);
+    // the ids name requests the events above don't, so nothing is matched
+    assert_eq!(ledger.responses, vec![
+        RequestResponse { gameloop: GameLoop(40), player: 3, request_id: 7, action: RequestAction::Fulfill },
+        RequestResponse { gameloop: GameLoop(50), player: 2, request_id: 8, action: RequestAction::Cancel },
+    ]);
+
+    assert_eq!(ledger.transfers.len(), 1);
+    assert_eq!(ledger.sent(3, 1), Resources::default());
+    assert_eq!(ledger.sent(1, 3), Resources { minerals: 50, vespene: 25, terrazine: 0, custom: 0 });
+    assert_eq!(ledger.received_by(3).minerals, 50);
+}
+
+#[test]
+fn ledger_alliances() {
+    let alliance = |alliance, control| GameEvent::SAllianceEvent(SAllianceEvent {
+        alliance: alliance,
+        control: control,
+    });
+    let events = vec![
+        event(100, 1, alliance(0b0110, 0)),
+        event(200, 2, alliance(0b0100, 0)),
+        event(300, 1, alliance(0b0110, 0b0100)),
+    ];
+    let mut ledger = Ledger::new();
+    for event in events.iter() {
+        ledger.apply(event);
+    }
+
+    assert!(ledger.alliance_at(1, GameLoop(99)).is_none());
+    let first = ledger.alliance_at(1, GameLoop(250)).unwrap();
+    assert!(first.allied_with(2));
+    assert!(!first.shares_control_with(2));
+    let last = ledger.alliance_at(1, GameLoop(300)).unwrap();
+    assert!(last.shares_control_with(2));
+    assert!(!ledger.alliance_at(2, GameLoop(300)).unwrap().allied_with(1));
+}
@@ -1,2 +1,41 @@
+mod apm;
+mod bank;
+mod camera;
+mod cheats;
 mod details;
-mod header;
\ No newline at end of file
+mod events;
+mod header;
+mod ledger;
+mod network;
+mod outcome;
+mod query;
+mod s2json;
+mod selection;
+mod summary;
+mod timeline;
+mod transcode;
+mod typed;
+mod value;
+
+use ::common::GameLoop;
+use ::format::protocol15405_events::GameEvent;
+use ::typed::{Blob, TypedEvent};
+
+const HEADER: &'static [u8] = include_bytes!("../../testdata/header");
+const DETAILS: &'static [u8] = include_bytes!("../../testdata/base_build_15405/replay.details");
+const INIT_DATA: &'static [u8] = include_bytes!("../../testdata/base_build_15405/replay.initData");
+const GAME_EVENTS: &'static [u8] = include_bytes!("../../testdata/base_build_15405/replay.game.events");
+const MESSAGE_EVENTS: &'static [u8] = include_bytes!("../../testdata/base_build_15405/replay.message.events");
+
+fn event(gameloop: u32, user_id: u32, event: GameEvent) -> TypedEvent<GameEvent> {
+    TypedEvent {
+        gameloop: GameLoop(gameloop),
+        user_id: Some(user_id),
+        bits: 0,
+        event: event,
+    }
+}
+
+fn blob(text: &str) -> Blob {
+    Blob(text.as_bytes().to_vec())
+}
\ No newline at end of file
@@ -0,0 +1,71 @@
+use ::common::{GameLoop, GameSpeed};
+use ::format::protocol15405_events::*;
+use ::network::{NetworkReport, LagEpisode, SpeedChange};
+use ::summary::ReplaySummary;
+use super::{event, HEADER, DETAILS, INIT_DATA, GAME_EVENTS};
+
+fn lag(player: u8) -> GameEvent {
+    GameEvent::SLagMessageEvent(SLagMessageEvent { lagging_player_id: player })
+}
+
+#[test]
+fn network_summary_15405() {
+    let mut summary = ReplaySummary::decode(HEADER, DETAILS, INIT_DATA).unwrap();
+    assert!(summary.network.is_none());
+    summary.attach_network(GAME_EVENTS).unwrap();
+
+    // a clean game: no lag and no speed changes
+    let network = summary.network.unwrap();
+    assert_eq!(network.lag().count(), 0);
+    assert!(network.speed_changes.is_empty());
+    assert_eq!(network.speed_at(GameLoop(25000)), GameSpeed::Faster);
+}
+
+#[test]
+fn network_lag() {
+    let events = vec![
+        event(100, 1, lag(3)),
+        event(101, 2, lag(3)),
+        event(116, 1, lag(3)),
+        event(200, 1, lag(3)),
+        event(150, 3, lag(5)),
+    ];
+    let mut report = NetworkReport::new(GameSpeed::Faster);
+    for event in events.iter() {
+        report.apply(event);
+    }
+
+    let lag = report.player_lag(3).unwrap();
+    assert_eq!(lag.episodes, vec![
+        LagEpisode { start: GameLoop(100), end: GameLoop(116), messages: 3 },
+        LagEpisode { start: GameLoop(200), end: GameLoop(200), messages: 1 },
+    ]);
+    assert_eq!(lag.messages(), 4);
+    assert_eq!(lag.lagged_loops(), 18);
+    assert_eq!(report.player_lag(5).unwrap().lagged_loops(), 1);
+    assert_eq!(report.lag_episodes(), 3);
+    assert!(report.player_lag(1).is_none());
+}
+
+#[test]
+fn network_speed() {
+    let events = vec![
+        event(10, 1, GameEvent::SSetAbsoluteGameSpeedEvent(SSetAbsoluteGameSpeedEvent { speed: 2 })),
+        event(20, 2, GameEvent::SAddAbsoluteGameSpeedEvent(SAddAbsoluteGameSpeedEvent { delta: 1 })),
+        event(30, 2, GameEvent::SAddAbsoluteGameSpeedEvent(SAddAbsoluteGameSpeedEvent { delta: 5 })),
+    ];
+    let mut report = NetworkReport::new(GameSpeed::Faster);
+    for event in events.iter() {
+        report.apply(event);
+    }
+
+    assert_eq!(report.speed_changes[1], SpeedChange {
+        gameloop: GameLoop(20),
+        user_id: Some(2),
+        speed: GameSpeed::Fast,
+    });
+    assert_eq!(report.speed_at(GameLoop(9)), GameSpeed::Faster);
+    assert_eq!(report.speed_at(GameLoop(10)), GameSpeed::Normal);
+    assert_eq!(report.speed_at(GameLoop(25)), GameSpeed::Fast);
+    assert_eq!(report.speed_at(GameLoop(30)), GameSpeed::Faster);
+}
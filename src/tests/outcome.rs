@@ -0,0 +1,78 @@
+use ::common::GameLoop;
+use ::outcome::{GameOutcome, Participant, Leave, EndReason};
+use ::summary::{ReplaySummary, PlayerResult};
+use super::{HEADER, DETAILS, INIT_DATA, GAME_EVENTS};
+
+fn leave(gameloop: u32, player_id: u32) -> Leave {
+    Leave {
+        gameloop: GameLoop(gameloop),
+        player_id: player_id,
+    }
+}
+
+#[test]
+fn outcome_15405() {
+    let summary = ReplaySummary::decode(HEADER, DETAILS, INIT_DATA).unwrap();
+    let rev = &summary.players[4];
+    assert_eq!((rev.user_id, rev.lobby_team_id), (Some(4), Some(1)));
+
+    let outcome = GameOutcome::from_game_events(&summary, GAME_EVENTS).unwrap();
+    assert_eq!(outcome.leaves, vec![
+        leave(21299, 5),
+        leave(21840, 7),
+        leave(24900, 6),
+        leave(25218, 8),
+    ]);
+    assert_eq!(outcome.first_leave(), Some(&leave(21299, 5)));
+    assert_eq!(outcome.left_at(6), Some(GameLoop(24900)));
+    assert_eq!(outcome.left_at(1), None);
+    assert_eq!(outcome.end, GameLoop(25243));
+    assert_eq!(outcome.end_reason, EndReason::Surrender);
+    assert_eq!(outcome.winners, vec![1, 2, 3, 4]);
+    assert_eq!(outcome.winning_team, Some(0));
+    assert!(!outcome.inferred);
+}
+
+#[test]
+fn outcome_inferred() {
+    let summary = ReplaySummary::decode(HEADER, DETAILS, INIT_DATA).unwrap();
+    let participants: Vec<Participant> = Participant::from_summary(&summary).into_iter()
+        .map(|p| Participant { result: PlayerResult::Unknown, ..p })
+        .collect();
+    let leaves = GameOutcome::from_game_events(&summary, GAME_EVENTS).unwrap().leaves;
+
+    let outcome = GameOutcome::new(&participants, leaves, summary.elapsed);
+    assert_eq!(outcome.end_reason, EndReason::Surrender);
+    assert_eq!(outcome.winners, vec![1, 2, 3, 4]);
+    assert_eq!(outcome.winning_team, Some(0));
+    assert!(outcome.inferred);
+}
+
+#[test]
+fn outcome_unsettled() {
+    let participants = vec![
+        Participant { player_id: 1, team: 0, result: PlayerResult::Unknown },
+        Participant { player_id: 2, team: 1, result: PlayerResult::Unknown },
+        Participant { player_id: 3, team: 1, result: PlayerResult::Unknown },
+    ];
+
+    // the replay stops when player 3 drops, with both teams still playing
+    let outcome = GameOutcome::new(&participants, vec![leave(1000, 3)], GameLoop(1010));
+    assert_eq!(outcome.end_reason, EndReason::Disconnect);
+    assert!(outcome.winners.is_empty());
+    assert_eq!(outcome.winning_team, None);
+
+    // a leave long before the end doesn't explain it
+    let outcome = GameOutcome::new(&participants, vec![leave(1000, 3)], GameLoop(5000));
+    assert_eq!(outcome.end_reason, EndReason::Unknown);
+    assert!(!outcome.inferred);
+
+    let outcome = GameOutcome::new(&participants, vec![], GameLoop(5000));
+    assert_eq!(outcome.end_reason, EndReason::Timeout);
+    assert!(outcome.winners.is_empty());
+
+    let outcome = GameOutcome::new(&participants, vec![leave(990, 3), leave(1000, 2)], GameLoop(1010));
+    assert_eq!(outcome.end_reason, EndReason::Surrender);
+    assert_eq!(outcome.winners, vec![1]);
+    assert_eq!(outcome.winning_team, Some(0));
+}
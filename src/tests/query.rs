@@ -0,0 +1,69 @@
+use ::format::protocol15405::{TYPEINFOS, GAME_DETAILS_TYPEID, REPLAY_INITDATA_TYPEID};
+use ::query::{Query, Error};
+use ::value::Value;
+use super::{DETAILS, INIT_DATA};
+
+fn strs(values: Vec<&Value>) -> Vec<&str> {
+    values.into_iter().map(|v| v.as_str().unwrap()).collect()
+}
+
+#[test]
+fn query_details() {
+    let details = Value::from_versioned(DETAILS, TYPEINFOS, GAME_DETAILS_TYPEID).unwrap();
+
+    let ids: Vec<u64> = details.query("m_playerList[*].m_toon.m_id").unwrap()
+        .into_iter().map(|v| v.as_u64().unwrap()).collect();
+    assert_eq!(ids, vec![854244, 708867, 387504, 268326, 230415, 242426, 267813, 373146]);
+
+    assert_eq!(strs(details.query("m_playerList[?m_result==1].m_name").unwrap()),
+               vec!["narod", "arkx", "min", "liekki"]);
+    assert_eq!(strs(details.query("m_playerList[?m_race=='Zerg'].m_name").unwrap()),
+               vec!["Embegee"]);
+    assert_eq!(strs(details.query("m_playerList[?m_color.m_r > 200].m_name").unwrap()),
+               vec!["Rev", "Embegee", "Blitzkrieg"]);
+    assert_eq!(strs(details.query("m_playerList[-1].m_name").unwrap()), vec!["Blitzkrieg"]);
+    assert_eq!(details.query("..m_programId").unwrap().len(), 8);
+    assert_eq!(details.query("m_playerList[8]").unwrap().len(), 0);
+}
+
+#[test]
+fn query_reuse() {
+    let init_data = Value::from_bitpacked(INIT_DATA, TYPEINFOS, REPLAY_INITDATA_TYPEID).unwrap();
+    let details = Value::from_versioned(DETAILS, TYPEINFOS, GAME_DETAILS_TYPEID).unwrap();
+
+    let names = Query::compile("..m_userInitialData[?m_name != ''].m_name").unwrap();
+    assert_eq!(names.select(&init_data).len(), 8);
+    assert_eq!(names.select(&details).len(), 0);
+
+    let unassigned = Query::compile("m_syncLobbyState.m_lobbyState.m_slots[?m_userId == null]").unwrap();
+    assert!(!unassigned.matches(&init_data));
+    let with_race = Query::compile("m_syncLobbyState.m_lobbyState.m_slots[?m_racePref.m_race]").unwrap();
+    assert_eq!(with_race.select(&init_data).len(), 8);
+}
+
+#[test]
+fn query_compile_errors() {
+    assert_eq!(Query::compile("m_playerList[").unwrap_err(),
+               Error { position: 13, reason: "expected an index, '*', '?' or a quoted key" });
+    assert_eq!(Query::compile("m_playerList[0").unwrap_err(),
+               Error { position: 14, reason: "expected ']'" });
+    assert_eq!(Query::compile("m_playerList[?m_result=1]").unwrap_err(),
+               Error { position: 22, reason: "expected a comparison operator" });
+    assert_eq!(Query::compile("a.").unwrap_err(),
+               Error { position: 2, reason: "expected a key" });
+    assert_eq!(Query::compile("a b").unwrap_err(),
+               Error { position: 1, reason: "unexpected character" });
+    assert_eq!(Query::compile("a['b").unwrap_err(),
+               Error { position: 4, reason: "unterminated string" });
+}
+
+#[test]
+fn query_compile() {
+    assert!(Query::compile("").is_ok());
+    assert!(Query::compile("$").is_ok());
+    assert!(Query::compile("$.m_playerList[*].m_toon.m_id").is_ok());
+    assert!(Query::compile("m_playerList[ ?@.m_name != 'x' ].m_name").is_ok());
+    assert!(Query::compile("..m_name").is_ok());
+    assert!(Query::compile("..[0]").is_ok());
+    assert!(Query::compile("m_list[-1]['quoted \\' key']").is_ok());
+}
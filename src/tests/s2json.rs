@@ -0,0 +1,118 @@
+use ::events::{game_events, message_events};
+use ::format::protocol15405::{TYPEINFOS, GAME_DETAILS_TYPEID, REPLAY_INITDATA_TYPEID};
+use ::s2json::{self, Style};
+use ::value::Value;
+use super::{HEADER, DETAILS, INIT_DATA, GAME_EVENTS, MESSAGE_EVENTS};
+
+const HEADER_JSON: &'static str = include_str!("../../testdata/header.json");
+
+#[test]
+fn header_matches_s2protocol() {
+    let mut out = Vec::new();
+    s2json::write_header(&mut out, HEADER, Style::Indent(4)).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), format!("{}\n", HEADER_JSON));
+}
+
+#[test]
+fn details_matches_s2protocol() {
+    let details = Value::from_versioned(DETAILS, TYPEINFOS, GAME_DETAILS_TYPEID).unwrap();
+
+    assert_eq!(s2json::to_string(&details["m_playerList"][4], Style::Indent(4)), r#"{
+    "m_color": {
+        "m_a": 255,
+        "m_b": 41,
+        "m_g": 225,
+        "m_r": 235
+    },
+    "m_control": 2,
+    "m_handicap": 100,
+    "m_name": "Rev",
+    "m_observe": 0,
+    "m_race": "Terran",
+    "m_result": 2,
+    "m_teamId": 1,
+    "m_toon": {
+        "m_id": 230415,
+        "m_programId": "\u0000\u0000S2",
+        "m_realm": 1,
+        "m_region": 2
+    }
+}"#);
+
+    assert_eq!(s2json::to_string(&details["m_cacheHandles"][0], Style::Line),
+        r#""s2ma\u0000\u0000EUm\u00e4\u0015\u0003\u00ba\u00cc\u00d0VV6\u000bo\u0002}\u00b8\u0081i\u00fa\u0019\u0089\u00bbcW\u00b1\u00b2\u0015\u00a2Ty9\u00f5\u00fb""#);
+}
+
+#[test]
+fn init_data_matches_s2protocol() {
+    let init_data = Value::from_bitpacked(INIT_DATA, TYPEINFOS, REPLAY_INITDATA_TYPEID).unwrap();
+    let lobby = &init_data["m_syncLobbyState"];
+
+    assert_eq!(s2json::to_string(&lobby["m_gameDescription"]["m_slotDescriptions"][0], Style::Line),
+        "{\"m_allowedColors\": [16, 65279], \
+          \"m_allowedControls\": [255, 57896044618658097711785492504343953926634992332820282019728792003956564819967], \
+          \"m_allowedDifficulty\": [32, 1056964608], \
+          \"m_allowedObserveTypes\": [3, 7], \
+          \"m_allowedRaces\": [3, 7]}");
+    assert_eq!(s2json::to_string(&lobby["m_userInitialData"][0], Style::Line),
+        "{\"m_name\": \"narod\", \"m_observe\": 0, \"m_racePreference\": {\"m_race\": null}, \
+          \"m_randomSeed\": 0, \"m_testAuto\": false, \"m_testMap\": false}");
+}
+
+#[test]
+fn events_match_s2protocol() {
+    let chat = message_events(MESSAGE_EVENTS)
+        .map(|e| e.unwrap())
+        .find(|e| e.name == "NNet.Game.SChatMessage")
+        .unwrap();
+    assert_eq!(s2json::to_string(&s2json::event_value(&chat), Style::Line),
+        "{\"_bits\": 56, \"_event\": \"NNet.Game.SChatMessage\", \"_eventid\": 0, \
+          \"_gameloop\": 332, \"_userid\": {\"m_playerId\": 8}, \"m_recipient\": 0, \"m_string\": \"yo\"}");
+
+    let mut out = Vec::new();
+    s2json::write_events(&mut out, game_events(GAME_EVENTS), Style::Line).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = out.lines().collect();
+    assert_eq!(lines.len(), 37058);
+    assert_eq!(lines[9],
+        "{\"_bits\": 104, \"_event\": \"NNet.Game.SCameraUpdateEvent\", \"_eventid\": 49, \
+          \"_gameloop\": 2, \"_userid\": {\"m_playerId\": 1}, \"m_distance\": 8704, \"m_pitch\": 318, \
+          \"m_target\": {\"x\": 26112, \"y\": 39874}, \"m_yaw\": 1024}");
+    assert_eq!(lines[17],
+        "{\"_bits\": 280, \"_event\": \"NNet.Game.SSelectionDeltaEvent\", \"_eventid\": 28, \
+          \"_gameloop\": 16, \"_userid\": {\"m_playerId\": 1}, \"m_controlGroupId\": 10, \
+          \"m_delta\": {\"m_addSubgroups\": [{\"m_count\": 6, \"m_intraSubgroupPriority\": 1, \"m_unitLink\": 114}], \
+          \"m_addUnitTags\": [73924609, 74186753, 74448897, 74711041, 74973185, 75235329], \
+          \"m_removeMask\": [0, 0], \"m_subgroupIndex\": 0}}");
+}
+
+#[test]
+fn bit_array_as_decimal() {
+    let bits = |len, bits: &[u8]| s2json::to_string(&Value::BitArray(len, bits.to_vec()), Style::Line);
+    assert_eq!(bits(0, &[]), "[0, 0]");
+    assert_eq!(bits(16, &[0x00, 0x00]), "[16, 0]");
+    assert_eq!(bits(16, &[0xfe, 0xff]), "[16, 65279]");
+    assert_eq!(bits(65, &[0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+               "[65, 18446744073709551616]");
+}
+
+#[test]
+fn versioned_bit_array_as_bytes() {
+    // typeid 42 is a bit array: the skip byte, a length of 16 as a vint,
+    // then the bytes as stored
+    let value = Value::from_versioned(&[0x01, 0x20, 0xfe, 0xff], TYPEINFOS, 42).unwrap();
+    assert_eq!(value, Value::AlignedBitArray(16, vec![0xfe, 0xff]));
+    assert_eq!(s2json::to_string(&value, Style::Line), "[16, \"\\u00fe\\u00ff\"]");
+}
+
+#[test]
+fn float_as_python_repr() {
+    let float = |val| s2json::to_string(&Value::F64(val), Style::Line);
+    assert_eq!(float(0.0), "[0.0]");
+    assert_eq!(float(2.0), "[2.0]");
+    assert_eq!(float(-1.5), "[-1.5]");
+    assert_eq!(float(0.1f32 as f64), "[0.10000000149011612]");
+    assert_eq!(float(1e-5), "[1e-05]");
+    assert_eq!(float(1.5e20), "[1.5e+20]");
+    assert_eq!(float(1e16), "[1e+16]");
+}
@@ -0,0 +1,171 @@
+use ::common::GameLoop;
+use ::format::protocol15405_events::*;
+use ::selection::{SelectionTracker, HotkeyCounts, Unit, Desync};
+use ::typed::{self, BitArray};
+use super::{event, GAME_EVENTS};
+
+fn group_sizes(tracker: &SelectionTracker, player: u32) -> Vec<usize> {
+    let state = tracker.player(player).unwrap();
+    (0..10).map(|idx| state.control_group(idx).unwrap().len()).collect()
+}
+
+#[test]
+fn selection_15405() {
+    let tracker = SelectionTracker::from_game_events(GAME_EVENTS).unwrap();
+    assert_eq!(group_sizes(&tracker, 4), vec![0, 43, 0, 5, 1, 1, 0, 0, 0, 0]);
+    let player = tracker.player(4).unwrap();
+    assert_eq!(player.selection().len(), 5);
+    assert_eq!(player.hotkeys(3), Some(&HotkeyCounts { set: 2, add: 0, get: 46, clear: 0 }));
+    assert_eq!(player.hotkey_total(), HotkeyCounts { set: 14, add: 0, get: 79, clear: 0 });
+    assert!(player.control_group(10).is_none());
+
+    let player = tracker.player(1).unwrap();
+    assert_eq!(player.selection().len(), 27);
+    assert_eq!(player.hotkey_total().total(), 49);
+}
+
+#[test]
+fn selection_at_gameloop() {
+    let tracker = SelectionTracker::at(typed::game_events(GAME_EVENTS), GameLoop(4800)).unwrap();
+    assert_eq!(group_sizes(&tracker, 3), vec![0, 1, 0, 2, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(group_sizes(&tracker, 7), vec![0, 2, 0, 0, 0, 1, 0, 0, 0, 0]);
+    assert_eq!(tracker.player(1).unwrap().hotkey_total().total(), 0);
+}
+
+fn delta(remove_mask: BitArray, add: Vec<(u16, u8)>, tags: Vec<u32>) -> GameEvent {
+    GameEvent::SSelectionDeltaEvent(SSelectionDeltaEvent {
+        control_group_id: 10,
+        delta: Delta {
+            subgroup_index: 0,
+            remove_mask: remove_mask,
+            add_subgroups: add.into_iter().map(|(unit_link, count)| AddSubgroup {
+                unit_link: unit_link,
+                intra_subgroup_priority: 0,
+                count: count,
+            }).collect(),
+            add_unit_tags: tags,
+        },
+    })
+}
+
+fn sync_check(count: u8, subgroup_count: u8) -> GameEvent {
+    GameEvent::SSelectionSyncCheckEvent(SSelectionSyncCheckEvent {
+        control_group_id: 10,
+        selection_sync_data: SelectionSyncData {
+            count: count,
+            subgroup_count: subgroup_count,
+            active_subgroup_index: 0,
+            unit_tags_checksum: 0,
+            subgroup_indices_checksum: 0,
+            subgroups_checksum: 0,
+        },
+    })
+}
+
+fn update(index: u8, update: u8, mask: Option<BitArray>) -> GameEvent {
+    GameEvent::SControlGroupUpdateEvent(SControlGroupUpdateEvent {
+        control_group_index: index,
+        control_group_update: update,
+        mask: mask,
+    })
+}
+
+#[test]
+fn selection_desync() {
+    let empty = BitArray { len: 0, bits: vec![] };
+    let events = vec![
+        event(1, 1, delta(empty.clone(), vec![(40, 2), (41, 1)], vec![100, 101, 102])),
+        event(2, 1, sync_check(3, 2)),
+        // drops the second unit
+        event(3, 1, delta(BitArray { len: 2, bits: vec![0b10] }, vec![], vec![])),
+        event(4, 1, sync_check(3, 2)),
+    ];
+
+    let mut tracker = SelectionTracker::new();
+    for event in events.iter() {
+        tracker.apply(event);
+    }
+    assert_eq!(tracker.player(1).unwrap().selection(), &[
+        Unit { tag: 100, unit_link: 40 },
+        Unit { tag: 102, unit_link: 41 },
+    ][..]);
+    assert_eq!(tracker.desyncs(), &[Desync {
+        gameloop: GameLoop(4),
+        player: 1,
+        control_group: 10,
+        expected_count: 3,
+        actual_count: 2,
+        expected_subgroups: 2,
+        actual_subgroups: 2,
+    }][..]);
+}
+
+#[test]
+fn selection_control_groups() {
+    let empty = BitArray { len: 0, bits: vec![] };
+    let events = vec![
+        event(1, 1, delta(empty.clone(), vec![(40, 3)], vec![100, 101, 102])),
+        event(2, 1, update(1, 0, None)),
+        event(3, 1, delta(BitArray { len: 3, bits: vec![0b111] }, vec![(41, 1)], vec![103])),
+        event(4, 1, update(1, 1, None)),
+        event(5, 1, update(2, 0, None)),
+        // unit 101 has died
+        event(6, 1, update(1, 2, Some(BitArray { len: 2, bits: vec![0b10] }))),
+        event(7, 1, update(2, 3, None)),
+    ];
+
+    let mut tracker = SelectionTracker::new();
+    for event in events.iter() {
+        tracker.apply(event);
+    }
+    let player = tracker.player(1).unwrap();
+    let tags: Vec<u32> = player.control_group(1).unwrap().iter().map(|u| u.tag).collect();
+    assert_eq!(tags, vec![100, 102, 103]);
+    assert_eq!(player.selection(), player.control_group(1).unwrap());
+    assert!(player.control_group(2).unwrap().is_empty());
+    assert_eq!(player.hotkeys(1), Some(&HotkeyCounts { set: 1, add: 1, get: 1, clear: 0 }));
+    assert_eq!(player.hotkeys(2), Some(&HotkeyCounts { set: 1, add: 0, get: 0, clear: 1 }));
+}
+
+#[test]
+fn selection_subgroup_order() {
+    let empty = BitArray { len: 0, bits: vec![] };
+    let events = vec![
+        event(1, 1, delta(empty.clone(), vec![(41, 2)], vec![200, 201])),
+        // the new unit type sorts ahead of the selected ones
+        event(2, 1, delta(empty.clone(), vec![(40, 1)], vec![300])),
+        event(3, 1, sync_check(3, 2)),
+        // so the first bit of the next mask removes it
+        event(4, 1, delta(BitArray { len: 1, bits: vec![0b1] }, vec![], vec![])),
+    ];
+
+    let mut tracker = SelectionTracker::new();
+    for event in events.iter() {
+        tracker.apply(event);
+    }
+    assert_eq!(tracker.player(1).unwrap().selection(), &[
+        Unit { tag: 200, unit_link: 41 },
+        Unit { tag: 201, unit_link: 41 },
+    ][..]);
+    assert!(tracker.desyncs().is_empty());
+}
+
+#[test]
+fn selection_mixed_mask() {
+    let empty = BitArray { len: 0, bits: vec![] };
+    let events = vec![
+        event(1, 1, delta(empty.clone(), vec![(41, 2)], vec![200, 201])),
+        event(2, 1, delta(empty.clone(), vec![(40, 2)], vec![100, 101])),
+        // the last unit of the first subgroup and the first of the second
+        event(3, 1, delta(BitArray { len: 3, bits: vec![0b110] }, vec![], vec![])),
+    ];
+
+    let mut tracker = SelectionTracker::new();
+    for event in events.iter() {
+        tracker.apply(event);
+    }
+    assert_eq!(tracker.player(1).unwrap().selection(), &[
+        Unit { tag: 100, unit_link: 40 },
+        Unit { tag: 201, unit_link: 41 },
+    ][..]);
+}
@@ -0,0 +1,94 @@
+use serde::de;
+use serde_json::value::Value;
+
+use ::summary::{ReplaySummary, PlayerResult, Error};
+use ::common::{GameSpeed, MinSec};
+use ::format::protocol15405::{
+    TYPEINFOS,
+    REPLAY_HEADER_TYPEID,
+    GAME_DETAILS_TYPEID,
+    REPLAY_INITDATA_TYPEID,
+};
+use ::{VersionedDeserializer, BitPackedDeserializer};
+use super::{HEADER, DETAILS, INIT_DATA};
+
+fn values() -> (Value, Value, Value) {
+    let header: Value = de::Deserialize::deserialize(
+        &mut VersionedDeserializer::new(HEADER, TYPEINFOS, REPLAY_HEADER_TYPEID)).unwrap();
+    let details: Value = de::Deserialize::deserialize(
+        &mut VersionedDeserializer::new(DETAILS, TYPEINFOS, GAME_DETAILS_TYPEID)).unwrap();
+    let init_data: Value = de::Deserialize::deserialize(
+        &mut BitPackedDeserializer::new(INIT_DATA, TYPEINFOS, REPLAY_INITDATA_TYPEID)).unwrap();
+    (header, details, init_data)
+}
+
+/// Replaces the value at `path`; numeric keys index arrays.
+fn set(value: &mut Value, path: &[&str], new: Value) {
+    let mut value = value;
+    for key in path.iter() {
+        let cur = value;
+        value = match key.parse::<usize>() {
+            Ok(idx) => &mut cur.as_array_mut().unwrap()[idx],
+            Err(_) => cur.as_object_mut().unwrap().get_mut(*key).unwrap(),
+        };
+    }
+    *value = new;
+}
+
+#[test]
+fn summary_15405() {
+    let summary = ReplaySummary::decode(HEADER, DETAILS, INIT_DATA).unwrap();
+
+    assert_eq!(summary.title, "Toxic Slums");
+    assert_eq!(format!("{}", summary.version), "1.0.2.16223");
+    assert_eq!(summary.version.base_build, 15405);
+    assert_eq!(summary.game_speed, GameSpeed::Faster);
+    assert_eq!(format!("{}", MinSec(summary.duration())), "18:46");
+    assert_eq!(summary.start_time.unix_timestamp(), 1281280520);
+    assert_eq!(format!("{}", summary.utc_offset), "+03:00");
+    assert!(summary.is_blizzard_map);
+    assert_eq!(summary.observers.len(), 0);
+
+    assert_eq!(summary.players.len(), 8);
+    let rev = &summary.players[4];
+    assert_eq!(rev.name, "Rev");
+    assert_eq!(rev.race, "Terran");
+    assert_eq!(rev.team_id, 1);
+    assert_eq!(rev.result, PlayerResult::Defeat);
+    assert_eq!(rev.toon_id, 230415);
+    assert_eq!(rev.handicap, 100);
+    assert_eq!((rev.color.r, rev.color.g, rev.color.b), (235, 225, 41));
+    assert_eq!(summary.players[0].result, PlayerResult::Victory);
+    let user_ids: Vec<Option<u32>> = summary.players.iter().map(|p| p.user_id).collect();
+    assert_eq!(user_ids, (0..8u32).map(Some).collect::<Vec<_>>());
+}
+
+#[test]
+fn summary_duplicate_names() {
+    let (header, mut details, mut init_data) = values();
+    // the first slot goes to a computer that shares the next player's name
+    set(&mut details, &["m_playerList", "0", "m_name"], Value::String("arkx".to_string()));
+    set(&mut init_data, &["m_syncLobbyState", "m_lobbyState", "m_slots", "0", "m_control"], Value::U64(3));
+    set(&mut init_data, &["m_syncLobbyState", "m_lobbyState", "m_slots", "0", "m_userId"], Value::Null);
+
+    let summary = ReplaySummary::from_values(&header, &details, &init_data).unwrap();
+    assert_eq!(summary.players[0].user_id, None);
+    assert_eq!(summary.players[1].user_id, Some(1));
+    assert_eq!(summary.players[7].user_id, Some(7));
+}
+
+#[test]
+fn summary_missing_player_list() {
+    let (header, mut details, init_data) = values();
+    set(&mut details, &["m_playerList"], Value::U64(0));
+    match ReplaySummary::from_values(&header, &details, &init_data) {
+        Err(Error::InvalidField("m_playerList")) => {},
+        other => panic!("unexpected {:?}", other),
+    }
+
+    details.as_object_mut().unwrap().remove("m_playerList");
+    match ReplaySummary::from_values(&header, &details, &init_data) {
+        Err(Error::MissingField("m_playerList")) => {},
+        other => panic!("unexpected {:?}", other),
+    }
+}
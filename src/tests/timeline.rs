@@ -0,0 +1,61 @@
+use std::time::Duration;
+
+use ::common::{GameLoop, GameSpeed};
+use ::timeline::{Timeline, StreamKind};
+use super::{GAME_EVENTS, MESSAGE_EVENTS};
+
+#[test]
+fn timeline_15405() {
+    let timeline = Timeline::from_replay(GAME_EVENTS, MESSAGE_EVENTS).unwrap();
+    assert_eq!(timeline.len(), 37058 + 45);
+    assert!(timeline.events().windows(2).all(|w| w[0].gameloop() <= w[1].gameloop()));
+
+    // on the same gameloop, game events come before messages
+    let first = timeline.iter().next().unwrap();
+    assert_eq!(first.stream, StreamKind::Game);
+    assert_eq!(timeline.events()[9].stream, StreamKind::Message);
+    assert_eq!(timeline.iter().take_while(|e| e.gameloop() == GameLoop(0)).count(), 9 + 40);
+
+    let idx = timeline.seek(GameLoop(332));
+    assert_eq!(idx, 400 + 40);
+    assert_eq!(timeline.events()[idx].event.name, "NNet.Game.SChatMessage");
+    assert_eq!(timeline.events()[idx].player(), Some(8));
+
+    let last = timeline.iter().last().unwrap();
+    assert_eq!(last.gameloop(), GameLoop(25221));
+    assert_eq!(timeline.seek(GameLoop(25222)), timeline.len());
+}
+
+#[test]
+fn timeline_windows() {
+    let timeline = Timeline::from_replay(GAME_EVENTS, MESSAGE_EVENTS).unwrap();
+
+    let start = GameLoop::from_game_time(Duration::from_secs(5 * 60));
+    let end = GameLoop::from_game_time(Duration::from_secs(6 * 60));
+    let window = timeline.window(start, end);
+    assert_eq!(window.len(), 1147);
+    assert!(window.iter().all(|e| e.stream == StreamKind::Game));
+    assert_eq!(window.iter().filter(|e| e.player() == Some(2)).count(), 157);
+
+    let start = GameLoop::from_real_time(Duration::from_secs(5 * 60), GameSpeed::Faster);
+    let end = GameLoop::from_real_time(Duration::from_secs(6 * 60), GameSpeed::Faster);
+    assert_eq!(timeline.window(start, end).len(), 2460);
+
+    assert!(timeline.window(end, start).is_empty());
+}
+
+#[test]
+fn timeline_cursor() {
+    let timeline = Timeline::from_replay(GAME_EVENTS, MESSAGE_EVENTS).unwrap();
+
+    let mut cursor = timeline.cursor(GameLoop(332));
+    assert_eq!(cursor.position(), 440);
+    let chat = cursor.next().unwrap();
+    assert_eq!(chat.event.name, "NNet.Game.SChatMessage");
+    assert_eq!(cursor.prev(), Some(chat));
+    assert!(cursor.prev().unwrap().gameloop() < GameLoop(332));
+
+    cursor.seek(GameLoop(0));
+    assert_eq!(cursor.prev(), None);
+    assert_eq!(cursor.count(), timeline.len());
+}
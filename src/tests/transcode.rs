@@ -0,0 +1,74 @@
+use serde_json;
+
+use ::events;
+use ::format::protocol15405::{
+    TYPEINFOS,
+    REPLAY_HEADER_TYPEID,
+    GAME_DETAILS_TYPEID,
+    REPLAY_INITDATA_TYPEID,
+};
+use ::s2json::event_value;
+use ::transcode::{self, transcode};
+use ::value::Value;
+use ::{VersionedDeserializer, BitPackedDeserializer};
+use super::{HEADER, DETAILS, INIT_DATA, GAME_EVENTS, MESSAGE_EVENTS};
+
+fn parse(buf: &[u8]) -> serde_json::Value {
+    serde_json::from_str(::std::str::from_utf8(buf).unwrap()).unwrap()
+}
+
+#[test]
+fn transcode_versioned() {
+    for &(buf, typeid) in [(HEADER, REPLAY_HEADER_TYPEID), (DETAILS, GAME_DETAILS_TYPEID)].iter() {
+        let mut out = Vec::new();
+        {
+            let mut serializer = serde_json::Serializer::new(&mut out);
+            let mut de = VersionedDeserializer::new(buf, TYPEINFOS, typeid);
+            transcode(&mut de, typeid as u32, &mut serializer).unwrap();
+        }
+        let value = Value::from_versioned(buf, TYPEINFOS, typeid).unwrap();
+        assert_eq!(parse(&out), serde_json::to_value(&value));
+    }
+}
+
+#[test]
+fn transcode_bitpacked() {
+    let mut out = Vec::new();
+    {
+        let mut serializer = serde_json::Serializer::new(&mut out);
+        let mut de = BitPackedDeserializer::new(INIT_DATA, TYPEINFOS, REPLAY_INITDATA_TYPEID);
+        transcode(&mut de, REPLAY_INITDATA_TYPEID as u32, &mut serializer).unwrap();
+    }
+    let value = Value::from_bitpacked(INIT_DATA, TYPEINFOS, REPLAY_INITDATA_TYPEID).unwrap();
+    assert_eq!(parse(&out), serde_json::to_value(&value));
+}
+
+#[test]
+fn ndjson_events() {
+    for &(buf, game) in [(MESSAGE_EVENTS, false), (GAME_EVENTS, true)].iter() {
+        let mut out = Vec::new();
+        if game {
+            transcode::write_ndjson(&mut out, transcode::game_events(buf)).unwrap();
+        } else {
+            transcode::write_ndjson(&mut out, transcode::message_events(buf)).unwrap();
+        }
+        let events: Vec<_> = if game {
+            events::game_events(buf).map(|e| e.unwrap()).collect()
+        } else {
+            events::message_events(buf).map(|e| e.unwrap()).collect()
+        };
+
+        let lines: Vec<&[u8]> = out.split(|&b| b == b'\n').filter(|l| !l.is_empty()).collect();
+        assert_eq!(lines.len(), events.len());
+        for (line, event) in lines.iter().zip(events.iter()) {
+            assert_eq!(parse(line), serde_json::to_value(&event_value(event)));
+        }
+    }
+}
+
+#[test]
+fn ndjson_truncated() {
+    let mut out = Vec::new();
+    let truncated = &GAME_EVENTS[..100];
+    assert!(transcode::write_ndjson(&mut out, transcode::game_events(truncated)).is_err());
+}
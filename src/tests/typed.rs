@@ -0,0 +1,92 @@
+use ::common::GameLoop;
+use ::format::protocol15405_events::*;
+use ::typed::{self, BitArray};
+use super::{GAME_EVENTS, MESSAGE_EVENTS};
+
+#[test]
+fn typed_game_events_15405() {
+    let events: Vec<_> = typed::game_events(GAME_EVENTS).map(|e| e.unwrap()).collect();
+    assert_eq!(events.len(), 37058);
+
+    match events[9].event {
+        GameEvent::SCameraUpdateEvent(ref camera) => {
+            assert_eq!(camera.target, Target { x: 26112, y: 39874 });
+            assert_eq!(camera.distance, Some(8704));
+            assert_eq!(camera.pitch, Some(318));
+            assert_eq!(camera.yaw, Some(1024));
+        },
+        ref other => panic!("unexpected {:?}", other),
+    }
+
+    match events[17].event {
+        GameEvent::SSelectionDeltaEvent(ref selection) => {
+            assert_eq!(selection.control_group_id, 10);
+            assert_eq!(selection.delta.remove_mask, BitArray { len: 0, bits: vec![] });
+            assert_eq!(selection.delta.add_subgroups, vec![
+                AddSubgroup { unit_link: 114, intra_subgroup_priority: 1, count: 6 },
+            ]);
+            assert_eq!(selection.delta.add_unit_tags.len(), 6);
+        },
+        ref other => panic!("unexpected {:?}", other),
+    }
+
+    let trade = events.iter().filter_map(|e| match e.event {
+        GameEvent::SResourceTradeEvent(ref trade) => Some((e, trade)),
+        _ => None,
+    }).next().unwrap();
+    assert_eq!(trade.0.gameloop, GameLoop(19474));
+    assert_eq!(trade.0.user_id, Some(5));
+    assert_eq!(trade.1.recipient_id, 6);
+    assert_eq!(trade.1.resources, vec![1594, 1155, 0, 0]);
+
+    let cmds = events.iter().filter(|e| e.event.name() == "NNet.Game.SCmdEvent").count();
+    assert_eq!(cmds, 6866);
+    let leaves = events.iter().filter(|e| e.event == GameEvent::SPlayerLeaveEvent(SPlayerLeaveEvent)).count();
+    assert_eq!(leaves, 4);
+}
+
+#[test]
+fn bit_array() {
+    let bits = BitArray { len: 12, bits: vec![0x08, 0x01] };
+    assert!(bits.bit(0));
+    assert!(!bits.bit(1));
+    assert!(bits.bit(11));
+    assert!(!bits.bit(12));
+    assert_eq!(bits.count_ones(), 2);
+}
+
+#[test]
+fn typed_message_events_15405() {
+    use ::format::protocol15405_messages::{SChatMessage, SPingMessage, Point};
+    use ::typed::{Blob, MessageEvent, Recipient};
+
+    let events: Vec<_> = typed::message_events(MESSAGE_EVENTS).map(|e| e.unwrap()).collect();
+    assert_eq!(events.len(), 45);
+
+    let chat: Vec<_> = events.iter().filter_map(|e| match e.event {
+        MessageEvent::SChatMessage(ref chat) => Some((e.gameloop, e.user_id, chat)),
+        _ => None,
+    }).collect();
+    assert_eq!(chat, vec![(GameLoop(332), Some(8), &SChatMessage {
+        recipient: Recipient::All,
+        string: Blob(b"yo".to_vec()),
+    })]);
+    assert_eq!(chat[0].2.string.as_str(), Some("yo"));
+
+    let pings: Vec<_> = events.iter().filter_map(|e| match e.event {
+        MessageEvent::SPingMessage(ref ping) => Some((e.gameloop, e.user_id, ping)),
+        _ => None,
+    }).collect();
+    assert_eq!(pings.len(), 4);
+    assert_eq!(pings[0], (GameLoop(22412), Some(4), &SPingMessage {
+        recipient: Recipient::Allies,
+        point: Point { x: 122623, y: 433127 },
+    }));
+    assert!(pings.iter().all(|p| p.2.recipient == Recipient::Allies));
+
+    let progress = events.iter().filter(|e| match e.event {
+        MessageEvent::SLoadingProgressMessage(ref msg) => msg.progress <= 100,
+        _ => false,
+    }).count();
+    assert_eq!(progress, 40);
+}
@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use serde_json;
+
+use ::format::protocol15405::{TYPEINFOS, GAME_DETAILS_TYPEID, REPLAY_INITDATA_TYPEID};
+use ::value::Value;
+use super::{DETAILS, INIT_DATA};
+
+#[test]
+fn details_value() {
+    let details = Value::from_versioned(DETAILS, TYPEINFOS, GAME_DETAILS_TYPEID).unwrap();
+
+    assert_eq!(details["m_title"], Value::String("Toxic Slums".to_string()));
+    assert_eq!(details["m_timeUTC"].as_i64(), Ok(129257541208634645));
+    assert_eq!(details["m_playerList"].as_array().map(|x| x.len()), Ok(8));
+    assert_eq!(details["m_playerList"][0]["m_toon"]["m_programId"], Value::FourCC(*b"\0\0S2"));
+
+    // cache handles are not valid UTF-8, and stay bytes
+    let handle = details["m_cacheHandles"][0].as_bytes().unwrap();
+    assert_eq!(handle.len(), 40);
+    assert_eq!(&handle[..4], b"s2ma");
+    assert!(details["m_cacheHandles"][0].as_str().is_err());
+}
+
+#[test]
+fn init_data_value() {
+    let init_data = Value::from_bitpacked(INIT_DATA, TYPEINFOS, REPLAY_INITDATA_TYPEID).unwrap();
+    let lobby = &init_data["m_syncLobbyState"];
+
+    assert_eq!(lobby["m_userInitialData"].as_array().map(|x| x.len()), Ok(16));
+    assert_eq!(lobby["m_userInitialData"][4]["m_name"].as_str(), Ok("Rev"));
+    assert_eq!(lobby["m_gameDescription"]["m_mapSizeX"].as_u64(), Ok(184));
+    assert_eq!(lobby["m_gameDescription"]["m_gameCacheName"].as_str(), Ok("Dflt"));
+    assert_eq!(lobby["m_gameDescription"]["m_slotDescriptions"][0]["m_allowedColors"],
+               Value::BitArray(16, vec![0xfe, 0xff]));
+    assert_eq!(lobby["m_lobbyState"]["m_hostUserId"], Value::Null);
+    assert_eq!(lobby["m_lobbyState"]["m_slots"][5]["m_racePref"]["m_race"],
+               Value::Optional(Box::new(Value::U64(1))));
+}
+
+fn sample() -> Value {
+    let mut toon = BTreeMap::new();
+    toon.insert("m_programId".to_string(), Value::FourCC(*b"\0\0S2"));
+    toon.insert("m_id".to_string(), Value::U64(854244));
+
+    let mut player = BTreeMap::new();
+    player.insert("m_name".to_string(), Value::String("narod".to_string()));
+    player.insert("m_toon".to_string(), Value::Dict(toon));
+
+    let mut root = BTreeMap::new();
+    root.insert("m_playerList".to_string(),
+        Value::Optional(Box::new(Value::Array(vec![Value::Dict(player)]))));
+    root.insert("m_handle".to_string(), Value::Bytes(vec![b's', 0xe4]));
+    Value::Dict(root)
+}
+
+#[test]
+fn value_index() {
+    let val = sample();
+    assert_eq!(val["m_playerList"][0]["m_name"].as_str(), Ok("narod"));
+    assert_eq!(val["m_playerList"][0]["m_toon"]["m_id"].as_u64(), Ok(854244));
+    assert_eq!(val["m_playerList"][1], Value::Null);
+    assert_eq!(val["missing"]["m_name"], Value::Null);
+    assert_eq!(val.get_path(&["m_playerList"]).and_then(|x| x.as_array()).map(|x| x.len()), Ok(1));
+}
+
+#[test]
+fn value_mutate() {
+    let mut val = sample();
+    val["m_playerList"][0]["m_name"] = Value::String("min".to_string());
+    *val.get_path_mut(&["m_playerList"]).unwrap() = Value::Null;
+    assert!(val["m_playerList"].is_null());
+    val.as_dict_mut().unwrap().remove("m_handle");
+    assert_eq!(val.as_dict().unwrap().len(), 1);
+}
+
+#[test]
+fn value_choice() {
+    let val = Value::Choice("m_uint6".to_string(), Box::new(Value::U64(3)));
+    assert_eq!(val["m_uint6"].as_u64(), Ok(3));
+    assert_eq!(val["m_uint14"], Value::Null);
+    assert_eq!(val.as_choice(), Ok(("m_uint6", &Value::U64(3))));
+}
+
+#[test]
+fn value_to_json() {
+    let json: serde_json::Value = sample().into();
+    assert_eq!(
+        serde_json::to_string(&json).unwrap(),
+        "{\"m_handle\":\"s\u{e4}\",\"m_playerList\":[{\"m_name\":\"narod\",\
+         \"m_toon\":{\"m_id\":854244,\"m_programId\":\"\\u0000\\u0000S2\"}}]}");
+}
+
+#[test]
+fn value_from_json() {
+    let json: serde_json::Value = serde_json::from_str("{\"a\":[1,-2,1.5,null,true]}").unwrap();
+    let val: Value = json.into();
+    assert_eq!(val["a"][0], Value::U64(1));
+    assert_eq!(val["a"][1], Value::I64(-2));
+    assert_eq!(val["a"][2], Value::F64(1.5));
+    assert_eq!(val["a"][3], Value::Null);
+    assert_eq!(val["a"][4], Value::Boolean(true));
+}
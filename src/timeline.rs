@@ -0,0 +1,184 @@
+//! All of a replay's event streams merged into one gameloop-ordered list.
+
+use std::cmp::Ordering;
+use std::slice;
+
+use common::GameLoop;
+use events::{self, Event};
+use versioned_serde::Result;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum StreamKind {
+    Game,
+    Message,
+    Tracker,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimelineEvent {
+    pub stream: StreamKind,
+    pub event: Event,
+}
+
+impl TimelineEvent {
+    pub fn gameloop(&self) -> GameLoop {
+        self.event.gameloop
+    }
+
+    /// The user for game and message events; tracker events carry no user,
+    /// so their `m_playerId` is used instead.
+    pub fn player(&self) -> Option<u32> {
+        match self.stream {
+            StreamKind::Tracker => self.event.value["m_playerId"].as_u64().ok().map(|id| id as u32),
+            _ => self.event.user_id,
+        }
+    }
+}
+
+/// Events from every stream, ordered by gameloop.  Events on the same
+/// gameloop keep their stream's order, and streams are ordered by
+/// `StreamKind`.
+///
+/// Decoding is done up front so that the timeline can be stepped through
+/// in either direction.
+#[derive(Clone, Debug, Default)]
+pub struct Timeline {
+    events: Vec<TimelineEvent>,
+}
+
+impl Timeline {
+    pub fn new() -> Timeline {
+        Timeline { events: Vec::new() }
+    }
+
+    /// Merges the contents of `replay.game.events` and
+    /// `replay.message.events`.
+    pub fn from_replay(game_events: &[u8], message_events: &[u8]) -> Result<Timeline> {
+        let mut timeline = Timeline::new();
+        try!(timeline.add_stream(StreamKind::Game, events::game_events(game_events)));
+        try!(timeline.add_stream(StreamKind::Message, events::message_events(message_events)));
+        Ok(timeline)
+    }
+
+    /// Decodes a whole stream and merges it in.  On error the timeline is
+    /// left unchanged.
+    pub fn add_stream<I>(&mut self, stream: StreamKind, events: I) -> Result<()>
+        where I: Iterator<Item=Result<Event>>,
+    {
+        let mut added = Vec::new();
+        for event in events {
+            added.push(TimelineEvent {
+                stream: stream,
+                event: try!(event),
+            });
+        }
+        self.events.extend(added.into_iter());
+        // stable, so same-gameloop events stay in stream order
+        self.events.sort_by(|a, b| {
+            match a.gameloop().cmp(&b.gameloop()) {
+                Ordering::Equal => a.stream.cmp(&b.stream),
+                ord => ord,
+            }
+        });
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    pub fn events(&self) -> &[TimelineEvent] {
+        &self.events
+    }
+
+    pub fn iter(&self) -> slice::Iter<TimelineEvent> {
+        self.events.iter()
+    }
+
+    /// Index of the first event at or after `gameloop`.
+    pub fn seek(&self, gameloop: GameLoop) -> usize {
+        let mut lo = 0;
+        let mut hi = self.events.len();
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.events[mid].gameloop() < gameloop {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    }
+
+    /// Events with `start <= gameloop < end`.
+    pub fn window(&self, start: GameLoop, end: GameLoop) -> &[TimelineEvent] {
+        if end <= start {
+            return &[];
+        }
+        let lo = self.seek(start);
+        let hi = self.seek(end);
+        &self.events[lo..hi]
+    }
+
+    /// A cursor positioned at the first event at or after `gameloop`.
+    pub fn cursor(&self, gameloop: GameLoop) -> Cursor {
+        Cursor {
+            timeline: self,
+            pos: self.seek(gameloop),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Timeline {
+    type Item = &'a TimelineEvent;
+    type IntoIter = slice::Iter<'a, TimelineEvent>;
+
+    fn into_iter(self) -> slice::Iter<'a, TimelineEvent> {
+        self.events.iter()
+    }
+}
+
+/// Steps through a timeline in either direction.
+pub struct Cursor<'a> {
+    timeline: &'a Timeline,
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// Index of the event `next` would return.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn seek(&mut self, gameloop: GameLoop) {
+        self.pos = self.timeline.seek(gameloop);
+    }
+
+    pub fn peek(&self) -> Option<&'a TimelineEvent> {
+        self.timeline.events.get(self.pos)
+    }
+
+    pub fn prev(&mut self) -> Option<&'a TimelineEvent> {
+        if self.pos == 0 {
+            return None;
+        }
+        self.pos -= 1;
+        self.timeline.events.get(self.pos)
+    }
+}
+
+impl<'a> Iterator for Cursor<'a> {
+    type Item = &'a TimelineEvent;
+
+    fn next(&mut self) -> Option<&'a TimelineEvent> {
+        let event = self.timeline.events.get(self.pos);
+        if event.is_some() {
+            self.pos += 1;
+        }
+        event
+    }
+}
@@ -0,0 +1,438 @@
+//! Streams decoded bytes straight into any serde `Serializer`, without
+//! building a `Value` tree first.  Event streams can be written out as
+//! NDJSON in constant memory.
+//!
+//! Compound types (arrays, structs, choices, optionals) are walked as the
+//! serializer asks for them; scalars are decoded one at a time.  The output
+//! shape matches `Value`'s `Serialize` impl, except that struct fields come
+//! out in wire order rather than sorted.
+
+use std::cell::RefCell;
+use std::io::{self, Write};
+use std::result;
+
+use serde::ser::{self, Serialize};
+use serde_json;
+
+use events::{EventDecoder, EventStream, EventHead};
+use format::{
+    TypeInfo,
+    TypeId,
+    Struct,
+    StructField,
+    IntBounds,
+    ChoiceTypeMap,
+};
+use format::protocol15405::{TYPEINFOS, SVARUINT32_TYPEID};
+use versioned_serde::{Error, Result};
+use bitpacked_serde;
+use value::Value;
+
+/// The structural reads the transcoder needs; implemented by both the
+/// versioned and bit-packed decoders.
+pub trait Source {
+    fn typeinfo(&self, typeid: TypeId) -> Result<&'static TypeInfo>;
+
+    /// Reads an array's length.
+    fn read_array_len(&mut self, bounds: IntBounds) -> Result<usize>;
+
+    /// Reads a choice's tag.  `None` means the variant is unknown and its
+    /// value has already been skipped.
+    fn read_choice(&mut self, bounds: IntBounds, types: &'static ChoiceTypeMap)
+        -> Result<Option<(&'static str, TypeId)>>;
+
+    /// Reads whether an optional value is present.
+    fn read_optional(&mut self) -> Result<bool>;
+
+    /// Reads how many fields of `def` follow.
+    fn read_struct_len(&mut self, def: Struct) -> Result<usize>;
+
+    /// Reads which field comes `idx`th.  `None` means the field is unknown
+    /// and has already been skipped.
+    fn read_struct_field(&mut self, def: Struct, idx: usize) -> Result<Option<&'static StructField>>;
+
+    /// Decodes anything without children.
+    fn read_scalar(&mut self, typeinfo: &'static TypeInfo) -> Result<Value>;
+}
+
+impl<'a, D: Source> Source for &'a mut D {
+    fn typeinfo(&self, typeid: TypeId) -> Result<&'static TypeInfo> {
+        (**self).typeinfo(typeid)
+    }
+
+    fn read_array_len(&mut self, bounds: IntBounds) -> Result<usize> {
+        (**self).read_array_len(bounds)
+    }
+
+    fn read_choice(&mut self, bounds: IntBounds, types: &'static ChoiceTypeMap)
+        -> Result<Option<(&'static str, TypeId)>>
+    {
+        (**self).read_choice(bounds, types)
+    }
+
+    fn read_optional(&mut self) -> Result<bool> {
+        (**self).read_optional()
+    }
+
+    fn read_struct_len(&mut self, def: Struct) -> Result<usize> {
+        (**self).read_struct_len(def)
+    }
+
+    fn read_struct_field(&mut self, def: Struct, idx: usize) -> Result<Option<&'static StructField>> {
+        (**self).read_struct_field(def, idx)
+    }
+
+    fn read_scalar(&mut self, typeinfo: &'static TypeInfo) -> Result<Value> {
+        (**self).read_scalar(typeinfo)
+    }
+}
+
+impl From<Error> for io::Error {
+    fn from(e: Error) -> io::Error {
+        match e {
+            Error::IoError(err) => err,
+            err => io::Error::new(io::ErrorKind::InvalidData, format!("{:?}", err)),
+        }
+    }
+}
+
+impl From<Error> for serde_json::Error {
+    fn from(e: Error) -> serde_json::Error {
+        serde_json::Error::IoError(From::from(e))
+    }
+}
+
+/// `Serialize` can't name the serializer's error type, so the first decode
+/// error is parked here, a `null` is written in its place and everything
+/// after it is skipped.  The entry points hand the parked error back.
+struct State<D> {
+    de: D,
+    error: Option<Error>,
+}
+
+fn read<D, T, F>(state: &RefCell<State<D>>, f: F) -> Option<T>
+    where D: Source, F: FnOnce(&mut D) -> Result<T>,
+{
+    let mut state = state.borrow_mut();
+    if state.error.is_some() {
+        return None;
+    }
+    match f(&mut state.de) {
+        Ok(val) => Some(val),
+        Err(err) => {
+            state.error = Some(err);
+            None
+        }
+    }
+}
+
+fn take_error<D, E>(state: &RefCell<State<D>>) -> result::Result<(), E> where E: From<Error> {
+    match state.borrow_mut().error.take() {
+        Some(err) => Err(From::from(err)),
+        None => Ok(()),
+    }
+}
+
+/// One not yet decoded instance of `typeinfo`.
+struct Node<'a, D: 'a> {
+    state: &'a RefCell<State<D>>,
+    typeinfo: &'static TypeInfo,
+}
+
+impl<'a, D: Source> Serialize for Node<'a, D> {
+    fn serialize<S>(&self, serializer: &mut S) -> result::Result<(), S::Error>
+        where S: ser::Serializer,
+    {
+        let state = self.state;
+        match *self.typeinfo {
+            TypeInfo::Array { bounds, typeid } => {
+                let len = match read(state, |de| de.read_array_len(bounds)) {
+                    Some(len) => len,
+                    None => return serializer.visit_unit(),
+                };
+                let item_ti = match read(state, |de| de.typeinfo(typeid)) {
+                    Some(item_ti) => item_ti,
+                    None => return serializer.visit_unit(),
+                };
+                serializer.visit_seq(ArrayVisitor {
+                    state: state,
+                    typeinfo: item_ti,
+                    len: len,
+                    offset: 0,
+                })
+            },
+            TypeInfo::Choice { bounds, ref types } => {
+                let variant = match read(state, |de| de.read_choice(bounds, types)) {
+                    Some(Some((name, typeid))) => {
+                        match read(state, |de| de.typeinfo(typeid)) {
+                            Some(field_ti) => Some((name, field_ti)),
+                            None => return serializer.visit_unit(),
+                        }
+                    },
+                    Some(None) => None,
+                    None => return serializer.visit_unit(),
+                };
+                serializer.visit_map(ChoiceVisitor {
+                    state: state,
+                    variant: variant,
+                })
+            },
+            TypeInfo::Optional { typeid } => {
+                match read(state, |de| de.read_optional()) {
+                    Some(true) => (),
+                    Some(false) => return serializer.visit_none(),
+                    None => return serializer.visit_unit(),
+                }
+                match read(state, |de| de.typeinfo(typeid)) {
+                    Some(inner_ti) => serializer.visit_some(Node { state: state, typeinfo: inner_ti }),
+                    None => serializer.visit_unit(),
+                }
+            },
+            TypeInfo::Struct(def) => {
+                // A struct whose only field is its parent is the parent.
+                if def.fields.len() == 1 && def.fields[0].0 == "__parent" {
+                    return match read(state, |de| de.typeinfo(def.fields[0].1)) {
+                        Some(parent_ti) => Node { state: state, typeinfo: parent_ti }.serialize(serializer),
+                        None => serializer.visit_unit(),
+                    };
+                }
+                serializer.visit_map(StructVisitor {
+                    state: state,
+                    def: def,
+                    done: false,
+                })
+            },
+            _ => match read(state, |de| de.read_scalar(self.typeinfo)) {
+                Some(val) => val.serialize(serializer),
+                None => serializer.visit_unit(),
+            },
+        }
+    }
+}
+
+struct ArrayVisitor<'a, D: 'a> {
+    state: &'a RefCell<State<D>>,
+    typeinfo: &'static TypeInfo,
+    len: usize,
+    offset: usize,
+}
+
+impl<'a, D: Source> ser::SeqVisitor for ArrayVisitor<'a, D> {
+    fn visit<S>(&mut self, serializer: &mut S) -> result::Result<Option<()>, S::Error>
+        where S: ser::Serializer,
+    {
+        if self.len == self.offset || self.state.borrow().error.is_some() {
+            return Ok(None);
+        }
+        self.offset += 1;
+        try!(serializer.visit_seq_elt(Node { state: self.state, typeinfo: self.typeinfo }));
+        Ok(Some(()))
+    }
+
+    fn len(&self) -> Option<usize> {
+        Some(self.len)
+    }
+}
+
+/// A choice is a map holding one entry, or none for a skipped variant.
+struct ChoiceVisitor<'a, D: 'a> {
+    state: &'a RefCell<State<D>>,
+    variant: Option<(&'static str, &'static TypeInfo)>,
+}
+
+impl<'a, D: Source> ser::MapVisitor for ChoiceVisitor<'a, D> {
+    fn visit<S>(&mut self, serializer: &mut S) -> result::Result<Option<()>, S::Error>
+        where S: ser::Serializer,
+    {
+        match self.variant.take() {
+            Some((name, typeinfo)) => {
+                try!(serializer.visit_map_elt(name, Node { state: self.state, typeinfo: typeinfo }));
+                Ok(Some(()))
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn len(&self) -> Option<usize> {
+        Some(if self.variant.is_some() { 1 } else { 0 })
+    }
+}
+
+/// Writes every field in one go, so that parent structs can be flattened
+/// into the same map.
+struct StructVisitor<'a, D: 'a> {
+    state: &'a RefCell<State<D>>,
+    def: Struct,
+    done: bool,
+}
+
+impl<'a, D: Source> ser::MapVisitor for StructVisitor<'a, D> {
+    fn visit<S>(&mut self, serializer: &mut S) -> result::Result<Option<()>, S::Error>
+        where S: ser::Serializer,
+    {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+        try!(serialize_fields(self.state, self.def, serializer));
+        Ok(Some(()))
+    }
+}
+
+fn serialize_fields<D, S>(state: &RefCell<State<D>>, def: Struct, serializer: &mut S)
+    -> result::Result<(), S::Error>
+    where D: Source, S: ser::Serializer,
+{
+    let len = match read(state, |de| de.read_struct_len(def)) {
+        Some(len) => len,
+        None => return Ok(()),
+    };
+    for idx in 0..len {
+        let &(name, typeid, _) = match read(state, |de| de.read_struct_field(def, idx)) {
+            Some(Some(field)) => field,
+            Some(None) => continue,
+            None => return Ok(()),
+        };
+        let field_ti = match read(state, |de| de.typeinfo(typeid)) {
+            Some(field_ti) => field_ti,
+            None => return Ok(()),
+        };
+        if name == "__parent" {
+            if let TypeInfo::Struct(parent) = *field_ti {
+                try!(serialize_fields(state, parent, serializer));
+                continue;
+            }
+        }
+        try!(serializer.visit_map_elt(name, Node { state: state, typeinfo: field_ti }));
+    }
+    Ok(())
+}
+
+/// Serializes one instance of `typeid` read from `de`.
+pub fn transcode<D, S>(de: &mut D, typeid: TypeId, serializer: &mut S) -> result::Result<(), S::Error>
+    where D: Source, S: ser::Serializer, S::Error: From<Error>,
+{
+    let typeinfo = try!(de.typeinfo(typeid));
+    let state = RefCell::new(State { de: de, error: None });
+    try!(Node { state: &state, typeinfo: typeinfo }.serialize(serializer));
+    take_error(&state)
+}
+
+/// Serializes events one at a time, each as a map of its fields plus
+/// `_event`, `_eventid`, `_gameloop`, `_userid` and, last, `_bits`.
+pub struct EventTranscoder<D> {
+    state: RefCell<State<D>>,
+    stream: EventStream,
+    gameloop: u32,
+}
+
+impl<D: Source + EventDecoder> EventTranscoder<D> {
+    pub fn new(de: D, stream: EventStream) -> EventTranscoder<D> {
+        EventTranscoder {
+            state: RefCell::new(State { de: de, error: None }),
+            stream: stream,
+            gameloop: 0,
+        }
+    }
+
+    pub fn done(&self) -> bool {
+        self.state.borrow().de.done()
+    }
+
+    /// Serializes the next event.  After an error the stream can't be
+    /// resynchronized and should be abandoned.
+    pub fn transcode_next<S>(&mut self, serializer: &mut S) -> result::Result<(), S::Error>
+        where S: ser::Serializer, S::Error: From<Error>,
+    {
+        let (start, head) = {
+            let mut state = self.state.borrow_mut();
+            let start = state.de.used_bits();
+            (start, try!(self.stream.read_head(&mut state.de, &mut self.gameloop)))
+        };
+        let typeinfo = try!(self.state.borrow().de.typeinfo(head.typeid));
+        try!(serializer.visit_map(EventVisitor {
+            state: &self.state,
+            head: head,
+            typeinfo: typeinfo,
+            start: start,
+            done: false,
+        }));
+        take_error(&self.state)
+    }
+}
+
+struct EventVisitor<'a, D: 'a> {
+    state: &'a RefCell<State<D>>,
+    head: EventHead,
+    typeinfo: &'static TypeInfo,
+    start: usize,
+    done: bool,
+}
+
+impl<'a, D: Source + EventDecoder> ser::MapVisitor for EventVisitor<'a, D> {
+    fn visit<S>(&mut self, serializer: &mut S) -> result::Result<Option<()>, S::Error>
+        where S: ser::Serializer,
+    {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+
+        try!(serializer.visit_map_elt("_event", self.head.name));
+        try!(serializer.visit_map_elt("_eventid", self.head.event_id));
+        try!(serializer.visit_map_elt("_gameloop", self.head.gameloop.0));
+        if let Some(user_id) = self.head.user_id {
+            try!(serializer.visit_map_elt("_userid", UserId(user_id)));
+        }
+        match *self.typeinfo {
+            TypeInfo::Struct(def) => try!(serialize_fields(self.state, def, serializer)),
+            _ => try!(serializer.visit_map_elt("_value", Node { state: self.state, typeinfo: self.typeinfo })),
+        }
+
+        let bits = {
+            let mut state = self.state.borrow_mut();
+            state.de.byte_align();
+            state.de.used_bits() - self.start
+        };
+        try!(serializer.visit_map_elt("_bits", bits));
+        Ok(Some(()))
+    }
+}
+
+/// `_userid` is `{"m_playerId": n}`, as s2protocol writes it.
+struct UserId(u32);
+
+impl Serialize for UserId {
+    fn serialize<S>(&self, serializer: &mut S) -> result::Result<(), S::Error>
+        where S: ser::Serializer,
+    {
+        let entry = Some(("m_playerId", self.0)).into_iter();
+        serializer.visit_map(ser::impls::MapIteratorVisitor::new(entry, Some(1)))
+    }
+}
+
+/// Writes each remaining event as one line of JSON.
+pub fn write_ndjson<W, D>(writer: &mut W, mut events: EventTranscoder<D>) -> io::Result<()>
+    where W: Write, D: Source + EventDecoder,
+{
+    while !events.done() {
+        {
+            let mut serializer = serde_json::Serializer::new(&mut *writer);
+            try!(events.transcode_next(&mut serializer));
+        }
+        try!(writer.write_all(b"\n"));
+    }
+    Ok(())
+}
+
+/// Transcodes the contents of `replay.game.events`.
+pub fn game_events(buf: &[u8]) -> EventTranscoder<bitpacked_serde::Deserializer> {
+    let de = bitpacked_serde::Deserializer::new(buf, TYPEINFOS, SVARUINT32_TYPEID);
+    EventTranscoder::new(de, EventStream::game_15405())
+}
+
+/// Transcodes the contents of `replay.message.events`.
+pub fn message_events(buf: &[u8]) -> EventTranscoder<bitpacked_serde::Deserializer> {
+    let de = bitpacked_serde::Deserializer::new(buf, TYPEINFOS, SVARUINT32_TYPEID);
+    EventTranscoder::new(de, EventStream::message_15405())
+}
@@ -0,0 +1,264 @@
+//! Plain Rust types for decoded events.  The per-protocol event structs are
+//! generated by `py2rs_events.py`; this module holds what they build on.
+
+use std::marker::PhantomData;
+use std::str;
+
+use common::GameLoop;
+use events::{self, Event, EventIter, EventDecoder};
+use versioned_serde;
+use bitpacked_serde;
+use value::Value;
+
+pub use format::protocol15405_events::GameEvent;
+pub use format::protocol15405_messages::MessageEvent;
+
+#[derive(Debug)]
+pub enum Error {
+    Decode(versioned_serde::Error),
+    UnknownEvent(u32),
+    MissingField(&'static str),
+    InvalidField(&'static str),
+    InvalidType,
+}
+
+impl From<versioned_serde::Error> for Error {
+    fn from(e: versioned_serde::Error) -> Error {
+        Error::Decode(e)
+    }
+}
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+pub trait FromValue: Sized {
+    fn from_value(value: &Value) -> Result<Self>;
+}
+
+pub trait FromEvent: Sized {
+    fn from_event(event: &Event) -> Result<Self>;
+}
+
+/// Converts the struct field `name`, naming it in the error if it has the
+/// wrong type.
+pub fn field<T: FromValue>(value: &Value, name: &'static str) -> Result<T> {
+    let dict = try!(value.as_dict().map_err(|_| Error::InvalidType));
+    match dict.get(name) {
+        Some(val) => T::from_value(val).map_err(|err| match err {
+            Error::InvalidType => Error::InvalidField(name),
+            err => err,
+        }),
+        None => Err(Error::MissingField(name)),
+    }
+}
+
+macro_rules! from_value_unsigned {
+    ($($ty:ty),*) => {
+        $(
+            impl FromValue for $ty {
+                fn from_value(value: &Value) -> Result<$ty> {
+                    let val = try!(value.as_u64().map_err(|_| Error::InvalidType));
+                    if val as $ty as u64 != val {
+                        return Err(Error::InvalidType);
+                    }
+                    Ok(val as $ty)
+                }
+            }
+        )*
+    }
+}
+
+macro_rules! from_value_signed {
+    ($($ty:ty),*) => {
+        $(
+            impl FromValue for $ty {
+                fn from_value(value: &Value) -> Result<$ty> {
+                    let val = try!(value.as_i64().map_err(|_| Error::InvalidType));
+                    if val as $ty as i64 != val {
+                        return Err(Error::InvalidType);
+                    }
+                    Ok(val as $ty)
+                }
+            }
+        )*
+    }
+}
+
+from_value_unsigned!(u8, u16, u32, u64);
+from_value_signed!(i8, i16, i32, i64);
+
+impl FromValue for bool {
+    fn from_value(value: &Value) -> Result<bool> {
+        value.as_bool().map_err(|_| Error::InvalidType)
+    }
+}
+
+impl FromValue for f32 {
+    fn from_value(value: &Value) -> Result<f32> {
+        value.as_f64().map(|val| val as f32).map_err(|_| Error::InvalidType)
+    }
+}
+
+impl FromValue for f64 {
+    fn from_value(value: &Value) -> Result<f64> {
+        value.as_f64().map_err(|_| Error::InvalidType)
+    }
+}
+
+impl FromValue for () {
+    fn from_value(value: &Value) -> Result<()> {
+        match *value {
+            Value::Null => Ok(()),
+            _ => Err(Error::InvalidType),
+        }
+    }
+}
+
+impl FromValue for [u8; 4] {
+    fn from_value(value: &Value) -> Result<[u8; 4]> {
+        match *value.unwrap_optional() {
+            Value::FourCC(fourcc) => Ok(fourcc),
+            _ => Err(Error::InvalidType),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: &Value) -> Result<Option<T>> {
+        match *value {
+            Value::Null => Ok(None),
+            Value::Optional(ref inner) => T::from_value(inner).map(Some),
+            _ => Err(Error::InvalidType),
+        }
+    }
+}
+
+impl<T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: &Value) -> Result<Vec<T>> {
+        let items = try!(value.as_array().map_err(|_| Error::InvalidType));
+        let mut out = Vec::with_capacity(items.len());
+        for item in items.iter() {
+            out.push(try!(T::from_value(item)));
+        }
+        Ok(out)
+    }
+}
+
+/// A blob, which is usually but not always UTF-8 text.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Blob(pub Vec<u8>);
+
+impl Blob {
+    pub fn as_str(&self) -> Option<&str> {
+        str::from_utf8(&self.0).ok()
+    }
+}
+
+impl FromValue for Blob {
+    fn from_value(value: &Value) -> Result<Blob> {
+        value.as_bytes().map(|buf| Blob(buf.to_vec())).map_err(|_| Error::InvalidType)
+    }
+}
+
+/// `len` bits stored as a right-aligned big-endian number, as with
+/// `Value::BitArray`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct BitArray {
+    pub len: usize,
+    pub bits: Vec<u8>,
+}
+
+impl BitArray {
+    /// Bit `idx` of the number, counting from the least significant.
+    pub fn bit(&self, idx: usize) -> bool {
+        if self.len <= idx || self.bits.len() <= idx / 8 {
+            return false;
+        }
+        let byte = self.bits[self.bits.len() - 1 - idx / 8];
+        byte >> (idx % 8) & 1 != 0
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.bits.iter().fold(0, |acc, &b| acc + b.count_ones() as usize)
+    }
+}
+
+impl FromValue for BitArray {
+    fn from_value(value: &Value) -> Result<BitArray> {
+        match *value.unwrap_optional() {
+            Value::BitArray(len, ref bits) => Ok(BitArray { len: len, bits: bits.clone() }),
+            _ => Err(Error::InvalidType),
+        }
+    }
+}
+
+/// Who a chat message or ping was sent to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Recipient {
+    All,
+    Allies,
+    Observers,
+    Other(u8),
+}
+
+impl FromValue for Recipient {
+    fn from_value(value: &Value) -> Result<Recipient> {
+        Ok(match try!(u8::from_value(value)) {
+            0 => Recipient::All,
+            1 => Recipient::Allies,
+            2 => Recipient::Observers,
+            other => Recipient::Other(other),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypedEvent<T> {
+    pub gameloop: GameLoop,
+    pub user_id: Option<u32>,
+    pub bits: usize,
+    pub event: T,
+}
+
+/// Converts each event of an `EventIter`.  Decode errors end the stream;
+/// conversion errors are yielded and iteration carries on.
+pub struct TypedEvents<D, T> {
+    inner: EventIter<D>,
+    marker: PhantomData<T>,
+}
+
+impl<D: EventDecoder, T: FromEvent> TypedEvents<D, T> {
+    pub fn new(inner: EventIter<D>) -> TypedEvents<D, T> {
+        TypedEvents {
+            inner: inner,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<D: EventDecoder, T: FromEvent> Iterator for TypedEvents<D, T> {
+    type Item = Result<TypedEvent<T>>;
+
+    fn next(&mut self) -> Option<Result<TypedEvent<T>>> {
+        let event = match self.inner.next() {
+            Some(Ok(event)) => event,
+            Some(Err(err)) => return Some(Err(Error::Decode(err))),
+            None => return None,
+        };
+        Some(T::from_event(&event).map(|typed| TypedEvent {
+            gameloop: event.gameloop,
+            user_id: event.user_id,
+            bits: event.bits,
+            event: typed,
+        }))
+    }
+}
+
+/// Typed events from the contents of `replay.game.events`.
+pub fn game_events(buf: &[u8]) -> TypedEvents<bitpacked_serde::Deserializer, GameEvent> {
+    TypedEvents::new(events::game_events(buf))
+}
+
+/// Typed events from the contents of `replay.message.events`.
+pub fn message_events(buf: &[u8]) -> TypedEvents<bitpacked_serde::Deserializer, MessageEvent> {
+    TypedEvents::new(events::message_events(buf))
+}
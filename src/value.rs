@@ -0,0 +1,424 @@
+use std::collections::BTreeMap;
+use std::ops::{Index, IndexMut};
+
+use serde::{ser, de};
+use serde::bytes::Bytes;
+use serde_json;
+
+use format::TypeInfo;
+use versioned_serde::{self, Deserializer as VersionedDeserializer};
+use bitpacked_serde::Deserializer as BitPackedDeserializer;
+
+/// A decoded protocol value.
+///
+/// Unlike `serde_json::Value`, this keeps blobs that are not valid UTF-8
+/// apart from strings, optionals apart from their contents, and keeps the
+/// protocol's choice, FourCC and bit array types.
+#[derive(PartialEq, Debug, Clone)]
+pub enum Value {
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Bytes(Vec<u8>),
+    String(String),
+    Array(Vec<Value>),
+    Dict(BTreeMap<String, Value>),
+    Optional(Box<Value>),
+    Boolean(bool),
+    /// A four character code, such as a toon's `m_programId`.
+    FourCC([u8; 4]),
+    /// A bit-packed bit array: its length in bits, and the bits as a
+    /// right-aligned big-endian number.
+    BitArray(usize, Vec<u8>),
+    /// A versioned bit array: its length in bits, and the bytes as stored.
+    /// s2protocol leaves these undecoded, so they stay in stream order.
+    AlignedBitArray(usize, Vec<u8>),
+    /// A choice: the name of the selected variant, and its value.
+    Choice(String, Box<Value>),
+    Null,
+}
+
+static NULL: Value = Value::Null;
+
+impl Value {
+    /// Decodes a versioned (`replay.details`, header) instance of `typeid`.
+    pub fn from_versioned(buf: &[u8], typeinfos: &'static [TypeInfo], typeid: usize) -> versioned_serde::Result<Value> {
+        VersionedDeserializer::new(buf, typeinfos, typeid).read_value()
+    }
+
+    /// Decodes a bit-packed (`replay.initData`, events) instance of `typeid`.
+    pub fn from_bitpacked(buf: &[u8], typeinfos: &'static [TypeInfo], typeid: usize) -> versioned_serde::Result<Value> {
+        BitPackedDeserializer::new(buf, typeinfos, typeid).read_value()
+    }
+
+    /// Strips any number of `Optional` wrappers.
+    pub fn unwrap_optional(&self) -> &Value {
+        match *self {
+            Value::Optional(ref val) => val.unwrap_optional(),
+            _ => self,
+        }
+    }
+
+    pub fn unwrap_optional_mut(&mut self) -> &mut Value {
+        match *self {
+            Value::Optional(ref mut val) => val.unwrap_optional_mut(),
+            _ => self,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        match *self {
+            Value::Null => true,
+            _ => false,
+        }
+    }
+
+    pub fn as_str(&self) -> Result<&str, ()> {
+        match *self.unwrap_optional() {
+            Value::String(ref val) => Ok(&*val),
+            _ => return Err(())
+        }
+    }
+
+    /// Blobs and FourCCs, whether or not they were valid UTF-8.
+    pub fn as_bytes(&self) -> Result<&[u8], ()> {
+        match *self.unwrap_optional() {
+            Value::Bytes(ref val) => Ok(&*val),
+            Value::String(ref val) => Ok(val.as_bytes()),
+            Value::FourCC(ref val) => Ok(&val[..]),
+            _ => return Err(())
+        }
+    }
+
+    pub fn as_array(&self) -> Result<&[Value], ()> {
+        match *self.unwrap_optional() {
+            Value::Array(ref val) => Ok(&*val),
+            _ => return Err(())
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Result<&mut Vec<Value>, ()> {
+        match *self.unwrap_optional_mut() {
+            Value::Array(ref mut val) => Ok(val),
+            _ => return Err(())
+        }
+    }
+
+    pub fn as_dict(&self) -> Result<&BTreeMap<String, Value>, ()> {
+        match *self.unwrap_optional() {
+            Value::Dict(ref val) => Ok(val),
+            _ => return Err(())
+        }
+    }
+
+    pub fn as_dict_mut(&mut self) -> Result<&mut BTreeMap<String, Value>, ()> {
+        match *self.unwrap_optional_mut() {
+            Value::Dict(ref mut val) => Ok(val),
+            _ => return Err(())
+        }
+    }
+
+    pub fn as_choice(&self) -> Result<(&str, &Value), ()> {
+        match *self.unwrap_optional() {
+            Value::Choice(ref name, ref val) => Ok((&*name, &**val)),
+            _ => return Err(())
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool, ()> {
+        match *self.unwrap_optional() {
+            Value::Boolean(val) => Ok(val),
+            _ => return Err(())
+        }
+    }
+
+    pub fn as_u64(&self) -> Result<u64, ()> {
+        match *self.unwrap_optional() {
+            Value::U64(val) => Ok(val),
+            Value::I64(val) if val >= 0 => Ok(val as u64),
+            _ => return Err(())
+        }
+    }
+
+    pub fn as_i64(&self) -> Result<i64, ()> {
+        match *self.unwrap_optional() {
+            Value::I64(val) => Ok(val),
+            Value::U64(val) if val <= i64::max_value() as u64 => Ok(val as i64),
+            _ => return Err(())
+        }
+    }
+
+    pub fn as_f64(&self) -> Result<f64, ()> {
+        match *self.unwrap_optional() {
+            Value::F64(val) => Ok(val),
+            Value::I64(val) => Ok(val as f64),
+            Value::U64(val) => Ok(val as f64),
+            _ => return Err(())
+        }
+    }
+
+    /// Looks up a dictionary key, or the selected variant of a choice.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match *self.unwrap_optional() {
+            Value::Dict(ref map) => map.get(key),
+            Value::Choice(ref name, ref val) if name == key => Some(&**val),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        match *self.unwrap_optional_mut() {
+            Value::Dict(ref mut map) => map.get_mut(key),
+            Value::Choice(ref name, ref mut val) if name == key => Some(&mut **val),
+            _ => None,
+        }
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<&Value> {
+        match *self.unwrap_optional() {
+            Value::Array(ref vec) => vec.get(index),
+            _ => None,
+        }
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut Value> {
+        match *self.unwrap_optional_mut() {
+            Value::Array(ref mut vec) => vec.get_mut(index),
+            _ => None,
+        }
+    }
+
+    pub fn get_path(&self, path: &[&str]) -> Result<&Value, ()> {
+        let mut root: &Value = self;
+        for &part in path.iter() {
+            root = try!(root.get(part).ok_or(()));
+        }
+        Ok(root)
+    }
+
+    pub fn get_path_mut(&mut self, path: &[&str]) -> Result<&mut Value, ()> {
+        let mut root: &mut Value = self;
+        for &part in path.iter() {
+            let next = root;
+            root = try!(next.get_mut(part).ok_or(()));
+        }
+        Ok(root)
+    }
+}
+
+/// Missing keys and out of range indices yield `Value::Null`, so lookups
+/// can be chained.
+impl<'a> Index<&'a str> for Value {
+    type Output = Value;
+
+    fn index(&self, key: &'a str) -> &Value {
+        self.get(key).unwrap_or(&NULL)
+    }
+}
+
+impl Index<usize> for Value {
+    type Output = Value;
+
+    fn index(&self, index: usize) -> &Value {
+        self.get_index(index).unwrap_or(&NULL)
+    }
+}
+
+/// Panics on missing keys; there is nothing to hand out a mutable
+/// reference to.
+impl<'a> IndexMut<&'a str> for Value {
+    fn index_mut(&mut self, key: &'a str) -> &mut Value {
+        match self.get_mut(key) {
+            Some(val) => val,
+            None => panic!("no such key: {:?}", key),
+        }
+    }
+}
+
+impl IndexMut<usize> for Value {
+    fn index_mut(&mut self, index: usize) -> &mut Value {
+        match self.get_index_mut(index) {
+            Some(val) => val,
+            None => panic!("index out of range: {}", index),
+        }
+    }
+}
+
+impl ser::Serialize for Value {
+    fn serialize<S>(&self, serializer: &mut S) -> Result<(), S::Error>
+        where S: ser::Serializer,
+    {
+        match *self {
+            Value::I64(val) => serializer.visit_i64(val),
+            Value::U64(val) => serializer.visit_u64(val),
+            Value::F64(val) => serializer.visit_f64(val),
+            Value::Bytes(ref val) => serializer.visit_bytes(val),
+            Value::String(ref val) => serializer.visit_str(val),
+            Value::Array(ref val) => val.serialize(serializer),
+            Value::Dict(ref val) => val.serialize(serializer),
+            Value::Optional(ref val) => serializer.visit_some(&**val),
+            Value::Boolean(val) => serializer.visit_bool(val),
+            Value::FourCC(ref val) => match ::std::str::from_utf8(val) {
+                Ok(str_val) => serializer.visit_str(str_val),
+                Err(_) => serializer.visit_bytes(val),
+            },
+            Value::BitArray(length, ref bits) |
+            Value::AlignedBitArray(length, ref bits) => (length as u64, Bytes::from(&bits[..])).serialize(serializer),
+            Value::Choice(ref name, ref val) => {
+                let entry = Some((&name[..], &**val)).into_iter();
+                serializer.visit_map(ser::impls::MapIteratorVisitor::new(entry, Some(1)))
+            },
+            Value::Null => serializer.visit_none(),
+        }
+    }
+}
+
+impl de::Deserialize for Value {
+    #[inline]
+    fn deserialize<D>(deserializer: &mut D) -> Result<Value, D::Error>
+        where D: de::Deserializer,
+    {
+        struct ValueVisitor;
+
+        impl de::Visitor for ValueVisitor {
+            type Value = Value;
+
+            #[inline]
+            fn visit_bool<E>(&mut self, value: bool) -> Result<Self::Value, E> {
+                Ok(Value::Boolean(value))
+            }
+
+            #[inline]
+            fn visit_u64<E>(&mut self, value: u64) -> Result<Value, E> {
+                Ok(Value::U64(value))
+            }
+
+            #[inline]
+            fn visit_i64<E>(&mut self, value: i64) -> Result<Value, E> {
+                Ok(Value::I64(value))
+            }
+
+            #[inline]
+            fn visit_f64<E>(&mut self, value: f64) -> Result<Value, E> {
+                Ok(Value::F64(value))
+            }
+
+            #[inline]
+            fn visit_seq<V>(&mut self, visitor: V) -> Result<Value, V::Error>
+                where V: de::SeqVisitor,
+            {
+                let values = try!(de::impls::VecVisitor::new().visit_seq(visitor));
+                Ok(Value::Array(values))
+            }
+
+            #[inline]
+            fn visit_map<V>(&mut self, visitor: V) -> Result<Value, V::Error>
+                where V: de::MapVisitor,
+            {
+                let values: BTreeMap<String, Value> = try!(
+                    de::impls::BTreeMapVisitor::new().visit_map(visitor));
+                Ok(Value::Dict(values))
+            }
+
+            #[inline]
+            fn visit_str<E>(&mut self, value: &str) -> Result<Self::Value, E> {
+                Ok(Value::String(From::from(value)))
+            }
+
+            #[inline]
+            fn visit_string<E>(&mut self, value: String) -> Result<Self::Value, E> {
+                Ok(Value::String(value))
+            }
+
+            #[inline]
+            fn visit_bytes<E>(&mut self, value: &[u8]) -> Result<Self::Value, E> {
+                Ok(Value::Bytes(value.to_vec()))
+            }
+
+            #[inline]
+            fn visit_byte_buf<E>(&mut self, value: Vec<u8>) -> Result<Self::Value, E> {
+                Ok(Value::Bytes(value))
+            }
+
+            #[inline]
+            fn visit_unit<E>(&mut self) -> Result<Self::Value, E> {
+                Ok(Value::Null)
+            }
+
+            fn visit_none<E>(&mut self) -> Result<Self::Value, E> {
+                Ok(Value::Null)
+            }
+
+            #[inline]
+            fn visit_some<D>(&mut self, des: &mut D) -> Result<Self::Value, D::Error>
+                where D: de::Deserializer
+            {
+                de::Deserialize::deserialize(des).map(|v| Value::Optional(Box::new(v)))
+            }
+        }
+
+        deserializer.visit(ValueVisitor)
+    }
+}
+
+/// Blobs that are not UTF-8 become strings with each byte taken as a
+/// Latin-1 code point, as s2protocol's JSON output does.  Bit arrays become
+/// `[length, [bytes...]]`, choices single-entry objects, and optionals
+/// their contents.
+impl From<Value> for serde_json::Value {
+    fn from(value: Value) -> serde_json::Value {
+        use serde_json::Value as Json;
+
+        match value {
+            Value::I64(val) => Json::I64(val),
+            Value::U64(val) => Json::U64(val),
+            Value::F64(val) => Json::F64(val),
+            Value::Bytes(val) => Json::String(latin1_string(&val)),
+            Value::String(val) => Json::String(val),
+            Value::Array(val) => Json::Array(val.into_iter().map(From::from).collect()),
+            Value::Dict(val) => {
+                Json::Object(val.into_iter().map(|(k, v)| (k, From::from(v))).collect())
+            },
+            Value::Optional(val) => From::from(*val),
+            Value::Boolean(val) => Json::Bool(val),
+            Value::FourCC(val) => Json::String(latin1_string(&val)),
+            Value::BitArray(length, bits) => Json::Array(vec![
+                Json::U64(length as u64),
+                Json::Array(bits.into_iter().map(|b| Json::U64(b as u64)).collect()),
+            ]),
+            Value::AlignedBitArray(length, bits) => Json::Array(vec![
+                Json::U64(length as u64),
+                Json::String(latin1_string(&bits)),
+            ]),
+            Value::Choice(name, val) => {
+                let mut map = BTreeMap::new();
+                map.insert(name, From::from(*val));
+                Json::Object(map)
+            },
+            Value::Null => Json::Null,
+        }
+    }
+}
+
+impl From<serde_json::Value> for Value {
+    fn from(value: serde_json::Value) -> Value {
+        use serde_json::Value as Json;
+
+        match value {
+            Json::Null => Value::Null,
+            Json::Bool(val) => Value::Boolean(val),
+            Json::I64(val) => Value::I64(val),
+            Json::U64(val) => Value::U64(val),
+            Json::F64(val) => Value::F64(val),
+            Json::String(val) => Value::String(val),
+            Json::Array(val) => Value::Array(val.into_iter().map(From::from).collect()),
+            Json::Object(val) => {
+                Value::Dict(val.into_iter().map(|(k, v)| (k, From::from(v))).collect())
+            },
+        }
+    }
+}
+
+fn latin1_string(buf: &[u8]) -> String {
+    buf.iter().map(|&b| b as char).collect()
+}
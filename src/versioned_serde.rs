@@ -3,13 +3,18 @@ use std::{io, result};
 use serde;
 use serde::de;
 
+use std::collections::BTreeMap;
+
 use super::format::{
     TypeInfo,
     TypeId,
     Struct,
     StructField,
     IntBounds,
+    ChoiceTypeMap,
 };
+use super::value::Value;
+use super::transcode;
 
 #[derive(Debug)]
 pub enum ErrorCode {
@@ -64,6 +69,12 @@ impl From<serde::de::value::Error> for Error {
     }
 }
 
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::IoError(e)
+    }
+}
+
 pub type Result<T> = result::Result<T, Error>;
 
 pub struct Deserializer {
@@ -124,6 +135,256 @@ impl Deserializer {
 
         Ok(last_ti)
     }
+
+    fn typeinfo(&self, typeid: TypeId) -> Result<&'static TypeInfo> {
+        match self.typeinfos.get(typeid as usize) {
+            Some(ti) => Ok(ti),
+            None => Err(Error::SyntaxError(ErrorCode::UnexpectedType, self.offset, self.offset)),
+        }
+    }
+
+    fn read_bytes(&mut self, length: usize) -> Result<&[u8]> {
+        let start = self.offset;
+        if self.buffer.len() < start + length {
+            return Err(Error::SyntaxError(ErrorCode::UnexpectedEOF, start, self.buffer.len()));
+        }
+        self.offset += length;
+        Ok(&self.buffer[start..self.offset])
+    }
+
+    /// Decodes the current type into a `Value`, keeping the choice, FourCC
+    /// and bit array distinctions that the serde data model loses.
+    pub fn read_value(&mut self) -> Result<Value> {
+        let typeinfo = try!(self.top_typeinfo());
+        self.read_typeinfo_value(typeinfo)
+    }
+
+    /// Decodes the next value as `typeid`, regardless of the root type.
+    /// Event streams are a sequence of differently typed instances.
+    pub fn read_instance(&mut self, typeid: TypeId) -> Result<Value> {
+        let typeinfo = try!(self.typeinfo(typeid));
+        self.read_typeinfo_value(typeinfo)
+    }
+
+    pub fn done(&self) -> bool {
+        self.buffer.len() <= self.offset
+    }
+
+    pub fn used_bits(&self) -> usize {
+        self.offset * 8
+    }
+
+    fn read_typeinfo_value(&mut self, typeinfo: &'static TypeInfo) -> Result<Value> {
+        match *typeinfo {
+            TypeInfo::Array { typeid, .. } => {
+                try!(self.expect_skip(0));
+                let length: usize = try!(self.parse_vint());
+                let item_ti = try!(self.typeinfo(typeid));
+                let mut out = Vec::new();
+                for _ in 0..length {
+                    out.push(try!(self.read_typeinfo_value(item_ti)));
+                }
+                Ok(Value::Array(out))
+            },
+            TypeInfo::BitArray { .. } => {
+                try!(self.expect_skip(1));
+                let length: usize = try!(self.parse_vint());
+                let bits = try!(self.read_bytes((length + 7) / 8)).to_vec();
+                Ok(Value::AlignedBitArray(length, bits))
+            },
+            TypeInfo::Blob { .. } => {
+                try!(self.expect_skip(2));
+                let length: usize = try!(self.parse_vint());
+                let buf = try!(self.read_bytes(length)).to_vec();
+                Ok(match String::from_utf8(buf) {
+                    Ok(str_val) => Value::String(str_val),
+                    Err(err) => Value::Bytes(err.into_bytes()),
+                })
+            },
+            TypeInfo::Bool => {
+                try!(self.expect_skip(6));
+                Ok(Value::Boolean(try!(self.read_byte()) != 0))
+            },
+            TypeInfo::Choice { ref types, .. } => {
+                try!(self.expect_skip(3));
+                let tag: i64 = try!(self.parse_vint());
+                match types.get(&(tag as u32)) {
+                    Some(&(name, typeid)) => {
+                        let field_ti = try!(self.typeinfo(typeid));
+                        let val = try!(self.read_typeinfo_value(field_ti));
+                        Ok(Value::Choice(name.to_string(), Box::new(val)))
+                    },
+                    None => {
+                        // newer writers may know variants we do not
+                        try!(self.skip_instance());
+                        Ok(Value::Dict(BTreeMap::new()))
+                    }
+                }
+            },
+            TypeInfo::FourCC => {
+                try!(self.expect_skip(7));
+                let buf = try!(self.read_bytes(4));
+                Ok(Value::FourCC([buf[0], buf[1], buf[2], buf[3]]))
+            },
+            TypeInfo::Int { .. } => {
+                try!(self.expect_skip(9));
+                let val: i64 = try!(self.parse_vint());
+                Ok(if val < 0 { Value::I64(val) } else { Value::U64(val as u64) })
+            },
+            TypeInfo::Null => Ok(Value::Null),
+            TypeInfo::Optional { typeid } => {
+                try!(self.expect_skip(4));
+                if try!(self.read_byte()) == 0 {
+                    return Ok(Value::Null);
+                }
+                let inner_ti = try!(self.typeinfo(typeid));
+                let val = try!(self.read_typeinfo_value(inner_ti));
+                Ok(Value::Optional(Box::new(val)))
+            },
+            TypeInfo::Real32 => {
+                try!(self.expect_skip(7));
+                let buf = try!(self.read_bytes(4));
+                let raw = (buf[0] as u32) << 24 | (buf[1] as u32) << 16 |
+                          (buf[2] as u32) << 8 | buf[3] as u32;
+                let val: f32 = unsafe { ::std::mem::transmute(raw) };
+                Ok(Value::F64(val as f64))
+            },
+            TypeInfo::Real64 => {
+                try!(self.expect_skip(8));
+                let buf = try!(self.read_bytes(8));
+                let raw = buf.iter().fold(0u64, |acc, &b| acc << 8 | b as u64);
+                let val: f64 = unsafe { ::std::mem::transmute(raw) };
+                Ok(Value::F64(val))
+            },
+            TypeInfo::Struct(st) => {
+                try!(self.expect_skip(5));
+                let length: usize = try!(self.parse_vint());
+                let mut out = BTreeMap::new();
+                for _ in 0..length {
+                    let tag: i32 = try!(self.parse_vint());
+                    let field = st.fields.iter().find(|&&(_, _, ftag)| ftag == tag);
+                    let (name, typeid, _) = match field {
+                        Some(&field) => field,
+                        None => {
+                            try!(self.skip_instance());
+                            continue;
+                        }
+                    };
+                    let field_ti = try!(self.typeinfo(typeid));
+                    let val = try!(self.read_typeinfo_value(field_ti));
+                    if name != "__parent" {
+                        out.insert(name.to_string(), val);
+                        continue;
+                    }
+                    match val {
+                        Value::Dict(parent) => out.extend(parent.into_iter()),
+                        val => {
+                            if st.fields.len() == 1 {
+                                return Ok(val);
+                            }
+                            out.insert(name.to_string(), val);
+                        }
+                    }
+                }
+                Ok(Value::Dict(out))
+            },
+        }
+    }
+
+    /// Skips over one instance of whatever type comes next.  The versioned
+    /// format is self-describing, so no typeinfo is needed.
+    fn skip_instance(&mut self) -> Result<()> {
+        let skip = try!(self.read_byte());
+        match skip {
+            0 => {
+                let length: usize = try!(self.parse_vint());
+                for _ in 0..length {
+                    try!(self.skip_instance());
+                }
+            },
+            1 => {
+                let length: usize = try!(self.parse_vint());
+                try!(self.read_bytes((length + 7) / 8));
+            },
+            2 => {
+                let length: usize = try!(self.parse_vint());
+                try!(self.read_bytes(length));
+            },
+            3 => {
+                let _tag: i64 = try!(self.parse_vint());
+                try!(self.skip_instance());
+            },
+            4 => {
+                if try!(self.read_byte()) != 0 {
+                    try!(self.skip_instance());
+                }
+            },
+            5 => {
+                let length: usize = try!(self.parse_vint());
+                for _ in 0..length {
+                    let _tag: i64 = try!(self.parse_vint());
+                    try!(self.skip_instance());
+                }
+            },
+            6 => { try!(self.read_byte()); },
+            7 => { try!(self.read_bytes(4)); },
+            8 => { try!(self.read_bytes(8)); },
+            9 => { let _val: i64 = try!(self.parse_vint()); },
+            _ => return Err(Error::SyntaxError(
+                ErrorCode::UnsupportedType(skip), self.offset - 1, self.offset)),
+        }
+        Ok(())
+    }
+}
+
+impl transcode::Source for Deserializer {
+    fn typeinfo(&self, typeid: TypeId) -> Result<&'static TypeInfo> {
+        Deserializer::typeinfo(self, typeid)
+    }
+
+    fn read_array_len(&mut self, _bounds: IntBounds) -> Result<usize> {
+        try!(self.expect_skip(0));
+        self.parse_vint()
+    }
+
+    fn read_choice(&mut self, _bounds: IntBounds, types: &'static ChoiceTypeMap)
+        -> Result<Option<(&'static str, TypeId)>>
+    {
+        try!(self.expect_skip(3));
+        let tag: i64 = try!(self.parse_vint());
+        match types.get(&(tag as u32)) {
+            Some(&variant) => Ok(Some(variant)),
+            None => {
+                try!(self.skip_instance());
+                Ok(None)
+            }
+        }
+    }
+
+    fn read_optional(&mut self) -> Result<bool> {
+        try!(self.expect_skip(4));
+        Ok(try!(self.read_byte()) != 0)
+    }
+
+    fn read_struct_len(&mut self, _def: Struct) -> Result<usize> {
+        try!(self.expect_skip(5));
+        self.parse_vint()
+    }
+
+    fn read_struct_field(&mut self, def: Struct, _idx: usize) -> Result<Option<&'static StructField>> {
+        let tag: i32 = try!(self.parse_vint());
+        match def.fields.iter().find(|&&(_, _, ftag)| ftag == tag) {
+            Some(field) => Ok(Some(field)),
+            None => {
+                try!(self.skip_instance());
+                Ok(None)
+            }
+        }
+    }
+
+    fn read_scalar(&mut self, typeinfo: &'static TypeInfo) -> Result<Value> {
+        self.read_typeinfo_value(typeinfo)
+    }
 }
 
 impl serde::de::Deserializer for Deserializer {
@@ -507,7 +768,7 @@ impl<'a> de::SeqVisitor for ArrayVisitor<'a> {
 }
 
 /// just visits a string
-struct StrVisitor<'a>(&'a str);
+pub struct StrVisitor<'a>(pub &'a str);
 
 impl<'a> de::Deserializer for StrVisitor<'a> {
     type Error = Error;